@@ -57,6 +57,35 @@ pub(crate) fn polynomial_ode_equations(
     Ok(LatexEquations(equations))
 }
 
+/// Symbolic Jacobian matrix of a polynomial ODE system in LaTeX format.
+pub(crate) fn polynomial_ode_jacobian(model: &DblModel) -> Result<LatexEquations, String> {
+    let jacobian = polynomial_ode_system(model)?
+        .jacobian()
+        .map_variables(latex_ob_names(model))
+        .extend_scalars(|param| param.map_variables(latex_mor_names(model)))
+        .to_latex_equations();
+    Ok(LatexEquations(jacobian))
+}
+
+/// Runs a parameter sweep of polynomial ODEs.
+pub(crate) fn polynomial_ode_sweep(
+    model: &DblModel,
+    data: ode::PolynomialODEProblemData,
+    sweep: ode::ParameterSweep,
+) -> Result<Vec<ode::SweepRun>, String> {
+    let sys = polynomial_ode_system(model)?;
+    Ok(ode::polynomial_ode_sweep(&sys, &data, &sweep))
+}
+
+/// Simulates polynomial ODEs along with their sensitivities to coefficients.
+pub(crate) fn polynomial_ode_sensitivities(
+    model: &DblModel,
+    data: ode::PolynomialODEProblemData,
+) -> Result<ode::ODESolutionWithSensitivities<QualifiedName>, String> {
+    let sys = polynomial_ode_system(model)?;
+    ode::polynomial_ode_sensitivities(&sys, &data).map_err(|err| format!("{err:?}"))
+}
+
 /// Simulates mass-action ODEs.
 pub(crate) fn polynomial_ode_simulation(
     model: &DblModel,
@@ -67,7 +96,7 @@ pub(crate) fn polynomial_ode_simulation(
     let latex_equations =
         sys_extended_scalars.map_variables(latex_ob_names(model)).to_latex_equations();
     let analysis = ode::polynomial_ode_analysis(sys_extended_scalars, data);
-    let solution = analysis.solve_with_defaults().map_err(|err| format!("{err:?}"));
+    let solution = analysis.solve().map_err(|err| format!("{err:?}"));
     Ok(ODEResultWithEquations {
         solution: ODEResult(solution.into()),
         latex_equations: LatexEquations(latex_equations),
@@ -137,9 +166,54 @@ pub(crate) fn mass_action_simulation(
     let latex_equations =
         sys_extended_scalars.map_variables(latex_ob_names(model)).to_latex_equations();
     let analysis = ode::into_mass_action_analysis(sys_extended_scalars, data);
-    let solution = analysis.solve_with_defaults().map_err(|err| format!("{err:?}"));
+    let solution = analysis.solve().map_err(|err| format!("{err:?}"));
     Ok(ODEResultWithEquations {
         solution: ODEResult(solution.into()),
         latex_equations: LatexEquations(latex_equations),
     })
 }
+
+/// Finds equilibria of mass-action ODEs.
+pub(crate) fn mass_action_equilibria(
+    model: &DblModel,
+    data: ode::MassActionProblemData,
+    search: ode::EquilibriumData,
+    logic: MassActionAnalysisLogic,
+) -> Result<Vec<ode::Equilibrium>, String> {
+    let sys = mass_action_system(model, data.mass_conservation_type, logic);
+    let sys_extended_scalars = ode::extend_mass_action_scalars(sys?, &data);
+    let analysis = ode::into_mass_action_analysis(sys_extended_scalars, data);
+    Ok(analysis.equilibria(&search))
+}
+
+/// Runs a parameter sweep of mass-action ODEs.
+pub(crate) fn mass_action_sweep(
+    model: &DblModel,
+    data: ode::MassActionProblemData,
+    sweep: ode::ParameterSweep,
+    logic: MassActionAnalysisLogic,
+) -> Result<Vec<ode::SweepRun>, String> {
+    let sys = mass_action_system(model, data.mass_conservation_type, logic)?;
+    Ok(ode::mass_action_sweep(&sys, &data, &sweep))
+}
+
+/// Simulates mass-action ODEs along with their sensitivities to rate coefficients.
+pub(crate) fn mass_action_sensitivities(
+    model: &DblModel,
+    data: ode::MassActionProblemData,
+    logic: MassActionAnalysisLogic,
+) -> Result<ode::ODESolutionWithSensitivities<ode::FlowParameter>, String> {
+    let sys = mass_action_system(model, data.mass_conservation_type, logic)?;
+    ode::mass_action_sensitivities(&sys, &data).map_err(|err| format!("{err:?}"))
+}
+
+/// Estimates rate coefficients of mass-action ODEs from observations.
+pub(crate) fn mass_action_fit(
+    model: &DblModel,
+    data: ode::MassActionProblemData,
+    fit: ode::MassActionFitData,
+    logic: MassActionAnalysisLogic,
+) -> Result<ode::MassActionFit, String> {
+    let sys = mass_action_system(model, data.mass_conservation_type, logic)?;
+    ode::mass_action_fit(&sys, &data, &fit).map_err(|err| err.to_string())
+}
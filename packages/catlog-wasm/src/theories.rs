@@ -9,7 +9,7 @@ use wasm_bindgen::prelude::*;
 use catlog::dbl::theory::{self as theory, NonUnital, Unital};
 use catlog::one::Path;
 use catlog::stdlib::{analyses, models, theories, theory_morphisms};
-use catlog::zero::{QualifiedLabel, name};
+use catlog::zero::{QualifiedLabel, QualifiedName, name};
 
 use super::latex::LatexEquations;
 use super::model_morphism::{MotifOccurrence, MotifsOptions, motifs};
@@ -92,24 +92,64 @@ impl ThSchema {
     /// Renders a model into valid SQL
     #[wasm_bindgen(js_name = "renderSQL")]
     pub fn render_sql(&self, model: &DblModel, backend: &str) -> JsResult<String, String> {
-        analyses::sql::SQLBackend::try_from(backend)
-            .and_then(|backend| {
-                analyses::sql::SQLAnalysis::new(backend).render(
-                    model.discrete()?,
-                    |id| {
-                        model
-                            .ob_generator_label(id)
-                            .unwrap_or_else(|| QualifiedLabel::single("".into()))
-                    },
-                    |id| {
-                        model
-                            .mor_generator_label(id)
-                            .unwrap_or_else(|| QualifiedLabel::single("".into()))
-                    },
-                )
-            })
-            .into()
+        render_sql(model, backend)
     }
+
+    /// Validates that an instance is an instance of a schema.
+    #[wasm_bindgen(js_name = "validateInstance")]
+    pub fn validate_instance(
+        &self,
+        model: &DblModel,
+        instance: analyses::instance::Instance,
+    ) -> Result<JsResult<(), Vec<analyses::instance::InvalidInstance>>, String> {
+        let result = instance.validate_in(model.discrete()?);
+        Ok(result.map_err(|errs| errs.into()).into())
+    }
+}
+
+/// The theory of database schemas with nullable mappings and attributes.
+#[wasm_bindgen]
+pub struct ThNullableSchema(Rc<theory::DiscreteDblTheory>);
+
+#[wasm_bindgen]
+impl ThNullableSchema {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self(Rc::new(theories::th_nullable_schema()))
+    }
+
+    #[wasm_bindgen]
+    pub fn theory(&self) -> DblTheory {
+        DblTheory(self.0.clone().into())
+    }
+
+    /// Renders a model into valid SQL, with nullable columns for nullable
+    /// mappings and attributes.
+    #[wasm_bindgen(js_name = "renderSQL")]
+    pub fn render_sql(&self, model: &DblModel, backend: &str) -> JsResult<String, String> {
+        render_sql(model, backend)
+    }
+}
+
+/// Renders a model of a theory of schemas into SQL for the named backend.
+fn render_sql(model: &DblModel, backend: &str) -> JsResult<String, String> {
+    analyses::sql::SQLBackend::try_from(backend)
+        .and_then(|backend| {
+            analyses::sql::SQLAnalysis::new(backend).render(
+                model.discrete()?,
+                |id| {
+                    model
+                        .ob_generator_label(id)
+                        .unwrap_or_else(|| QualifiedLabel::single("".into()))
+                },
+                |id| {
+                    model
+                        .mor_generator_label(id)
+                        .unwrap_or_else(|| QualifiedLabel::single("".into()))
+                },
+            )
+        })
+        .into()
 }
 
 /// The theory of signed categories.
@@ -162,7 +202,7 @@ impl ThSignedCategory {
                 .add_positive(Path::Id(name("Object")))
                 .add_negative(name("Negative").into())
                 .lotka_volterra_analysis(model.discrete()?, data)
-                .solve_with_defaults()
+                .solve()
                 .map_err(|err| format!("{err:?}"))
                 .into(),
         ))
@@ -180,11 +220,97 @@ impl ThSignedCategory {
                 .add_positive(Path::Id(name("Object")))
                 .add_negative(name("Negative").into())
                 .linear_ode_analysis(model.discrete()?, data)
-                .solve_with_defaults()
+                .solve()
                 .map_err(|err| format!("{err:?}"))
                 .into(),
         ))
     }
+
+    /// Analyzes the linear stability of the Lotka-Volterra system at a point.
+    #[wasm_bindgen(js_name = "lotkaVolterraStability")]
+    pub fn lotka_volterra_stability(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::LotkaVolterraProblemData,
+        point: analyses::ode::LinearStabilityData,
+    ) -> Result<analyses::ode::LinearStability, String> {
+        Ok(analyses::ode::SignedCoefficientBuilder::new(name("Object"))
+            .add_positive(Path::Id(name("Object")))
+            .add_negative(name("Negative").into())
+            .lotka_volterra_analysis(model.discrete()?, data)
+            .linear_stability(&point))
+    }
+
+    /// Analyzes the linear stability of the linear ODE system at a point.
+    #[wasm_bindgen(js_name = "linearODEStability")]
+    pub fn linear_ode_stability(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::LinearODEProblemData,
+        point: analyses::ode::LinearStabilityData,
+    ) -> Result<analyses::ode::LinearStability, String> {
+        Ok(analyses::ode::SignedCoefficientBuilder::new(name("Object"))
+            .add_positive(Path::Id(name("Object")))
+            .add_negative(name("Negative").into())
+            .linear_ode_analysis(model.discrete()?, data)
+            .linear_stability(&point))
+    }
+
+    /// Finds equilibria of the Lotka-Volterra system derived from a model.
+    #[wasm_bindgen(js_name = "lotkaVolterraEquilibria")]
+    pub fn lotka_volterra_equilibria(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::LotkaVolterraProblemData,
+        search: analyses::ode::EquilibriumData,
+    ) -> Result<Vec<analyses::ode::Equilibrium>, String> {
+        Ok(analyses::ode::SignedCoefficientBuilder::new(name("Object"))
+            .add_positive(Path::Id(name("Object")))
+            .add_negative(name("Negative").into())
+            .lotka_volterra_analysis(model.discrete()?, data)
+            .equilibria(&search))
+    }
+
+    /// Runs a parameter sweep of the Lotka-Volterra system derived from a model.
+    #[wasm_bindgen(js_name = "lotkaVolterraSweep")]
+    pub fn lotka_volterra_sweep(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::LotkaVolterraProblemData,
+        sweep: analyses::ode::ParameterSweep,
+    ) -> Result<Vec<analyses::ode::SweepRun>, String> {
+        Ok(analyses::ode::SignedCoefficientBuilder::new(name("Object"))
+            .add_positive(Path::Id(name("Object")))
+            .add_negative(name("Negative").into())
+            .lotka_volterra_sweep(model.discrete()?, &data, &sweep))
+    }
+
+    /// Runs a parameter sweep of the linear ODE system derived from a model.
+    #[wasm_bindgen(js_name = "linearODESweep")]
+    pub fn linear_ode_sweep(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::LinearODEProblemData,
+        sweep: analyses::ode::ParameterSweep,
+    ) -> Result<Vec<analyses::ode::SweepRun>, String> {
+        Ok(analyses::ode::SignedCoefficientBuilder::new(name("Object"))
+            .add_positive(Path::Id(name("Object")))
+            .add_negative(name("Negative").into())
+            .linear_ode_sweep(model.discrete()?, &data, &sweep))
+    }
+
+    /// Computes the subspace of equilibria of the linear ODE system.
+    #[wasm_bindgen(js_name = "linearODEEquilibria")]
+    pub fn linear_ode_equilibria(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::LinearODEProblemData,
+    ) -> Result<analyses::ode::LinearODEEquilibria, String> {
+        Ok(analyses::ode::SignedCoefficientBuilder::new(name("Object"))
+            .add_positive(Path::Id(name("Object")))
+            .add_negative(name("Negative").into())
+            .linear_ode_equilibria(model.discrete()?, data))
+    }
 }
 
 /// The theory of delayable signed categories.
@@ -247,6 +373,38 @@ impl ThDelayableSignedCategory {
         motifs(&delayed_negative_loop, model, options)
     }
 
+    /// Simulates the linear DDE system derived from a model.
+    #[wasm_bindgen(js_name = "linearDDE")]
+    pub fn linear_dde(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::DelayProblemData,
+    ) -> Result<ODEResult, String> {
+        Ok(ODEResult(
+            delayed_coefficient_builder()
+                .linear_dde_analysis(model.discrete()?, data)
+                .solve()
+                .map_err(|err| format!("{err:?}"))
+                .into(),
+        ))
+    }
+
+    /// Simulates the Lotka-Volterra DDE system derived from a model.
+    #[wasm_bindgen(js_name = "lotkaVolterraDDE")]
+    pub fn lotka_volterra_dde(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::DelayProblemData,
+    ) -> Result<ODEResult, String> {
+        Ok(ODEResult(
+            delayed_coefficient_builder()
+                .lotka_volterra_dde_analysis(model.discrete()?, data)
+                .solve()
+                .map_err(|err| format!("{err:?}"))
+                .into(),
+        ))
+    }
+
     /// Sigma migrates a delayable signed category to a signed category.
     #[wasm_bindgen(js_name = "toSignedCategory")]
     pub fn to_signed_category(boxed: &DblModel, th: &DblTheory) -> Result<DblModel, String> {
@@ -260,6 +418,21 @@ impl ThDelayableSignedCategory {
     }
 }
 
+/// Builder for the signed coefficients of a delayable signed category.
+///
+/// The superfluous generators `PositiveSlow` and `NegativeSlow` are included
+/// because the frontend uses them as morphism types.
+fn delayed_coefficient_builder()
+-> analyses::ode::SignedCoefficientBuilder<QualifiedName, catlog::one::QualifiedPath> {
+    analyses::ode::SignedCoefficientBuilder::new(name("Object"))
+        .add_positive(Path::Id(name("Object")))
+        .add_negative(name("Negative").into())
+        .add_delayed_positive(name("Slow").into())
+        .add_delayed_positive(name("PositiveSlow").into())
+        .add_delayed_negative(Path::pair(name("Negative"), name("Slow")))
+        .add_delayed_negative(name("NegativeSlow").into())
+}
+
 /// The theory of nullable signed categories.
 #[wasm_bindgen]
 pub struct ThNullableSignedCategory(Rc<theory::DiscreteDblTheory>);
@@ -329,6 +502,80 @@ impl ThCategoryLinks {
     ) -> Result<LatexEquations, String> {
         mass_action_equations(model, data, MassActionAnalysisLogic::StockFlow)
     }
+
+    /// Finds equilibria of the mass-action ODE system derived from a model.
+    #[wasm_bindgen(js_name = "massActionEquilibria")]
+    pub fn mass_action_equilibria(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+        search: analyses::ode::EquilibriumData,
+    ) -> Result<Vec<analyses::ode::Equilibrium>, String> {
+        mass_action_equilibria(model, data, search, MassActionAnalysisLogic::StockFlow)
+    }
+
+    /// Runs a parameter sweep of the mass-action ODE system derived from a model.
+    #[wasm_bindgen(js_name = "massActionSweep")]
+    pub fn mass_action_sweep(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+        sweep: analyses::ode::ParameterSweep,
+    ) -> Result<Vec<analyses::ode::SweepRun>, String> {
+        mass_action_sweep(model, data, sweep, MassActionAnalysisLogic::StockFlow)
+    }
+
+    /// Simulates the mass-action ODE system along with its sensitivities to rates.
+    #[wasm_bindgen(js_name = "massActionSensitivities")]
+    pub fn mass_action_sensitivities(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+    ) -> Result<analyses::ode::ODESolutionWithSensitivities<analyses::ode::FlowParameter>, String>
+    {
+        mass_action_sensitivities(model, data, MassActionAnalysisLogic::StockFlow)
+    }
+
+    /// Estimates rate coefficients of the mass-action ODE system from observations.
+    #[wasm_bindgen(js_name = "massActionFit")]
+    pub fn mass_action_fit(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+        fit: analyses::ode::MassActionFitData,
+    ) -> Result<analyses::ode::MassActionFit, String> {
+        mass_action_fit(model, data, fit, MassActionAnalysisLogic::StockFlow)
+    }
+
+    /// Simulates the stochastic mass-action system derived from a model.
+    #[wasm_bindgen(js_name = "stochasticMassAction")]
+    pub fn stochastic_mass_action(
+        &self,
+        model: &DblModel,
+        data: analyses::stochastic::StochasticMassActionProblemData,
+    ) -> Result<ODEResult, String> {
+        Ok(ODEResult(
+            analyses::stochastic::StockFlowStochasticMassActionAnalysis::default()
+                .build_stochastic_system(model.discrete_tab()?, data)
+                .and_then(|mut sys| sys.simulate())
+                .map_err(|err| format!("{err:?}"))
+                .into(),
+        ))
+    }
+
+    /// Simulates an ensemble of runs of the stochastic mass-action system.
+    #[wasm_bindgen(js_name = "stochasticMassActionEnsemble")]
+    pub fn stochastic_mass_action_ensemble(
+        &self,
+        model: &DblModel,
+        data: analyses::stochastic::StochasticMassActionProblemData,
+        ensemble: analyses::stochastic::StochasticEnsembleData,
+    ) -> Result<analyses::stochastic::StochasticEnsemble, String> {
+        analyses::stochastic::StockFlowStochasticMassActionAnalysis::default()
+            .build_stochastic_system(model.discrete_tab()?, data)
+            .and_then(|mut sys| sys.simulate_ensemble(&ensemble))
+            .map_err(|err| format!("{err:?}"))
+    }
 }
 
 /// The theory of categories with signed links.
@@ -366,6 +613,80 @@ impl ThCategorySignedLinks {
     ) -> Result<LatexEquations, String> {
         mass_action_equations(model, data, MassActionAnalysisLogic::StockFlow)
     }
+
+    /// Finds equilibria of the mass-action ODE system derived from a model.
+    #[wasm_bindgen(js_name = "massActionEquilibria")]
+    pub fn mass_action_equilibria(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+        search: analyses::ode::EquilibriumData,
+    ) -> Result<Vec<analyses::ode::Equilibrium>, String> {
+        mass_action_equilibria(model, data, search, MassActionAnalysisLogic::StockFlow)
+    }
+
+    /// Runs a parameter sweep of the mass-action ODE system derived from a model.
+    #[wasm_bindgen(js_name = "massActionSweep")]
+    pub fn mass_action_sweep(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+        sweep: analyses::ode::ParameterSweep,
+    ) -> Result<Vec<analyses::ode::SweepRun>, String> {
+        mass_action_sweep(model, data, sweep, MassActionAnalysisLogic::StockFlow)
+    }
+
+    /// Simulates the mass-action ODE system along with its sensitivities to rates.
+    #[wasm_bindgen(js_name = "massActionSensitivities")]
+    pub fn mass_action_sensitivities(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+    ) -> Result<analyses::ode::ODESolutionWithSensitivities<analyses::ode::FlowParameter>, String>
+    {
+        mass_action_sensitivities(model, data, MassActionAnalysisLogic::StockFlow)
+    }
+
+    /// Estimates rate coefficients of the mass-action ODE system from observations.
+    #[wasm_bindgen(js_name = "massActionFit")]
+    pub fn mass_action_fit(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+        fit: analyses::ode::MassActionFitData,
+    ) -> Result<analyses::ode::MassActionFit, String> {
+        mass_action_fit(model, data, fit, MassActionAnalysisLogic::StockFlow)
+    }
+
+    /// Simulates the stochastic mass-action system derived from a model.
+    #[wasm_bindgen(js_name = "stochasticMassAction")]
+    pub fn stochastic_mass_action(
+        &self,
+        model: &DblModel,
+        data: analyses::stochastic::StochasticMassActionProblemData,
+    ) -> Result<ODEResult, String> {
+        Ok(ODEResult(
+            analyses::stochastic::StockFlowStochasticMassActionAnalysis::default()
+                .build_stochastic_system(model.discrete_tab()?, data)
+                .and_then(|mut sys| sys.simulate())
+                .map_err(|err| format!("{err:?}"))
+                .into(),
+        ))
+    }
+
+    /// Simulates an ensemble of runs of the stochastic mass-action system.
+    #[wasm_bindgen(js_name = "stochasticMassActionEnsemble")]
+    pub fn stochastic_mass_action_ensemble(
+        &self,
+        model: &DblModel,
+        data: analyses::stochastic::StochasticMassActionProblemData,
+        ensemble: analyses::stochastic::StochasticEnsembleData,
+    ) -> Result<analyses::stochastic::StochasticEnsemble, String> {
+        analyses::stochastic::StockFlowStochasticMassActionAnalysis::default()
+            .build_stochastic_system(model.discrete_tab()?, data)
+            .and_then(|mut sys| sys.simulate_ensemble(&ensemble))
+            .map_err(|err| format!("{err:?}"))
+    }
 }
 
 /// The theory of strict symmetric monoidal categories.
@@ -404,6 +725,70 @@ impl ThSymMonoidalCategory {
         mass_action_equations(model, data, MassActionAnalysisLogic::PetriNet)
     }
 
+    /// Finds equilibria of the mass-action ODE system derived from a model.
+    #[wasm_bindgen(js_name = "massActionEquilibria")]
+    pub fn mass_action_equilibria(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+        search: analyses::ode::EquilibriumData,
+    ) -> Result<Vec<analyses::ode::Equilibrium>, String> {
+        mass_action_equilibria(model, data, search, MassActionAnalysisLogic::PetriNet)
+    }
+
+    /// Runs a parameter sweep of the mass-action ODE system derived from a model.
+    #[wasm_bindgen(js_name = "massActionSweep")]
+    pub fn mass_action_sweep(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+        sweep: analyses::ode::ParameterSweep,
+    ) -> Result<Vec<analyses::ode::SweepRun>, String> {
+        mass_action_sweep(model, data, sweep, MassActionAnalysisLogic::PetriNet)
+    }
+
+    /// Simulates the mass-action ODE system along with its sensitivities to rates.
+    #[wasm_bindgen(js_name = "massActionSensitivities")]
+    pub fn mass_action_sensitivities(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+    ) -> Result<analyses::ode::ODESolutionWithSensitivities<analyses::ode::FlowParameter>, String>
+    {
+        mass_action_sensitivities(model, data, MassActionAnalysisLogic::PetriNet)
+    }
+
+    /// Estimates rate coefficients of the mass-action ODE system from observations.
+    #[wasm_bindgen(js_name = "massActionFit")]
+    pub fn mass_action_fit(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::MassActionProblemData,
+        fit: analyses::ode::MassActionFitData,
+    ) -> Result<analyses::ode::MassActionFit, String> {
+        mass_action_fit(model, data, fit, MassActionAnalysisLogic::PetriNet)
+    }
+
+    /// Computes the stoichiometric matrix and conservation laws of a model.
+    #[wasm_bindgen(js_name = "conservationLaws")]
+    pub fn conservation_laws(
+        &self,
+        model: &DblModel,
+    ) -> Result<analyses::ode::ConservationLaws, String> {
+        let model = model.modal_unital().map_err(|_| "Model should be of a modal theory")?;
+        Ok(analyses::ode::PetriNetMassActionAnalysis::default().conservation_laws(model))
+    }
+
+    /// Computes the deficiency of the mass-action network of a Petri net.
+    #[wasm_bindgen(js_name = "deficiency")]
+    pub fn deficiency(
+        &self,
+        model: &DblModel,
+    ) -> Result<analyses::ode::DeficiencyAnalysis, String> {
+        let model = model.modal_unital().map_err(|_| "Model should be of a modal theory")?;
+        Ok(analyses::ode::PetriNetMassActionAnalysis::default().deficiency(model))
+    }
+
     /// Simulates the stochastic mass-action system derived from a model.
     #[wasm_bindgen(js_name = "stochasticMassAction")]
     pub fn stochastic_mass_action(
@@ -411,11 +796,27 @@ impl ThSymMonoidalCategory {
         model: &DblModel,
         data: analyses::stochastic::StochasticMassActionProblemData,
     ) -> Result<ODEResult, String> {
-        Ok(ODEResult(JsResult::Ok(
+        Ok(ODEResult(
             analyses::stochastic::PetriNetStochasticMassActionAnalysis::default()
                 .build_stochastic_system(model.modal_unital()?, data)
-                .simulate(),
-        )))
+                .simulate()
+                .map_err(|err| format!("{err:?}"))
+                .into(),
+        ))
+    }
+
+    /// Simulates an ensemble of runs of the stochastic mass-action system.
+    #[wasm_bindgen(js_name = "stochasticMassActionEnsemble")]
+    pub fn stochastic_mass_action_ensemble(
+        &self,
+        model: &DblModel,
+        data: analyses::stochastic::StochasticMassActionProblemData,
+        ensemble: analyses::stochastic::StochasticEnsembleData,
+    ) -> Result<analyses::stochastic::StochasticEnsemble, String> {
+        analyses::stochastic::PetriNetStochasticMassActionAnalysis::default()
+            .build_stochastic_system(model.modal_unital()?, data)
+            .simulate_ensemble(&ensemble)
+            .map_err(|err| format!("{err:?}"))
     }
 
     /// Solve the subreachability problem for petri nets.
@@ -428,6 +829,50 @@ impl ThSymMonoidalCategory {
         let model = model.modal_unital().map_err(|_| "Model should be of a modal theory")?;
         Ok(analyses::reachability::subreachability(model, data))
     }
+
+    /// Find a shortest firing sequence covering the forbidden marking, if any.
+    #[wasm_bindgen(js_name = "subreachabilityWitness")]
+    pub fn subreachability_witness(
+        &self,
+        model: &DblModel,
+        data: analyses::reachability::ReachabilityProblemData,
+    ) -> Result<Option<analyses::reachability::FiringSequence>, String> {
+        let model = model.modal_unital().map_err(|_| "Model should be of a modal theory")?;
+        Ok(analyses::reachability::subreachability_witness(model, data))
+    }
+
+    /// Explores the reachability graph and coverability tree of a Petri net.
+    #[wasm_bindgen(js_name = "stateSpace")]
+    pub fn state_space(
+        &self,
+        model: &DblModel,
+        data: analyses::reachability::StateSpaceData,
+    ) -> Result<analyses::reachability::StateSpace, String> {
+        let model = model.modal_unital().map_err(|_| "Model should be of a modal theory")?;
+        Ok(analyses::reachability::state_space(model, data))
+    }
+
+    /// Computes invariants, siphons, and traps of a Petri net.
+    #[wasm_bindgen(js_name = "structuralAnalysis")]
+    pub fn structural_analysis(
+        &self,
+        model: &DblModel,
+        data: analyses::structural::StructuralAnalysisData,
+    ) -> Result<analyses::structural::StructuralAnalysis, String> {
+        let model = model.modal_unital().map_err(|_| "Model should be of a modal theory")?;
+        Ok(analyses::structural::structural_analysis(model, data))
+    }
+
+    /// Checks a CTL or LTL property of a bounded Petri net.
+    #[wasm_bindgen(js_name = "modelCheck")]
+    pub fn model_check(
+        &self,
+        model: &DblModel,
+        data: analyses::temporal::ModelCheckingData,
+    ) -> Result<analyses::temporal::ModelCheckingResult, String> {
+        let model = model.modal_unital().map_err(|_| "Model should be of a modal theory")?;
+        analyses::temporal::model_check(model, data).map_err(|err| err.to_string())
+    }
 }
 
 /// A theory of systems of polynomial ODEs
@@ -465,6 +910,33 @@ impl ThPolynomialODE {
     ) -> Result<LatexEquations, String> {
         polynomial_ode_equations(model, data)
     }
+
+    /// Returns the entries of the symbolic Jacobian matrix in LaTeX format.
+    #[wasm_bindgen(js_name = "polynomialODEJacobian")]
+    pub fn polynomial_ode_jacobian(&self, model: &DblModel) -> Result<LatexEquations, String> {
+        polynomial_ode_jacobian(model)
+    }
+
+    /// Runs a parameter sweep of the ODE system derived from a model.
+    #[wasm_bindgen(js_name = "polynomialODESweep")]
+    pub fn polynomial_ode_sweep(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::PolynomialODEProblemData,
+        sweep: analyses::ode::ParameterSweep,
+    ) -> Result<Vec<analyses::ode::SweepRun>, String> {
+        polynomial_ode_sweep(model, data, sweep)
+    }
+
+    /// Simulates the ODE system along with its sensitivities to coefficients.
+    #[wasm_bindgen(js_name = "polynomialODESensitivities")]
+    pub fn polynomial_ode_sensitivities(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::PolynomialODEProblemData,
+    ) -> Result<analyses::ode::ODESolutionWithSensitivities<QualifiedName>, String> {
+        polynomial_ode_sensitivities(model, data)
+    }
 }
 
 /// A theory of systems of signed polynomial ODEs
@@ -502,6 +974,33 @@ impl ThSignedPolynomialODE {
     ) -> Result<LatexEquations, String> {
         polynomial_ode_equations(model, data)
     }
+
+    /// Returns the entries of the symbolic Jacobian matrix in LaTeX format.
+    #[wasm_bindgen(js_name = "polynomialODEJacobian")]
+    pub fn polynomial_ode_jacobian(&self, model: &DblModel) -> Result<LatexEquations, String> {
+        polynomial_ode_jacobian(model)
+    }
+
+    /// Runs a parameter sweep of the ODE system derived from a model.
+    #[wasm_bindgen(js_name = "polynomialODESweep")]
+    pub fn polynomial_ode_sweep(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::PolynomialODEProblemData,
+        sweep: analyses::ode::ParameterSweep,
+    ) -> Result<Vec<analyses::ode::SweepRun>, String> {
+        polynomial_ode_sweep(model, data, sweep)
+    }
+
+    /// Simulates the ODE system along with its sensitivities to coefficients.
+    #[wasm_bindgen(js_name = "polynomialODESensitivities")]
+    pub fn polynomial_ode_sensitivities(
+        &self,
+        model: &DblModel,
+        data: analyses::ode::PolynomialODEProblemData,
+    ) -> Result<analyses::ode::ODESolutionWithSensitivities<QualifiedName>, String> {
+        polynomial_ode_sensitivities(model, data)
+    }
 }
 
 /// A theory of power systems.
@@ -533,11 +1032,58 @@ impl ThPowerSystem {
                 .add_link_type(Path::empty(name("Bus")))
                 .add_link_type(Path::single(name("Passive")))
                 .build_system(model.discrete()?, data)
-                .solve_with_defaults()
+                .solve()
                 .map_err(|err| format!("{err:?}"))
                 .into(),
         ))
     }
+
+    /// Simulates the Kuramoto system and computes its synchronization metrics.
+    #[wasm_bindgen(js_name = "kuramotoSynchronization")]
+    pub fn kuramoto_synchronization(
+        &self,
+        model: &DblModel,
+        data: &analyses::ode::KuramotoProblemData,
+    ) -> Result<analyses::ode::KuramotoSolution, String> {
+        analyses::ode::KuramotoAnalysis::new(name("Bus"))
+            .add_link_type(Path::empty(name("Bus")))
+            .add_link_type(Path::single(name("Passive")))
+            .build_system(model.discrete()?, data)
+            .solve_with_synchronization()
+            .map_err(|err| format!("{err:?}"))
+    }
+
+    /// Solves the DC power flow of a model.
+    #[wasm_bindgen(js_name = "powerFlow")]
+    pub fn power_flow(
+        &self,
+        model: &DblModel,
+        data: analyses::power_flow::PowerFlowData,
+    ) -> Result<analyses::power_flow::PowerFlow, String> {
+        power_flow_analysis()
+            .power_flow(model.discrete()?, &data)
+            .map_err(|err| format!("{err:?}"))
+    }
+
+    /// Solves the DC power flow of a model with each branch removed in turn.
+    #[wasm_bindgen(js_name = "contingencyAnalysis")]
+    pub fn contingency_analysis(
+        &self,
+        model: &DblModel,
+        data: analyses::power_flow::PowerFlowData,
+    ) -> Result<analyses::power_flow::ContingencyAnalysis, String> {
+        power_flow_analysis()
+            .contingencies(model.discrete()?, &data)
+            .map_err(|err| format!("{err:?}"))
+    }
+}
+
+/// DC power-flow analysis of a power system, whose branches are lines and
+/// passive branches.
+fn power_flow_analysis() -> analyses::power_flow::PowerFlowAnalysis {
+    analyses::power_flow::PowerFlowAnalysis::new(name("Bus"))
+        .add_branch_type(Path::empty(name("Bus")))
+        .add_branch_type(Path::single(name("Passive")))
 }
 
 #[cfg(test)]
@@ -556,6 +1102,15 @@ mod tests {
         assert_eq!(th.tgt(attr), Ok(attr_type));
     }
 
+    #[test]
+    fn nullable_schema_theory() {
+        let th = ThNullableSchema::new().theory();
+        let entity = ObType::Basic(ustr("Entity"));
+        let nullable = MorType::Basic(ustr("Nullable"));
+        assert_eq!(th.src(nullable.clone()), Ok(entity.clone()));
+        assert_eq!(th.tgt(nullable), Ok(entity));
+    }
+
     #[test]
     fn discrete_tab_theory() {
         let th = ThCategoryLinks::new().theory();
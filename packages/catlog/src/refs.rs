@@ -107,6 +107,15 @@ pub const StockFlow: () = ();
 /// - [arXiv:1302.6946](https://arxiv.org/abs/1302.6946)
 pub const CTForTheSciences: () = ();
 
+/// Reference: *Functorial data migration*.
+///
+/// David I. Spivak, 2012. *Functorial data migration*. Information and
+/// Computation 217.
+///
+/// - [DOI:10.1016/j.ic.2012.05.001](https://doi.org/10.1016/j.ic.2012.05.001)
+/// - [arXiv:1009.1166](https://arxiv.org/abs/1009.1166)
+pub const FunctorialDataMigration: () = ();
+
 /// Reference: *Ideals, varieties, and algorithms*.
 ///
 /// David A. Cox, John B. Little, Don O'Shea, 2015. *Ideals, varieties, and
@@ -132,6 +141,15 @@ pub const KockTrees: () = ();
 /// - [arXiv:1407.3744](https://arxiv.org/abs/1407.3744)
 pub const KockGraphs: () = ();
 
+/// Reference: Synchronization in complex oscillator networks and smart grids.
+///
+/// Florian Dörfler, Michael Chertkov, Francesco Bullo, 2013: Synchronization in
+/// complex oscillator networks and smart grids.
+///
+/// - [DOI:10.1073/pnas.1212134110](https://doi.org/10.1073/pnas.1212134110)
+/// - [arXiv:1208.0045](https://arxiv.org/abs/1208.0045)
+pub const DorflerSmartGrids: () = ();
+
 /// Reference: Handbook of Model Checking.
 ///
 /// Edmund M. Clarke, Thomas A. Henzinger, Helmut Veith, Roderick Bloem, 2018.
@@ -0,0 +1,312 @@
+//! Simulation of dynamical systems defined by delay differential equations.
+//!
+//! A delay differential equation (DDE) is like an ODE except that the vector
+//! field can depend on the state at finitely many earlier times `t - τ`, for
+//! fixed delays `τ > 0`. DDEs are solved here by the *method of steps*: on any
+//! interval no longer than the smallest delay, the delayed states are already
+//! known, so the DDE reduces to an ODE that is solved by a Runge-Kutta method.
+//! The delayed states are read off a history of the solution, interpolated by
+//! cubic Hermite splines.
+
+use std::ops::Sub;
+
+use nalgebra::DVector;
+use num_traits::{One, Pow, ToPrimitive, Zero};
+use ode_solvers::{
+    self,
+    dop_shared::{IntegrationError, SolverResult},
+};
+
+use crate::zero::alg::Polynomial;
+
+/// A system of delay differential equations (DDEs).
+pub trait DDESystem {
+    /// The delays of the system, all positive.
+    fn delays(&self) -> &[f32];
+
+    /// Compute the vector field in place, given the state at the current time
+    /// and the delayed states, one for each [delay](Self::delays).
+    fn vector_field(
+        &self,
+        dx: &mut DVector<f32>,
+        x: &DVector<f32>,
+        delayed: &[DVector<f32>],
+        t: f32,
+    );
+}
+
+/// A DDE problem ready to be solved.
+///
+/// Besides the [DDE system](DDESystem) and the time span, the problem comprises
+/// the initial values, which are taken to be the constant history of the state
+/// before the start time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DDEProblem<Sys> {
+    pub(crate) system: Sys,
+    pub(crate) initial_values: DVector<f32>,
+    pub(crate) start_time: f32,
+    pub(crate) end_time: f32,
+}
+
+impl<Sys> DDEProblem<Sys> {
+    /// Creates a new DDE problem.
+    pub fn new(system: Sys, initial_values: DVector<f32>) -> Self {
+        DDEProblem {
+            system,
+            initial_values,
+            start_time: 0.0,
+            end_time: 0.0,
+        }
+    }
+
+    /// Sets the start time for the problem.
+    pub fn start_time(mut self, t: f32) -> Self {
+        self.start_time = t;
+        self
+    }
+
+    /// Sets the end time for the problem.
+    pub fn end_time(mut self, t: f32) -> Self {
+        self.end_time = t;
+        self
+    }
+}
+
+impl<Sys: DDESystem> DDEProblem<Sys> {
+    /// Solves the DDE system by the method of steps using the Runge-Kutta method.
+    ///
+    /// The step size is reduced if necessary so that each step of the method
+    /// of steps, whose length is the smallest delay, is a whole number of steps.
+    /// Delays shorter than the step size are not resolved: each step of the
+    /// method of steps spans at least one step of the solver, within which the
+    /// delayed states beyond the history are taken to be the latest state.
+    pub fn solve_rk4(
+        &self,
+        step_size: f32,
+    ) -> Result<SolverResult<f32, DVector<f32>>, IntegrationError> {
+        let mut history = History::new(self.initial_values.clone(), self.start_time);
+        let mut dx = self.initial_values.clone();
+        self.eval_vector_field(&history, &mut dx, &self.initial_values, self.start_time);
+        history.derivatives.push(dx);
+
+        let min_delay = self.system.delays().iter().copied().fold(f32::INFINITY, f32::min);
+        let segment = min_delay.max(step_size);
+        let mut t = self.start_time;
+        while t < self.end_time {
+            let t_next = (t + segment).min(self.end_time);
+            if t_next <= t {
+                return Err(IntegrationError::StepSizeUnderflow { x: t.into() });
+            }
+            let num_steps = ((t_next - t) / step_size).ceil().max(1.0);
+            // Enlarge the step slightly so that rounding cannot add a step.
+            let h = (t_next - t) / num_steps * (1.0 + 4.0 * f32::EPSILON);
+            let x = history.states.last().unwrap().clone();
+            let mut stepper = ode_solvers::Rk4::new(
+                MethodOfSteps { problem: self, history: &history },
+                t,
+                x,
+                t_next,
+                h,
+            );
+            stepper.integrate()?;
+
+            let (t_out, x_out) = stepper.results().get();
+            let mut steps: Vec<_> = t_out.iter().copied().zip(x_out.iter().cloned()).collect();
+            if let Some(last) = steps.last_mut() {
+                last.0 = t_next;
+            }
+            for (s, x) in steps.into_iter().skip(1) {
+                let mut dx = x.clone();
+                self.eval_vector_field(&history, &mut dx, &x, s);
+                history.times.push(s);
+                history.states.push(x);
+                history.derivatives.push(dx);
+            }
+            t = t_next;
+        }
+
+        Ok(SolverResult::new(history.times, history.states))
+    }
+
+    /// Evaluates the vector field, reading the delayed states off the history.
+    fn eval_vector_field(
+        &self,
+        history: &History,
+        dx: &mut DVector<f32>,
+        x: &DVector<f32>,
+        t: f32,
+    ) {
+        let delayed: Vec<_> =
+            self.system.delays().iter().map(|tau| history.eval(t - tau)).collect();
+        self.system.vector_field(dx, x, &delayed, t);
+    }
+}
+
+/// History of a solution to a DDE, for interpolation.
+struct History {
+    times: Vec<f32>,
+    states: Vec<DVector<f32>>,
+    derivatives: Vec<DVector<f32>>,
+}
+
+impl History {
+    fn new(x0: DVector<f32>, t0: f32) -> Self {
+        Self {
+            times: vec![t0],
+            states: vec![x0],
+            derivatives: Vec::new(),
+        }
+    }
+
+    /// Evaluates the history at a time by cubic Hermite interpolation.
+    ///
+    /// Before the start time, the history is constant.
+    fn eval(&self, t: f32) -> DVector<f32> {
+        let k = self.times.partition_point(|s| *s <= t);
+        if k == 0 {
+            return self.states[0].clone();
+        }
+        if k == self.times.len() {
+            return self.states[k - 1].clone();
+        }
+        let (t0, t1) = (self.times[k - 1], self.times[k]);
+        let h = t1 - t0;
+        let s = (t - t0) / h;
+        let h00 = (1.0 + 2.0 * s) * (1.0 - s) * (1.0 - s);
+        let h10 = s * (1.0 - s) * (1.0 - s);
+        let h01 = s * s * (3.0 - 2.0 * s);
+        let h11 = s * s * (s - 1.0);
+        &self.states[k - 1] * h00
+            + &self.derivatives[k - 1] * (h10 * h)
+            + &self.states[k] * h01
+            + &self.derivatives[k] * (h11 * h)
+    }
+}
+
+/// The ODE solved on a single step of the method of steps.
+struct MethodOfSteps<'a, Sys> {
+    problem: &'a DDEProblem<Sys>,
+    history: &'a History,
+}
+
+impl<Sys: DDESystem> ode_solvers::dop_shared::System<f32, DVector<f32>> for MethodOfSteps<'_, Sys> {
+    fn system(&self, x: f32, y: &DVector<f32>, dy: &mut DVector<f32>) {
+        self.problem.eval_vector_field(self.history, dy, y, x);
+    }
+}
+
+/// A variable in a polynomial DDE system: a state variable, possibly delayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DelayedVar {
+    /// Index of the state variable.
+    pub var: usize,
+
+    /// Index of the delay, or `None` for the current state.
+    pub delay: Option<usize>,
+}
+
+impl DelayedVar {
+    /// The state variable at the current time.
+    pub fn current(var: usize) -> Self {
+        Self { var, delay: None }
+    }
+
+    /// The state variable delayed by the delay with the given index.
+    pub fn delayed(var: usize, delay: usize) -> Self {
+        Self { var, delay: Some(delay) }
+    }
+}
+
+/// A numerical system of polynomial delay differential equations.
+pub struct PolynomialDDESystem<Exp> {
+    /// Components of the vector field, polynomials in possibly delayed variables.
+    pub components: Vec<Polynomial<DelayedVar, f32, Exp>>,
+
+    /// Delays of the system, indexed by the delayed variables.
+    pub delays: Vec<f32>,
+}
+
+impl<Exp> DDESystem for PolynomialDDESystem<Exp>
+where
+    Exp: Clone + Ord + Zero + One + Sub<Output = Exp> + ToPrimitive,
+    f32: Pow<Exp, Output = f32>,
+{
+    fn delays(&self) -> &[f32] {
+        &self.delays
+    }
+
+    fn vector_field(
+        &self,
+        dx: &mut DVector<f32>,
+        x: &DVector<f32>,
+        delayed: &[DVector<f32>],
+        _t: f32,
+    ) {
+        for i in 0..dx.len() {
+            dx[i] = self.components[i].eval(|var| match var.delay {
+                None => x[var.var],
+                Some(k) => delayed[k][var.var],
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delayed_decay() {
+        // dx/dt = -x(t - 1) with x = 1 for t <= 0. On [0,1], x = 1 - t, and on
+        // [1,2], x = 1 - t + (t - 1)^2 / 2.
+        let sys = PolynomialDDESystem::<u8> {
+            components: vec![-Polynomial::generator(DelayedVar::delayed(0, 0))],
+            delays: vec![1.0],
+        };
+        let problem = DDEProblem::new(sys, DVector::from_element(1, 1.0)).end_time(2.0);
+        let result = problem.solve_rk4(0.1).unwrap();
+        let (t_out, x_out) = result.get();
+        assert_eq!(t_out.last(), Some(&2.0));
+        assert_eq!(t_out.len(), 21);
+
+        let exact = |t: f32| {
+            if t <= 1.0 {
+                1.0 - t
+            } else {
+                1.0 - t + (t - 1.0).powi(2) / 2.0
+            }
+        };
+        for (t, x) in t_out.iter().zip(x_out) {
+            assert!((x[0] - exact(*t)).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn without_delays() {
+        // Without delays, the problem is just an ODE: dx/dt = -x.
+        let sys = PolynomialDDESystem::<u8> {
+            components: vec![-Polynomial::generator(DelayedVar::current(0))],
+            delays: vec![],
+        };
+        let problem = DDEProblem::new(sys, DVector::from_element(1, 1.0)).end_time(1.0);
+        let result = problem.solve_rk4(0.01).unwrap();
+        let (t_out, x_out) = result.get();
+        assert_eq!(t_out.len(), 101);
+        assert!((x_out.last().unwrap()[0] - (-1f32).exp()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn tiny_delay() {
+        // A delay far below the step size acts like no delay: dx/dt = -x.
+        let sys = PolynomialDDESystem::<u8> {
+            components: vec![-Polynomial::generator(DelayedVar::delayed(0, 0))],
+            delays: vec![1e-9],
+        };
+        let problem = DDEProblem::new(sys, DVector::from_element(1, 1.0)).end_time(1.0);
+        let result = problem.solve_rk4(0.01).unwrap();
+        let (t_out, x_out) = result.get();
+        assert_eq!(t_out.last(), Some(&1.0));
+        assert!(t_out.len() <= 201);
+        assert!((x_out.last().unwrap()[0] - (-1f32).exp()).abs() < 1e-2);
+    }
+}
@@ -0,0 +1,134 @@
+//! Equilibria of ODE systems.
+//!
+//! An equilibrium, or fixed point, of an ODE system is a state at which the
+//! vector field vanishes. This module finds equilibria numerically by Newton's
+//! method, using the Jacobian of the [system](ODESystem), and for linear systems
+//! in closed form as the null space of the coefficient matrix.
+
+use nalgebra::{DMatrix, DVector};
+
+use super::ODESystem;
+
+/// Options for Newton's method.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NewtonOptions {
+    /// Tolerance on the norm of the vector field, relative to the norm of the
+    /// state plus one.
+    pub tolerance: f32,
+
+    /// Maximum number of iterations before giving up.
+    pub max_iters: usize,
+}
+
+impl Default for NewtonOptions {
+    fn default() -> Self {
+        Self { tolerance: 1e-5, max_iters: 100 }
+    }
+}
+
+/// Finds an equilibrium of an ODE system by Newton's method.
+///
+/// The Newton step is computed by least squares, so that the method still makes
+/// progress when the Jacobian is singular, as it is whenever the system has a
+/// conserved quantity. Steps are damped by backtracking to avoid overshooting.
+/// Returns `None` if the method fails to converge from the given starting point.
+pub fn newton_equilibrium<Sys: ODESystem>(
+    system: &Sys,
+    x0: DVector<f32>,
+    t: f32,
+    options: NewtonOptions,
+) -> Option<DVector<f32>> {
+    let mut x = x0;
+    let mut fx = system.eval_vector_field(&x, t);
+    for _ in 0..options.max_iters {
+        if fx.norm() <= options.tolerance * (1.0 + x.norm()) {
+            return Some(x);
+        }
+        let svd = system.jacobian(&x, t).svd(true, true);
+        let eps = svd.singular_values.max() * f32::EPSILON.sqrt();
+        let step = svd.solve(&fx, eps).ok()?;
+
+        let mut lambda = 1.0;
+        let (x_new, fx_new) = loop {
+            let x_new = &x - &step * lambda;
+            let fx_new = system.eval_vector_field(&x_new, t);
+            if fx_new.norm() < fx.norm() || lambda < 1e-3 {
+                break (x_new, fx_new);
+            }
+            lambda *= 0.5;
+        };
+        if !x_new.iter().all(|v| v.is_finite()) {
+            return None;
+        }
+        (x, fx) = (x_new, fx_new);
+    }
+    (fx.norm() <= options.tolerance * (1.0 + x.norm())).then_some(x)
+}
+
+/// Computes an orthonormal basis of the null space of a matrix.
+///
+/// The equilibria of the linear system `dx/dt = A x` are exactly the null space
+/// of `A`. Singular values below `tolerance`, relative to the largest singular
+/// value, are treated as zero. Each basis vector is normalized so that its
+/// component of largest magnitude is positive.
+pub fn null_space(matrix: &DMatrix<f32>, tolerance: f32) -> Vec<DVector<f32>> {
+    let n = matrix.ncols();
+    if matrix.nrows() == 0 || n == 0 {
+        return (0..n)
+            .map(|j| DVector::from_fn(n, |i, _| if i == j { 1.0 } else { 0.0 }))
+            .collect();
+    }
+
+    // Pad to a square matrix so that the SVD has a full set of right singular
+    // vectors.
+    let mut square = DMatrix::zeros(matrix.nrows().max(n), n);
+    square.rows_mut(0, matrix.nrows()).copy_from(matrix);
+    let svd = square.svd(false, true);
+    let v_t = svd.v_t.expect("SVD should compute right singular vectors");
+    let threshold = tolerance * svd.singular_values.max().max(f32::MIN_POSITIVE);
+
+    svd.singular_values
+        .iter()
+        .enumerate()
+        .filter(|(_, sigma)| **sigma <= threshold)
+        .map(|(i, _)| {
+            let v: DVector<f32> = v_t.row(i).transpose();
+            let i_max = v.iamax();
+            if v[i_max] < 0.0 { -v } else { v }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::PolynomialSystem;
+    use super::*;
+    use crate::zero::alg::Polynomial;
+
+    #[test]
+    fn newton_quadratic() {
+        let x = Polynomial::<_, f32, u8>::generator('x');
+        let sys: PolynomialSystem<_, _, _> = [('x', x.clone() * x + (-2.0))].into_iter().collect();
+        let sys = sys.to_numerical();
+
+        let start = DVector::from_element(1, 1.0);
+        let eq = newton_equilibrium(&sys, start, 0.0, Default::default()).unwrap();
+        assert!((eq[0] - 2f32.sqrt()).abs() < 1e-5);
+
+        let start = DVector::from_element(1, -5.0);
+        let eq = newton_equilibrium(&sys, start, 0.0, Default::default()).unwrap();
+        assert!((eq[0] + 2f32.sqrt()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn null_space_rank_deficient() {
+        let a = DMatrix::from_row_slice(2, 3, &[1.0, 0.0, -1.0, 0.0, 1.0, -1.0]);
+        let basis = null_space(&a, 1e-5);
+        assert_eq!(basis.len(), 1);
+        let expected = DVector::from_element(3, 1.0 / 3f32.sqrt());
+        assert!((&basis[0] - expected).norm() < 1e-5);
+
+        let a = DMatrix::<f32>::identity(2, 2);
+        assert!(null_space(&a, 1e-5).is_empty());
+    }
+}
@@ -0,0 +1,490 @@
+//! Events and discontinuities in the simulation of ODE systems.
+//!
+//! An *event* occurs when a condition, a polynomial function of the state
+//! variables, crosses zero in a given direction. An event can terminate the
+//! simulation or change the state discontinuously, after which integration is
+//! restarted from the new state. The solver is stopped at the first output at
+//! which a condition has crossed zero, and the time of the event is then located
+//! by bisection on a cubic Hermite interpolant between consecutive outputs.
+
+use nalgebra::DVector;
+use ode_solvers::dop_shared::{IntegrationError, SolverResult, Stats, System};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::polynomial::eval_numerical;
+use super::{ODEProblem, ODEScalar, ODESolverOutput, ODESystem, cast_scalar};
+use crate::zero::alg::Polynomial;
+
+/// Direction in which the condition of an event crosses zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum EventDirection {
+    /// The condition goes from negative to nonnegative.
+    Rising,
+
+    /// The condition goes from positive to nonpositive.
+    Falling,
+
+    /// The condition crosses zero in either direction.
+    Either,
+}
+
+impl EventDirection {
+    /// Whether the condition crosses zero between two of its values.
+    fn is_crossing<T: ODEScalar>(self, before: T, after: T) -> bool {
+        let rising = before < T::zero() && after >= T::zero();
+        let falling = before > T::zero() && after <= T::zero();
+        match self {
+            EventDirection::Rising => rising,
+            EventDirection::Falling => falling,
+            EventDirection::Either => rising || falling,
+        }
+    }
+}
+
+/// Action taken when an event occurs.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "tag"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum EventAction<Var = usize> {
+    /// Records the time of the event without changing the state.
+    Record,
+
+    /// Terminates the simulation.
+    Terminate,
+
+    /// Sets a variable to a value.
+    Set {
+        /// The variable to set.
+        var: Var,
+        /// The new value of the variable.
+        value: f32,
+    },
+
+    /// Multiplies a variable by a factor.
+    Scale {
+        /// The variable to scale.
+        var: Var,
+        /// The factor by which to multiply the variable.
+        factor: f32,
+    },
+}
+
+/// An event in an ODE problem.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ODEEvent {
+    /// Condition of the event, a polynomial in the indices of state variables.
+    pub condition: Polynomial<usize, f32, i8>,
+
+    /// Direction in which the condition must cross zero.
+    pub direction: EventDirection,
+
+    /// Action taken when the event occurs.
+    pub action: EventAction,
+}
+
+impl ODEEvent {
+    /// Evaluates the condition of the event at a state.
+    pub fn eval_condition<T: ODEScalar>(&self, x: &DVector<T>) -> T {
+        eval_numerical(&self.condition, x)
+    }
+
+    /// Applies the action of the event to a state, unless it terminates.
+    fn apply<T: ODEScalar>(&self, x: &mut DVector<T>) {
+        match self.action {
+            EventAction::Set { var, value } => x[var] = cast_scalar(value),
+            EventAction::Scale { var, factor } => x[var] *= cast_scalar::<f32, T>(factor),
+            EventAction::Record | EventAction::Terminate => {}
+        }
+    }
+}
+
+/// An occurrence of an event during a simulation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct EventOccurrence {
+    /// Time at which the event occurred.
+    pub time: f32,
+
+    /// Index of the event in the problem.
+    pub event: usize,
+}
+
+/// Maximum number of bisections used to locate an event.
+const MAX_BISECTIONS: usize = 50;
+
+/// Times and states output by a solver along with its statistics.
+type SegmentOutput<T> = (SolverResult<T, DVector<T>>, Stats);
+
+/// A segment of an ODE problem between events.
+///
+/// As a system for the solvers of `ode_solvers`, the segment stops integration
+/// at the first output at which the condition of an event has crossed zero, so
+/// that the solver never integrates past an event.
+pub(super) struct Segment<'a, Sys, T> {
+    pub(super) problem: ODEProblem<&'a Sys, T>,
+    events: &'a [ODEEvent],
+    last: (T, Vec<T>),
+    crossed: bool,
+}
+
+impl<'a, Sys, T: ODEScalar> Segment<'a, Sys, T> {
+    fn new(problem: ODEProblem<&'a Sys, T>, events: &'a [ODEEvent]) -> Self {
+        let conditions = events.iter().map(|e| e.eval_condition(&problem.initial_values));
+        let last = (problem.start_time, conditions.collect());
+        Self { problem, events, last, crossed: false }
+    }
+
+    /// Whether an event has occurred by the given output of the solver.
+    pub(super) fn has_event(&mut self, t: T, x: &DVector<T>) -> bool {
+        if self.crossed || t == self.last.0 {
+            return self.crossed;
+        }
+        let after: Vec<_> = self.events.iter().map(|e| e.eval_condition(x)).collect();
+        self.crossed = self
+            .events
+            .iter()
+            .enumerate()
+            .any(|(i, event)| event.direction.is_crossing(self.last.1[i], after[i]));
+        self.last = (t, after);
+        self.crossed
+    }
+}
+
+impl<Sys: ODESystem<T>, T: ODEScalar> System<T, DVector<T>> for &mut Segment<'_, Sys, T> {
+    fn system(&self, t: T, x: &DVector<T>, dx: &mut DVector<T>) {
+        self.problem.system.vector_field(dx, x, t);
+    }
+
+    fn solout(&mut self, t: T, x: &DVector<T>, _dx: &DVector<T>) -> bool {
+        self.has_event(t, x)
+    }
+}
+
+impl<Sys: ODESystem<T>, T: ODEScalar> ODEProblem<Sys, T> {
+    /// Solves the problem between events, restarting after each event.
+    ///
+    /// The given solver is called on a [segment](Segment) of the problem
+    /// without events, starting from the initial values and then from the state
+    /// after each event, and should stop once the segment reports an event.
+    pub(super) fn solve_with_events<F>(
+        &self,
+        mut solve: F,
+    ) -> Result<ODESolverOutput<T>, IntegrationError>
+    where
+        F: FnMut(&mut Segment<Sys, T>) -> Result<SegmentOutput<T>, IntegrationError>,
+    {
+        let (mut times, mut states) = (Vec::new(), Vec::new());
+        let mut occurrences = Vec::new();
+        let mut stats = Stats {
+            num_eval: 0,
+            accepted_steps: 0,
+            rejected_steps: 0,
+        };
+        let (mut t, mut x) = (self.start_time, self.initial_values.clone());
+        loop {
+            let problem = ODEProblem {
+                system: &self.system,
+                initial_values: x,
+                start_time: t,
+                end_time: self.end_time,
+                rtol: self.rtol,
+                atol: self.atol,
+                max_steps: self.max_steps,
+                max_events: self.max_events,
+                events: Vec::new(),
+            };
+            let (result, segment_stats) = solve(&mut Segment::new(problem, &self.events))?;
+            stats.num_eval += segment_stats.num_eval;
+            stats.accepted_steps += segment_stats.accepted_steps;
+            stats.rejected_steps += segment_stats.rejected_steps;
+            let (t_out, x_out) = result.get();
+
+            // After an event, the first output repeats the last state.
+            let skip = usize::from(!times.is_empty());
+            let Some((k, index, t_event, x_event)) = self.find_event(t_out, x_out) else {
+                times.extend(t_out.iter().skip(skip));
+                states.extend(x_out.iter().skip(skip).cloned());
+                break;
+            };
+            times.extend(t_out.iter().take(k + 1).skip(skip));
+            states.extend(x_out.iter().take(k + 1).skip(skip).cloned());
+            times.push(t_event);
+            states.push(x_event.clone());
+            occurrences.push(EventOccurrence { time: cast_scalar(t_event), event: index });
+
+            let event = &self.events[index];
+            if event.action == EventAction::Terminate {
+                break;
+            }
+            if occurrences.len() > self.max_events as usize {
+                return Err(IntegrationError::MaxNumStepReached {
+                    x: nalgebra::convert(t_event),
+                    n_step: self.max_events,
+                });
+            }
+            let mut x_next = x_event;
+            event.apply(&mut x_next);
+            if event.action != EventAction::Record {
+                times.push(t_event);
+                states.push(x_next.clone());
+            }
+            if t_event >= self.end_time {
+                break;
+            }
+            (t, x) = (t_event, x_next);
+        }
+        Ok(ODESolverOutput {
+            result: SolverResult::new(times, states),
+            events: occurrences,
+            stats,
+        })
+    }
+
+    /// Finds the earliest event between consecutive outputs of a solver.
+    ///
+    /// Returns the index of the output preceding the event, the index of the
+    /// event, and the time and state at which it occurs.
+    fn find_event(
+        &self,
+        t_out: &[T],
+        x_out: &[DVector<T>],
+    ) -> Option<(usize, usize, T, DVector<T>)> {
+        if self.events.is_empty() || x_out.is_empty() {
+            return None;
+        }
+        let conditions = |x: &DVector<T>| -> Vec<T> {
+            self.events.iter().map(|e| e.eval_condition(x)).collect()
+        };
+        let mut before = conditions(&x_out[0]);
+        for k in 0..(x_out.len() - 1) {
+            let after = conditions(&x_out[k + 1]);
+            let mut crossing = self
+                .events
+                .iter()
+                .enumerate()
+                .filter(|(i, event)| event.direction.is_crossing(before[*i], after[*i]))
+                .peekable();
+            if crossing.peek().is_some() {
+                let interp = HermiteInterpolant::new(
+                    &self.system,
+                    (t_out[k], &x_out[k]),
+                    (t_out[k + 1], &x_out[k + 1]),
+                );
+                return crossing
+                    .map(|(i, event)| {
+                        let (t, x) = interp.locate(event, before[i]);
+                        (k, i, t, x)
+                    })
+                    .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal));
+            }
+            before = after;
+        }
+        None
+    }
+}
+
+/// Cubic Hermite interpolant of the solution between two times.
+struct HermiteInterpolant<'a, T: ODEScalar> {
+    t0: T,
+    t1: T,
+    x0: &'a DVector<T>,
+    x1: &'a DVector<T>,
+    dx0: DVector<T>,
+    dx1: DVector<T>,
+}
+
+impl<'a, T: ODEScalar> HermiteInterpolant<'a, T> {
+    fn new<Sys: ODESystem<T>>(
+        system: &Sys,
+        (t0, x0): (T, &'a DVector<T>),
+        (t1, x1): (T, &'a DVector<T>),
+    ) -> Self {
+        let dx0 = system.eval_vector_field(x0, t0);
+        let dx1 = system.eval_vector_field(x1, t1);
+        Self { t0, t1, x0, x1, dx0, dx1 }
+    }
+
+    fn eval(&self, t: T) -> DVector<T> {
+        let (one, two, three) =
+            (T::one(), nalgebra::convert::<f64, T>(2.0), nalgebra::convert::<f64, T>(3.0));
+        let h = self.t1 - self.t0;
+        let s = (t - self.t0) / h;
+        let h00 = (one + two * s) * (one - s) * (one - s);
+        let h10 = s * (one - s) * (one - s);
+        let h01 = s * s * (three - two * s);
+        let h11 = s * s * (s - one);
+        self.x0 * h00 + &self.dx0 * (h10 * h) + self.x1 * h01 + &self.dx1 * (h11 * h)
+    }
+
+    /// Locates the time at which the condition of an event crosses zero.
+    ///
+    /// Returns the earliest time found at which the condition has crossed,
+    /// so that the event does not occur again immediately after a restart.
+    fn locate(&self, event: &ODEEvent, mut before: T) -> (T, DVector<T>) {
+        let (mut lo, mut hi) = (self.t0, self.t1);
+        let mut x_hi = self.x1.clone();
+        for _ in 0..MAX_BISECTIONS {
+            let mid = (lo + hi) / nalgebra::convert(2.0);
+            if mid <= lo || mid >= hi {
+                break;
+            }
+            let x_mid = self.eval(mid);
+            let value = event.eval_condition(&x_mid);
+            if event.direction.is_crossing(before, value) {
+                (hi, x_hi) = (mid, x_mid);
+            } else {
+                (lo, before) = (mid, value);
+            }
+        }
+        (hi, x_hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulate::ode::NumericalPolynomialSystem;
+    use num_traits::Zero;
+
+    /// Falling object: dh/dt = v, dv/dt = -g, with v and h the first variables.
+    fn falling_object(h0: f32) -> ODEProblem<NumericalPolynomialSystem<i8>> {
+        let v = Polynomial::<usize, f32, i8>::generator(1);
+        let system = NumericalPolynomialSystem {
+            components: vec![v, Polynomial::zero() + (-1.0)],
+        };
+        ODEProblem::new(system, DVector::from_vec(vec![h0, 0.0])).end_time(3.0)
+    }
+
+    #[test]
+    fn terminate_on_ground() {
+        // h = 2 - t^2/2 hits the ground at t = 2.
+        let ground = ODEEvent {
+            condition: Polynomial::generator(0),
+            direction: EventDirection::Falling,
+            action: EventAction::Terminate,
+        };
+        let problem = falling_object(2.0).events(vec![ground]);
+        for output in [
+            problem.solve_dopri5_with_events(0.1).unwrap(),
+            problem.solve_rosenbrock23_with_events(0.1).unwrap(),
+        ] {
+            let (result, events) = (output.result, output.events);
+            assert_eq!(events.len(), 1);
+            assert!((events[0].time - 2.0).abs() < 1e-3);
+            let (t_out, x_out) = result.get();
+            assert_eq!(t_out.last(), Some(&events[0].time));
+            assert!(x_out.last().unwrap()[0].abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn bouncing_ball() {
+        // The ball bounces with restitution 1/2 at t = 2 and again at t = 4.
+        let bounce = ODEEvent {
+            condition: Polynomial::generator(0),
+            direction: EventDirection::Falling,
+            action: EventAction::Scale { var: 1, factor: -0.5 },
+        };
+        let problem = falling_object(2.0).end_time(5.0).events(vec![bounce]);
+        let output = problem.solve_dopri5_with_events(0.05).unwrap();
+        let (result, events) = (output.result, output.events);
+        let times: Vec<_> = events.iter().map(|e| e.time).collect();
+        assert_eq!(times.len(), 2);
+        assert!((times[0] - 2.0).abs() < 1e-3);
+        assert!((times[1] - 4.0).abs() < 1e-2);
+
+        // The velocity jumps at each bounce.
+        let (t_out, x_out) = result.get();
+        let k = t_out.iter().position(|t| *t == times[0]).unwrap();
+        assert_eq!(t_out[k + 1], times[0]);
+        assert!((x_out[k][1] + 2.0).abs() < 1e-2);
+        assert!((x_out[k + 1][1] - 1.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn terminate_before_blow_up() {
+        // dx/dt = x^2 blows up at t = 1, after the event at t = 1/3.
+        let x = Polynomial::<usize, f32, i8>::generator(0);
+        let system = NumericalPolynomialSystem { components: vec![x.clone() * x] };
+        let event = ODEEvent {
+            condition: Polynomial::generator(0) + (-1.5),
+            direction: EventDirection::Rising,
+            action: EventAction::Terminate,
+        };
+        let problem = ODEProblem::new(system, DVector::from_element(1, 1.0f32))
+            .end_time(2.0)
+            .events(vec![event]);
+        for output in [
+            problem.solve_dopri5_with_events(0.01).unwrap(),
+            problem.solve_rosenbrock23_with_events(0.01).unwrap(),
+            problem.solve_rk4_with_events(0.01).unwrap(),
+        ] {
+            assert_eq!(output.events.len(), 1);
+            assert!((output.events[0].time - 1.0 / 3.0).abs() < 1e-3);
+            let (_, x_out) = output.result.get();
+            assert!(x_out.iter().all(|x| x[0].is_finite() && x[0] <= 1.5 + 1e-3));
+        }
+    }
+
+    #[test]
+    fn too_many_events() {
+        // dx/dt = -1, with x flipped in sign whenever it crosses zero, so that
+        // once x reaches zero the event recurs without time advancing.
+        let system = NumericalPolynomialSystem::<i8> {
+            components: vec![Polynomial::zero() + (-1.0)],
+        };
+        let flip = ODEEvent {
+            condition: Polynomial::generator(0),
+            direction: EventDirection::Either,
+            action: EventAction::Scale { var: 0, factor: -1.0 },
+        };
+        let problem = ODEProblem::new(system, DVector::from_element(1, 1.0))
+            .end_time(2.0)
+            .events(vec![flip])
+            .max_events(20);
+        let result = problem.solve_rk4_with_events(0.01);
+        assert!(matches!(result, Err(IntegrationError::MaxNumStepReached { n_step: 20, .. })));
+    }
+
+    #[test]
+    fn record_and_reset() {
+        // dx/dt = 1, reset to zero whenever x reaches 1, so x is a sawtooth.
+        let system = NumericalPolynomialSystem::<i8> {
+            components: vec![Polynomial::zero() + 1.0],
+        };
+        let reset = ODEEvent {
+            condition: Polynomial::generator(0) + (-1.0),
+            direction: EventDirection::Rising,
+            action: EventAction::Set { var: 0, value: 0.0 },
+        };
+        let half = ODEEvent {
+            condition: Polynomial::generator(0) + (-0.5),
+            direction: EventDirection::Either,
+            action: EventAction::Record,
+        };
+        let problem = ODEProblem::new(system, DVector::from_element(1, 0.0))
+            .end_time(2.4)
+            .events(vec![reset, half]);
+        let output = problem.solve_rk4_with_events(0.01).unwrap();
+        let (result, events) = (output.result, output.events);
+        let expected = [(0.5, 1), (1.0, 0), (1.5, 1), (2.0, 0)];
+        assert_eq!(events.len(), expected.len());
+        for (occurrence, (time, event)) in events.iter().zip(expected) {
+            assert!((occurrence.time - time).abs() < 1e-3);
+            assert_eq!(occurrence.event, event);
+        }
+        let (_, x_out) = result.get();
+        assert!(x_out.iter().all(|x| x[0] <= 1.0 + 1e-3));
+    }
+}
@@ -2,11 +2,13 @@
 //!
 //! The first-order and second-order Kuramoto models are both described in
 //! Section 2.1 of [Nitzbon et al 2017](crate::refs::NitzbonNetworkStability),
-//! among other sources.
+//! among other sources. The analysis of phase-locked states follows [Dörfler
+//! et al 2013](crate::refs::DorflerSmartGrids).
 
 use nalgebra::{DMatrix, DVector};
+use num_traits::Float;
 
-use super::ODESystem;
+use super::{ODEScalar, ODESystem, cast_scalar};
 
 /// Differential order of a Kuramoto system.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -63,28 +65,194 @@ impl KuramotoSystem {
             forcing_params,
         }
     }
+
+    /// Number of oscillators in the system.
+    pub fn num_oscillators(&self) -> usize {
+        self.coupling_coeffs.nrows()
+    }
+
+    /// Laplacian matrix of the coupling graph linearized at the given phases.
+    ///
+    /// The edge from `i` to `j` has weight `K_{i,j} cos(ϕ_i - ϕ_j)`, so that the
+    /// Jacobian of the coupling terms with respect to the phases is the negative
+    /// of this matrix.
+    pub fn laplacian<T: ODEScalar>(&self, phases: &[T]) -> DMatrix<T> {
+        let n = self.num_oscillators();
+        let mut laplacian = DMatrix::zeros(n, n);
+        for i in 0..n {
+            for j in (0..n).filter(|j| *j != i) {
+                let k: T = cast_scalar(self.coupling_coeffs[(i, j)]);
+                let w = k * Float::cos(phases[i] - phases[j]);
+                laplacian[(i, j)] = -w;
+                laplacian[(i, i)] += w;
+            }
+        }
+        laplacian
+    }
+
+    /// Common angular frequency of the oscillators in a phase-locked state.
+    ///
+    /// Summing the equations of motion over all oscillators cancels the
+    /// symmetric coupling terms, so the frequency `Ω` of any phase-locked state
+    /// satisfies `Σ_i P_i = Ω Σ_i α_i`. Returns `None` if there is no damping.
+    pub fn locked_frequency(&self) -> Option<f32> {
+        let total_damping = self.damping_coeffs.sum();
+        (total_damping > 0.0).then(|| self.forcing_params.sum() / total_damping)
+    }
+
+    /// Instantaneous angular frequencies of the oscillators at a state.
+    pub fn frequencies(&self, x: &DVector<f32>) -> DVector<f32> {
+        let n = self.num_oscillators();
+        match self.order {
+            KuramotoOrder::First => {
+                let mut dx = DVector::zeros(n);
+                self.vector_field(&mut dx, x, 0.0);
+                dx
+            }
+            KuramotoOrder::Second => x.rows(n, n).into_owned(),
+        }
+    }
+
+    /// Finds a phase-locked state, in which all oscillators rotate at the
+    /// [common frequency](Self::locked_frequency) with constant phase
+    /// differences.
+    ///
+    /// Phases satisfying the power-flow equations
+    /// `P_i - α_i Ω = Σ_j K_{i,j} sin(ϕ_i - ϕ_j)` are found by Newton's method,
+    /// starting from the solution of the linearized equations. The phases are
+    /// measured relative to their mean. Returns `None` if the coupling graph is
+    /// disconnected or Newton's method fails to converge, which happens in
+    /// particular when the coupling is too weak for a phase-locked state to
+    /// exist.
+    pub fn phase_locked_state(&self) -> Option<DVector<f32>> {
+        let n = self.num_oscillators();
+        let omega = self.locked_frequency()?;
+        if n == 0 || !is_connected(&self.laplacian(&vec![0.0f64; n])) {
+            return None;
+        }
+        let power = DVector::from_fn(n, |i, _| {
+            f64::from(self.forcing_params[i]) - f64::from(self.damping_coeffs[i] * omega)
+        });
+        let scale = power.amax().max(1.0);
+
+        // Fix the first phase at zero, dropping its redundant equation.
+        let mut phases = DVector::<f64>::zeros(n);
+        for _ in 0..MAX_NEWTON_ITERATIONS {
+            let laplacian = self.laplacian(phases.as_slice());
+            let mut residual = power.clone();
+            for i in 0..n {
+                for j in 0..n {
+                    let k = f64::from(self.coupling_coeffs[(i, j)]);
+                    residual[i] -= k * (phases[i] - phases[j]).sin();
+                }
+            }
+            if residual.amax() <= NEWTON_TOLERANCE * scale {
+                let mean = phases.mean();
+                return Some(phases.map(|phi| (phi - mean) as f32));
+            }
+            let reduced = laplacian.view((1, 1), (n - 1, n - 1)).into_owned();
+            let step = reduced.lu().solve(&residual.rows(1, n - 1))?;
+            if !step.iter().all(|x| x.is_finite()) {
+                return None;
+            }
+            let mut update = phases.rows_mut(1, n - 1);
+            update += step;
+        }
+        None
+    }
+
+    /// Critical multiple of the coupling coefficients for phase locking.
+    ///
+    /// This is the largest difference across a coupled pair of oscillators
+    /// between the phases `L^† (P - α Ω)` of the linearized power-flow
+    /// equations, with `L` the Laplacian of the coupling graph. When it is less
+    /// than one, a stable phase-locked state exists with all phase differences
+    /// across couplings less than `π/2`. The condition is sufficient and, for
+    /// many networks, close to necessary ([Dörfler et al
+    /// 2013](crate::refs::DorflerSmartGrids)). Returns `None` if there is no
+    /// damping or the coupling graph is disconnected.
+    pub fn critical_coupling(&self) -> Option<f32> {
+        let n = self.num_oscillators();
+        let omega = self.locked_frequency()?;
+        let laplacian = self.laplacian(&vec![0.0f64; n]);
+        if n == 0 || !is_connected(&laplacian) {
+            return None;
+        }
+        let power = DVector::from_fn(n - 1, |i, _| {
+            f64::from(self.forcing_params[i + 1]) - f64::from(self.damping_coeffs[i + 1] * omega)
+        });
+        let reduced = laplacian.view((1, 1), (n - 1, n - 1)).into_owned();
+        let theta = reduced.lu().solve(&power)?.insert_row(0, 0.0);
+        let mut critical = 0.0f64;
+        for i in 0..n {
+            for j in (i + 1)..n {
+                if self.coupling_coeffs[(i, j)] > 0.0 {
+                    critical = critical.max((theta[i] - theta[j]).abs());
+                }
+            }
+        }
+        Some(critical as f32)
+    }
+}
+
+/// Maximum number of iterations of Newton's method for phase-locked states.
+const MAX_NEWTON_ITERATIONS: usize = 100;
+
+/// Tolerance, relative to the forcing, of the residual of phase-locked states.
+const NEWTON_TOLERANCE: f64 = 1e-10;
+
+/// Whether the graph with the given Laplacian matrix is connected.
+fn is_connected(laplacian: &DMatrix<f64>) -> bool {
+    let n = laplacian.nrows();
+    let mut visited = vec![false; n];
+    let mut stack = vec![0];
+    visited[0] = true;
+    while let Some(i) = stack.pop() {
+        for j in 0..n {
+            if !visited[j] && laplacian[(i, j)] != 0.0 {
+                visited[j] = true;
+                stack.push(j);
+            }
+        }
+    }
+    visited.into_iter().all(|v| v)
+}
+
+/// Kuramoto order parameter of a collection of phases.
+///
+/// The order parameter `r = |Σ_j exp(i ϕ_j)| / n` is one when all phases are
+/// equal and close to zero when they are spread evenly around the circle.
+pub fn order_parameter(phases: &[f32]) -> f32 {
+    if phases.is_empty() {
+        return 0.0;
+    }
+    let (cos, sin) = phases.iter().fold((0.0, 0.0), |(c, s), phi| (c + phi.cos(), s + phi.sin()));
+    cos.hypot(sin) / phases.len() as f32
 }
 
-impl ODESystem for KuramotoSystem {
-    fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, _t: f32) {
+impl<T: ODEScalar> ODESystem<T> for KuramotoSystem {
+    fn vector_field(&self, dx: &mut DVector<T>, x: &DVector<T>, _t: T) {
+        let coupling = |i: usize, j: usize| -> T { cast_scalar(self.coupling_coeffs[(i, j)]) };
+        let damping = |i: usize| -> T { cast_scalar(self.damping_coeffs[i]) };
+        let forcing = |i: usize| -> T { cast_scalar(self.forcing_params[i]) };
         match self.order {
             KuramotoOrder::First => {
                 let n = x.len();
                 for i in 0..n {
-                    let mut rhs = self.forcing_params[i];
+                    let mut rhs = forcing(i);
                     for j in 0..n {
-                        rhs -= self.coupling_coeffs[(i, j)] * (x[i] - x[j]).sin();
+                        rhs -= coupling(i, j) * Float::sin(x[i] - x[j]);
                     }
-                    dx[i] = rhs / self.damping_coeffs[i];
+                    dx[i] = rhs / damping(i);
                 }
             }
             KuramotoOrder::Second => {
                 let n = x.len() / 2;
                 for i in 0..n {
                     dx[i] = x[i + n];
-                    let mut rhs = self.forcing_params[i] - self.damping_coeffs[i] * x[i + n];
+                    let mut rhs = forcing(i) - damping(i) * x[i + n];
                     for j in 0..n {
-                        rhs -= self.coupling_coeffs[(i, j)] * (x[i] - x[j]).sin();
+                        rhs -= coupling(i, j) * Float::sin(x[i] - x[j]);
                     }
                     dx[i + n] = rhs;
                 }
@@ -96,7 +264,7 @@ impl ODESystem for KuramotoSystem {
 #[cfg(test)]
 mod tests {
     use expect_test::expect;
-    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, TAU as TWO_PI};
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, FRAC_PI_6, PI, TAU as TWO_PI};
 
     use super::super::{ODEProblem, textplot_mapped_ode_result};
     use super::*;
@@ -150,6 +318,37 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn phase_locking() {
+        assert_eq!(order_parameter(&[0.5, 0.5, 0.5]), 1.0);
+        assert!(order_parameter(&[0.0, PI]) < 1e-6);
+
+        // Two oscillators, one driving the other, coupled strongly enough to
+        // lock with a phase difference of `π/6`.
+        let coupling = |k: f32| DMatrix::from_row_slice(2, 2, &[0.0, k, k, 0.0]);
+        let mut sys = KuramotoSystem {
+            order: KuramotoOrder::First,
+            coupling_coeffs: coupling(2.0),
+            damping_coeffs: DVector::from_element(2, 1.0),
+            forcing_params: DVector::from_column_slice(&[1.0, -1.0]),
+        };
+        assert_eq!(sys.locked_frequency(), Some(0.0));
+        let phases = sys.phase_locked_state().unwrap();
+        assert!((phases[0] - phases[1] - FRAC_PI_6).abs() < 1e-6);
+        assert!((phases[0] + phases[1]).abs() < 1e-6);
+        assert_eq!(sys.critical_coupling(), Some(0.5));
+
+        // The locked state is an equilibrium with a stable direction.
+        let jac: DMatrix<f32> = sys.jacobian(&phases, 0.0);
+        assert!(jac.symmetric_eigenvalues().min() < -1.0);
+        assert!(sys.frequencies(&phases).amax() < 1e-6);
+
+        // With weak coupling, there is no phase-locked state.
+        sys.coupling_coeffs = coupling(0.4);
+        assert_eq!(sys.phase_locked_state(), None);
+        assert_eq!(sys.critical_coupling(), Some(2.5));
+    }
+
     #[test]
     fn second_order_kuramoto() {
         let sys = KuramotoSystem::fully_connected_homogeneous(
@@ -1,96 +1,215 @@
 //! Simulation of dynamical systems defined by ODEs.
 
-use nalgebra::DVector;
+use std::iter::{Product, Sum};
+
+use nalgebra::{DMatrix, DVector, RealField};
+use num_traits::{Float, Pow};
 use ode_solvers::{
     self,
-    dop_shared::{IntegrationError, SolverResult},
+    dop_shared::{FloatNumber, IntegrationError, OutputType, SolverResult, Stats},
 };
 
 #[cfg(test)]
 use textplots::{Chart, Plot, Shape};
 
+/// Floating point type in which ODEs are solved.
+///
+/// This trait is implemented by both `f32` and `f64`. Single precision is the
+/// default throughout, but long simulations may require double precision.
+pub trait ODEScalar: FloatNumber + RealField + Sum + Product + Pow<i8, Output = Self> {}
+
+impl<T> ODEScalar for T where T: FloatNumber + RealField + Sum + Product + Pow<i8, Output = T> {}
+
+/// Converts between floating point types, possibly losing precision.
+pub fn cast_scalar<T: ODEScalar, U: ODEScalar>(x: T) -> U {
+    nalgebra::convert(nalgebra::convert::<T, f64>(x))
+}
+
 /// A system of ordinary differential equations (ODEs).
 ///
-/// An ODE system is anything that can compute a vector field.
-pub trait ODESystem {
+/// An ODE system is anything that can compute a vector field. The system can
+/// be solved in any [floating point type](ODEScalar) for which it implements
+/// this trait, by default single precision.
+pub trait ODESystem<T: ODEScalar = f32> {
     /// Compute the vector field at the given time and state in place.
-    fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, t: f32);
+    fn vector_field(&self, dx: &mut DVector<T>, x: &DVector<T>, t: T);
 
     /// Compute and return the vector field at the given time and state.
-    fn eval_vector_field(&self, x: &DVector<f32>, t: f32) -> DVector<f32> {
-        let mut dx = DVector::from_element(x.len(), 0.0f32);
+    fn eval_vector_field(&self, x: &DVector<T>, t: T) -> DVector<T> {
+        let mut dx = DVector::zeros(x.len());
         self.vector_field(&mut dx, x, t);
         dx
     }
+
+    /// Compute the Jacobian matrix of the vector field at the given time and state.
+    ///
+    /// The default implementation approximates the Jacobian by forward finite
+    /// differences. Systems that can compute their Jacobian analytically, such as
+    /// [polynomial systems](NumericalPolynomialSystem), should override it.
+    fn jacobian(&self, x: &DVector<T>, t: T) -> DMatrix<T> {
+        let n = x.len();
+        let fx = self.eval_vector_field(x, t);
+        let mut jac = DMatrix::zeros(n, n);
+        let mut x_pert = x.clone();
+        for j in 0..n {
+            let h = Float::sqrt(<T as Float>::epsilon()) * Float::max(Float::abs(x[j]), T::one());
+            x_pert[j] = x[j] + h;
+            let fx_pert = self.eval_vector_field(&x_pert, t);
+            jac.set_column(j, &((fx_pert - &fx) / h));
+            x_pert[j] = x[j];
+        }
+        jac
+    }
+}
+
+impl<T: ODEScalar, Sys: ODESystem<T> + ?Sized> ODESystem<T> for &Sys {
+    fn vector_field(&self, dx: &mut DVector<T>, x: &DVector<T>, t: T) {
+        (**self).vector_field(dx, x, t)
+    }
+
+    fn jacobian(&self, x: &DVector<T>, t: T) -> DMatrix<T> {
+        (**self).jacobian(x, t)
+    }
 }
 
 /// An ODE problem ready to be solved.
 ///
 /// An ODE problem comprises an [ODE system](ODESystem) plus the extra information
 /// needed to solve the system, namely the initial values and the time span.
+/// Optionally, the problem can have [events](ODEEvent) that terminate the
+/// simulation or change the state discontinuously. The problem also sets the
+/// tolerances and maximum number of steps of the adaptive solvers, and the
+/// maximum number of events.
 #[derive(Clone, Debug, PartialEq)]
-pub struct ODEProblem<Sys> {
+pub struct ODEProblem<Sys, T = f32> {
     pub(crate) system: Sys,
-    pub(crate) initial_values: DVector<f32>,
-    pub(crate) start_time: f32,
-    pub(crate) end_time: f32,
-    rtol: f32,
-    atol: f32,
+    pub(crate) initial_values: DVector<T>,
+    pub(crate) start_time: T,
+    pub(crate) end_time: T,
+    // Tolerances are kept in double precision, so that they are not rounded
+    // when the problem is cast to double precision.
+    rtol: f64,
+    atol: f64,
+    max_steps: u32,
+    max_events: u32,
+    pub(crate) events: Vec<ODEEvent>,
 }
 
-impl<Sys> ODEProblem<Sys> {
+impl<Sys, T: ODEScalar> ODEProblem<Sys, T> {
     /// Creates a new ODE problem.
-    pub fn new(system: Sys, initial_values: DVector<f32>) -> Self {
+    pub fn new(system: Sys, initial_values: DVector<T>) -> Self {
         ODEProblem {
             system,
             initial_values,
-            start_time: 0.0,
-            end_time: 0.0,
+            start_time: T::zero(),
+            end_time: T::zero(),
             // Same defaults as `scipy.integrate.RK45`.
             rtol: 0.001,
             atol: 1e-6,
+            // Same default as `ode_solvers`.
+            max_steps: 100000,
+            max_events: 1000,
+            events: Vec::new(),
         }
     }
 
     /// Sets the start time for the problem.
-    pub fn start_time(mut self, t: f32) -> Self {
+    pub fn start_time(mut self, t: T) -> Self {
         self.start_time = t;
         self
     }
 
     /// Sets the end time for the problem.
-    pub fn end_time(mut self, t: f32) -> Self {
+    pub fn end_time(mut self, t: T) -> Self {
         self.end_time = t;
         self
     }
 
     /// Sets the time span (start and end time) for the problem.
-    pub fn time_span(mut self, tspan: (f32, f32)) -> Self {
+    pub fn time_span(mut self, tspan: (T, T)) -> Self {
         (self.start_time, self.end_time) = tspan;
         self
     }
+
+    /// Sets the events for the problem.
+    pub fn events(mut self, events: Vec<ODEEvent>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Sets the relative tolerance for the adaptive solvers.
+    pub fn rtol(mut self, rtol: T) -> Self {
+        self.rtol = cast_scalar(rtol);
+        self
+    }
+
+    /// Sets the absolute tolerance for the adaptive solvers.
+    pub fn atol(mut self, atol: T) -> Self {
+        self.atol = cast_scalar(atol);
+        self
+    }
+
+    /// Sets the maximum number of steps for the adaptive solvers.
+    pub fn max_steps(mut self, n_max: u32) -> Self {
+        self.max_steps = n_max;
+        self
+    }
+
+    /// Sets the maximum number of events before the solvers are aborted.
+    ///
+    /// Events that keep occurring, possibly infinitely often in finite time,
+    /// are reported as [`IntegrationError::MaxNumStepReached`] at the time of
+    /// the last event, with the maximum number of events as the number of
+    /// steps.
+    pub fn max_events(mut self, n_max: u32) -> Self {
+        self.max_events = n_max;
+        self
+    }
+
+    /// Converts the problem to another floating point type.
+    ///
+    /// The converted problem borrows the system, which must also implement
+    /// [`ODESystem`] for the new type.
+    pub fn cast<U: ODEScalar>(&self) -> ODEProblem<&Sys, U> {
+        ODEProblem {
+            system: &self.system,
+            initial_values: self.initial_values.map(cast_scalar),
+            start_time: cast_scalar(self.start_time),
+            end_time: cast_scalar(self.end_time),
+            rtol: self.rtol,
+            atol: self.atol,
+            max_steps: self.max_steps,
+            max_events: self.max_events,
+            events: self.events.clone(),
+        }
+    }
 }
 
-impl<Sys> ODEProblem<Sys>
+impl<Sys, T> ODEProblem<Sys, T>
 where
-    Sys: ODESystem,
+    Sys: ODESystem<T>,
+    T: ODEScalar,
 {
     /// Solves the ODE system using the Runge-Kutta method.
     ///
     /// Returns the solver results if successful and an integration error otherwise.
-    pub fn solve_rk4(
+    pub fn solve_rk4(&self, step_size: T) -> Result<SolverResult<T, DVector<T>>, IntegrationError> {
+        Ok(self.solve_rk4_with_events(step_size)?.result)
+    }
+
+    /// Solves the ODE system using the Runge-Kutta method, reporting events and
+    /// solver statistics.
+    pub fn solve_rk4_with_events(
         &self,
-        step_size: f32,
-    ) -> Result<SolverResult<f32, DVector<f32>>, IntegrationError> {
-        let mut stepper = ode_solvers::Rk4::new(
-            self,
-            self.start_time,
-            self.initial_values.clone(),
-            self.end_time,
-            step_size,
-        );
-        stepper.integrate()?;
-        Ok(stepper.into())
+        step_size: T,
+    ) -> Result<ODESolverOutput<T>, IntegrationError> {
+        self.solve_with_events(|segment| {
+            let (t0, t1) = (segment.problem.start_time, segment.problem.end_time);
+            let y0 = segment.problem.initial_values.clone();
+            let mut stepper = ode_solvers::Rk4::new(segment, t0, y0, t1, step_size);
+            let stats = stepper.integrate()?;
+            Ok((stepper.into(), stats))
+        })
     }
 
     /// Solves the ODE system using the Dormand-Prince method.
@@ -99,27 +218,247 @@ where
     /// selection of initial step size.
     pub fn solve_dopri5(
         &self,
-        output_step_size: f32,
-    ) -> Result<SolverResult<f32, DVector<f32>>, IntegrationError> {
-        let mut stepper = ode_solvers::Dopri5::new(
-            self,
-            self.start_time,
-            self.end_time,
-            output_step_size,
-            self.initial_values.clone(),
-            self.rtol,
-            self.atol,
-        );
-        stepper.integrate()?;
-        Ok(stepper.into())
+        output_step_size: T,
+    ) -> Result<SolverResult<T, DVector<T>>, IntegrationError>
+    where
+        f64: From<T>,
+    {
+        Ok(self.solve_dopri5_with_events(output_step_size)?.result)
+    }
+
+    /// Solves the ODE system using the Dormand-Prince method, reporting events
+    /// and solver statistics.
+    pub fn solve_dopri5_with_events(
+        &self,
+        output_step_size: T,
+    ) -> Result<ODESolverOutput<T>, IntegrationError>
+    where
+        f64: From<T>,
+    {
+        let c = |x: f64| -> T { nalgebra::convert(x) };
+        self.solve_with_events(|segment| {
+            let problem = &segment.problem;
+            let (t0, t1) = (problem.start_time, problem.end_time);
+            let (rtol, atol) = (c(problem.rtol), c(problem.atol));
+            let y0 = problem.initial_values.clone();
+            let max_steps = problem.max_steps;
+            // Same parameters as `Dopri5::new`, except for the maximum number of steps.
+            let mut stepper = ode_solvers::Dopri5::from_param(
+                segment,
+                t0,
+                t1,
+                output_step_size,
+                y0,
+                rtol,
+                atol,
+                c(0.9),
+                c(0.04),
+                c(0.2),
+                c(10.0),
+                t1 - t0,
+                T::zero(),
+                max_steps,
+                1000,
+                OutputType::Dense,
+            );
+            let stats = stepper.integrate()?;
+            Ok((stepper.into(), stats))
+        })
+    }
+
+    /// Solves the ODE system using the Rosenbrock method of order 2(3).
+    ///
+    /// A linearly implicit method suitable for [stiff](Self::is_stiff) problems,
+    /// with adaptive step size control. See [`Rosenbrock23`] for details.
+    pub fn solve_rosenbrock23(
+        &self,
+        output_step_size: T,
+    ) -> Result<SolverResult<T, DVector<T>>, IntegrationError> {
+        Ok(self.solve_rosenbrock23_with_events(output_step_size)?.result)
+    }
+
+    /// Solves the ODE system using the Rosenbrock method, reporting events and
+    /// solver statistics.
+    pub fn solve_rosenbrock23_with_events(
+        &self,
+        output_step_size: T,
+    ) -> Result<ODESolverOutput<T>, IntegrationError> {
+        self.solve_with_events(|segment| {
+            let problem = &segment.problem;
+            let (system, t0, t1) = (problem.system, problem.start_time, problem.end_time);
+            let (rtol, atol) = (cast_scalar(problem.rtol), cast_scalar(problem.atol));
+            let y0 = problem.initial_values.clone();
+            let max_steps = problem.max_steps;
+            let mut stepper = Rosenbrock23::new(system, t0, t1, output_step_size, y0, rtol, atol)
+                .max_steps(max_steps)
+                .stop_when(|t, x| segment.has_event(t, x));
+            let stats = stepper.integrate()?;
+            Ok((stepper.into(), stats))
+        })
+    }
+
+    /// Solves the ODE system, returning the states at the given times.
+    ///
+    /// The times should be increasing and no earlier than the start time of the
+    /// problem; the end time is ignored. The system is integrated between
+    /// consecutive times using the Dormand-Prince method, falling back to the
+    /// Rosenbrock method if the problem turns out to be stiff. Events of the
+    /// problem are not taken into account.
+    pub fn solve_at_times(&self, times: &[T]) -> Result<Vec<DVector<T>>, IntegrationError>
+    where
+        f64: From<T>,
+    {
+        self.solve_at_times_with(times, |problem, dt| match problem.solve_dopri5(dt) {
+            Err(
+                IntegrationError::StiffnessDetected { .. }
+                | IntegrationError::MaxNumStepReached { .. },
+            ) => problem.solve_rosenbrock23(dt),
+            result => result,
+        })
+    }
+
+    /// Solves the ODE system by the given method, returning the states at the
+    /// given times.
+    ///
+    /// Like [`solve_at_times`](Self::solve_at_times), except that the system is
+    /// integrated between consecutive times by calling `solve` on the problem
+    /// for that interval, with an output step size just short of the interval.
+    /// The state at the next time is taken to be the last output of the solver.
+    pub fn solve_at_times_with<F>(
+        &self,
+        times: &[T],
+        mut solve: F,
+    ) -> Result<Vec<DVector<T>>, IntegrationError>
+    where
+        F: FnMut(&ODEProblem<&Sys, T>, T) -> Result<SolverResult<T, DVector<T>>, IntegrationError>,
+    {
+        let mut states = Vec::with_capacity(times.len());
+        let (mut t, mut x) = (self.start_time, self.initial_values.clone());
+        for &t_next in times {
+            if t_next > t {
+                let problem = ODEProblem {
+                    system: &self.system,
+                    initial_values: x,
+                    start_time: t,
+                    end_time: t_next,
+                    rtol: self.rtol,
+                    atol: self.atol,
+                    max_steps: self.max_steps,
+                    max_events: self.max_events,
+                    events: Vec::new(),
+                };
+                // Output only at the end time, taking care that the solver does
+                // not skip it due to rounding.
+                let four: T = nalgebra::convert(4.0);
+                let dt = (t_next - t) * (T::one() - four * <T as Float>::epsilon());
+                let result = solve(&problem, dt)?;
+                let (_, x_out) = result.get();
+                x = x_out.last().expect("Solver should produce output").clone();
+                t = t_next;
+            }
+            states.push(x.clone());
+        }
+        Ok(states)
     }
+
+    /// Heuristically checks whether the ODE problem is stiff.
+    ///
+    /// A problem is deemed stiff when the Jacobian has an eigenvalue whose decay
+    /// rate, measured against the duration of the problem, would force an
+    /// explicit method to take a very large number of steps for stability rather
+    /// than accuracy. Stiffness often develops only after the initial state, as
+    /// when an intermediate species is produced, so the Jacobian is sampled along
+    /// a short probe of explicit Euler steps starting from the initial state.
+    /// Stiffness that develops later in the simulation is not detected.
+    pub fn is_stiff(&self) -> bool {
+        let duration = self.end_time - self.start_time;
+        if duration == T::zero() {
+            return false;
+        }
+        let threshold: T = nalgebra::convert(STIFFNESS_THRESHOLD);
+        let probe_steps: T = nalgebra::convert(STIFFNESS_PROBE_STEPS as f64);
+        let (mut x, mut t) = (self.initial_values.clone(), self.start_time);
+        for _ in 0..STIFFNESS_PROBE_STEPS {
+            let jac = self.system.jacobian(&x, t);
+            if !jac.iter().all(|a| Float::is_finite(*a)) {
+                return false;
+            }
+            let norm = jac
+                .row_iter()
+                .map(|row| row.iter().map(|a| Float::abs(*a)).sum())
+                .fold(T::zero(), Float::max);
+            if max_decay_rate(jac) * Float::abs(duration) > threshold {
+                return true;
+            }
+
+            // Limit the step size so that the explicit probe itself is stable.
+            let mut h = duration / threshold / probe_steps;
+            if norm * Float::abs(h) > T::one() {
+                h /= norm * Float::abs(h);
+            }
+            x += self.system.eval_vector_field(&x, t) * h;
+            t += h;
+        }
+        false
+    }
+}
+
+/// Largest decay rate of a linear system, i.e., the largest negated real part of
+/// an eigenvalue of the matrix, or zero if there is none.
+fn max_decay_rate<T: ODEScalar>(jac: DMatrix<T>) -> T {
+    // The Schur decomposition need not converge on degenerate matrices, such as
+    // the zero matrix, so the iterations are capped.
+    if jac.iter().all(|a| *a == T::zero()) {
+        return T::zero();
+    }
+    jac.try_schur(<T as Float>::epsilon(), SCHUR_MAX_ITERATIONS)
+        .map_or(T::zero(), |schur| {
+            schur
+                .complex_eigenvalues()
+                .iter()
+                .map(|lambda| -lambda.re)
+                .fold(T::zero(), Float::max)
+        })
 }
 
-impl<Sys> ode_solvers::dop_shared::System<f32, DVector<f32>> for &ODEProblem<Sys>
+/// Output of an ODE solver.
+pub struct ODESolverOutput<T = f32> {
+    /// Times and states output by the solver.
+    ///
+    /// When an event changes the state, the output contains the states both
+    /// before and after the event, at the same time.
+    pub result: SolverResult<T, DVector<T>>,
+
+    /// Events that occurred, in order of time.
+    pub events: Vec<EventOccurrence>,
+
+    /// Statistics of the solver, summed over all restarts after events.
+    pub stats: Stats,
+}
+
+/// Threshold above which an ODE problem is [considered stiff](ODEProblem::is_stiff).
+///
+/// An explicit Runge-Kutta method is stable only when its step size times the
+/// largest decay rate is bounded by a small constant, so this is roughly the
+/// number of steps forced on an explicit solver purely for stability.
+const STIFFNESS_THRESHOLD: f64 = 1e4;
+
+/// Number of explicit steps taken when [probing](ODEProblem::is_stiff) an ODE
+/// problem for stiffness.
+///
+/// The probe spans the time that an explicit solver would need for one step
+/// on a problem at the [stiffness threshold](STIFFNESS_THRESHOLD).
+const STIFFNESS_PROBE_STEPS: usize = 10;
+
+/// Maximum number of iterations when computing eigenvalues to check stiffness.
+const SCHUR_MAX_ITERATIONS: usize = 1000;
+
+impl<Sys, T> ode_solvers::dop_shared::System<T, DVector<T>> for &ODEProblem<Sys, T>
 where
-    Sys: ODESystem,
+    Sys: ODESystem<T>,
+    T: ODEScalar,
 {
-    fn system(&self, x: f32, y: &DVector<f32>, dy: &mut DVector<f32>) {
+    fn system(&self, x: T, y: &DVector<T>, dy: &mut DVector<T>) {
         self.system.vector_field(dy, y, x);
     }
 }
@@ -157,8 +496,16 @@ pub(crate) fn textplot_mapped_ode_result<Sys>(
     chart.to_string()
 }
 
+pub mod equilibrium;
+pub mod events;
 pub mod kuramoto;
 pub mod polynomial;
+pub mod rosenbrock;
+pub mod sensitivity;
 
+pub use equilibrium::*;
+pub use events::*;
 pub use kuramoto::*;
 pub use polynomial::*;
+pub use rosenbrock::*;
+pub use sensitivity::*;
@@ -2,12 +2,13 @@
 
 use std::fmt::Display;
 use std::hash::Hash;
-use std::ops::{Add, Neg};
+use std::ops::{Add, Mul, Neg, Sub};
 
 use derivative::Derivative;
 use indexmap::IndexMap;
-use nalgebra::DVector;
-use num_traits::{One, Pow, Zero};
+use itertools::Itertools;
+use nalgebra::{DMatrix, DVector};
+use num_traits::{One, Pow, ToPrimitive, Zero};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -16,7 +17,8 @@ use tsify::Tsify;
 
 #[cfg(test)]
 use super::ODEProblem;
-use super::ODESystem;
+use super::SensitivitySystem;
+use super::{ODEScalar, ODESystem, cast_scalar};
 use crate::zero::{alg::Polynomial, rig::DisplayCoef};
 
 /// A system of polynomial differential equations.
@@ -109,6 +111,132 @@ where
             })
             .collect()
     }
+
+    /// Computes the Jacobian matrix of the system symbolically.
+    ///
+    /// Both the rows and the columns of the matrix are indexed by the variables
+    /// of the system, in the order of its components.
+    pub fn jacobian(&self) -> PolynomialJacobian<Var, Coef, Exp>
+    where
+        Var: Clone,
+        Coef: Clone + Zero + One + Neg<Output = Coef> + Mul<Output = Coef>,
+        Exp: Clone + Zero + One + Sub<Output = Exp> + ToPrimitive,
+    {
+        let variables = self.components.keys().cloned().collect_vec();
+        let entries = self
+            .components
+            .values()
+            .map(|poly| variables.iter().map(|var| poly.partial_derivative(var)).collect())
+            .collect();
+        PolynomialJacobian { variables, entries }
+    }
+}
+
+/// The Jacobian matrix of a polynomial system.
+///
+/// Each entry is itself a polynomial, the partial derivative of a component of
+/// the vector field with respect to a variable.
+#[derive(Clone)]
+pub struct PolynomialJacobian<Var, Coef, Exp> {
+    /// Variables indexing the rows and columns of the matrix.
+    pub variables: Vec<Var>,
+
+    /// Entries of the matrix, as a list of rows.
+    ///
+    /// The entry in row `i` and column `j` is the partial derivative of the
+    /// `i`th component with respect to the `j`th variable.
+    pub entries: Vec<Vec<Polynomial<Var, Coef, Exp>>>,
+}
+
+impl<Var, Coef, Exp> PolynomialJacobian<Var, Coef, Exp>
+where
+    Var: Ord,
+    Exp: Ord,
+{
+    /// Iterates over the nonzero entries of the matrix.
+    pub fn nonzero_entries(&self) -> impl Iterator<Item = (&Var, &Var, &Polynomial<Var, Coef, Exp>)>
+    where
+        Coef: Zero,
+    {
+        self.entries.iter().zip(&self.variables).flat_map(move |(row, var)| {
+            row.iter()
+                .zip(&self.variables)
+                .filter(|(poly, _)| !poly.is_zero())
+                .map(move |(poly, wrt)| (var, wrt, poly))
+        })
+    }
+
+    /// Maps the coefficients of the polynomials comprising the matrix.
+    pub fn extend_scalars<NewCoef, F>(self, f: F) -> PolynomialJacobian<Var, NewCoef, Exp>
+    where
+        F: Clone + FnMut(Coef) -> NewCoef,
+    {
+        let entries = self
+            .entries
+            .into_iter()
+            .map(|row| row.into_iter().map(|poly| poly.extend_scalars(f.clone())).collect())
+            .collect();
+        PolynomialJacobian { variables: self.variables, entries }
+    }
+
+    /// Maps the variables of the polynomials comprising the matrix.
+    pub fn map_variables<NewVar, F>(&self, mut f: F) -> PolynomialJacobian<NewVar, Coef, Exp>
+    where
+        NewVar: Clone + Ord,
+        Coef: Clone + Add<Output = Coef>,
+        Exp: Clone + Add<Output = Exp>,
+        F: FnMut(&Var) -> NewVar,
+    {
+        let variables = self.variables.iter().map(&mut f).collect();
+        let entries = self
+            .entries
+            .iter()
+            .map(|row| row.iter().map(|poly| poly.map_variables(&mut f)).collect())
+            .collect();
+        PolynomialJacobian { variables, entries }
+    }
+
+    /// Evaluates the matrix numerically at a state of the system.
+    pub fn eval<F>(&self, f: F) -> DMatrix<f32>
+    where
+        Coef: Clone,
+        Exp: Clone,
+        f32: Mul<Coef, Output = f32> + Pow<Exp, Output = f32>,
+        F: Fn(&Var) -> f32,
+    {
+        let n = self.variables.len();
+        DMatrix::from_fn(n, n, |i, j| self.entries[i][j].eval(&f))
+    }
+
+    /// Converts the nonzero entries of the matrix to equations as LaTeX strings.
+    pub fn to_latex_equations(&self) -> Vec<LatexEquation>
+    where
+        Var: Display,
+        Coef: Display + DisplayCoef + Clone + PartialEq + Zero + One + Neg<Output = Coef>,
+        Exp: Display + PartialEq + One,
+    {
+        self.nonzero_entries()
+            .map(|(var, wrt, poly)| LatexEquation {
+                lhs: format!("\\frac{{\\partial \\dot{{{var}}}}}{{\\partial {wrt}}}"),
+                rhs: poly.to_latex(),
+            })
+            .collect()
+    }
+}
+
+impl<Var, Coef, Exp> Display for PolynomialJacobian<Var, Coef, Exp>
+where
+    Var: Display + Ord,
+    Coef: Zero,
+    Exp: Ord,
+    Polynomial<Var, Coef, Exp>: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (var, wrt, poly) in self.nonzero_entries() {
+            writeln!(f, "J[{var}, {wrt}] = {poly}")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -144,6 +272,65 @@ where
     }
 }
 
+impl<Var, Param, Exp> PolynomialSystem<Var, Polynomial<Param, f32, Exp>, Exp>
+where
+    Var: Clone + Hash + Ord,
+    Param: Clone + Hash + Ord,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + ToPrimitive,
+{
+    /// Collects the parameters occurring in the coefficients of the system.
+    ///
+    /// The parameters are sorted and without duplicates.
+    pub fn parameters(&self) -> Vec<Param> {
+        self.components
+            .values()
+            .flat_map(|poly| poly.coefficients())
+            .flat_map(|coef| coef.monomials())
+            .flat_map(|m| m.variables())
+            .cloned()
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// Converts a system with symbolic coefficients into a numerical system
+    /// augmented with its forward sensitivity equations.
+    ///
+    /// Sensitivities are computed with respect to the parameters in `sensitive`,
+    /// in that order. Every parameter takes its value from `values`. As in
+    /// [`to_numerical`](PolynomialSystem::to_numerical), the state variables are
+    /// ordered like the components of the system.
+    pub fn to_sensitivity_system<F>(
+        &self,
+        mut values: F,
+        sensitive: &[Param],
+    ) -> SensitivitySystem<Exp>
+    where
+        F: FnMut(&Param) -> f32,
+    {
+        let indices: IndexMap<Var, usize> =
+            self.components.keys().enumerate().map(|(i, var)| (var.clone(), i)).collect();
+        let mut params: IndexMap<Param, usize> =
+            sensitive.iter().enumerate().map(|(k, p)| (p.clone(), k)).collect();
+        let n = indices.len();
+        let components = self
+            .components
+            .values()
+            .map(|poly| {
+                poly.flatten(
+                    |var| indices[var],
+                    |p| {
+                        let len = params.len();
+                        n + *params.entry(p.clone()).or_insert(len)
+                    },
+                )
+            })
+            .collect();
+        let parameters = params.keys().map(&mut values).collect();
+        SensitivitySystem::new(components, parameters, sensitive.len())
+    }
+}
+
 impl<Var, Coef, Exp> Display for PolynomialSystem<Var, Coef, Exp>
 where
     Var: Display,
@@ -182,18 +369,39 @@ pub struct NumericalPolynomialSystem<Exp> {
     pub components: Vec<Polynomial<usize, f32, Exp>>,
 }
 
-impl<Exp> ODESystem for NumericalPolynomialSystem<Exp>
+impl<Exp, T> ODESystem<T> for NumericalPolynomialSystem<Exp>
 where
-    Exp: Clone + Ord,
-    f32: Pow<Exp, Output = f32>,
+    Exp: Clone + Ord + Zero + One + Sub<Output = Exp> + ToPrimitive,
+    T: ODEScalar + Pow<Exp, Output = T>,
 {
-    fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, _t: f32) {
+    fn vector_field(&self, dx: &mut DVector<T>, x: &DVector<T>, _t: T) {
         for i in 0..dx.len() {
-            dx[i] = self.components[i].eval(|var| x[*var])
+            dx[i] = eval_numerical(&self.components[i], x);
+        }
+    }
+
+    /// Computes the Jacobian analytically by differentiating the polynomials.
+    fn jacobian(&self, x: &DVector<T>, _t: T) -> DMatrix<T> {
+        let n = x.len();
+        let mut jac = DMatrix::zeros(n, n);
+        for (i, component) in self.components.iter().enumerate() {
+            for j in component.monomials().flat_map(|m| m.variables()).copied().unique() {
+                jac[(i, j)] = eval_numerical(&component.partial_derivative(&j), x);
+            }
         }
+        jac
     }
 }
 
+/// Evaluates a polynomial with `f32` coefficients at a state of any precision.
+pub(super) fn eval_numerical<Exp, T>(poly: &Polynomial<usize, f32, Exp>, x: &DVector<T>) -> T
+where
+    Exp: Clone + Ord,
+    T: ODEScalar + Pow<Exp, Output = T>,
+{
+    poly.eval_with_scalars(|var| x[*var], |coef| cast_scalar(*coef))
+}
+
 impl<Exp> Display for NumericalPolynomialSystem<Exp>
 where
     Exp: Clone + Ord + Add<Output = Exp> + One + Display,
@@ -218,6 +426,33 @@ mod tests {
 
     type Parameter<Id> = Polynomial<Id, f32, u8>;
 
+    #[test]
+    fn sir_jacobian() {
+        let param = |c: char| Polynomial::<_, f32, i8>::generator(c);
+        let var = |c: char| Polynomial::<_, Polynomial<_, f32, i8>, u8>::generator(c);
+        let terms = [
+            ('S', -var('S') * var('I') * param('β')),
+            ('I', var('S') * var('I') * param('β')),
+            ('I', -var('I') * param('γ')),
+            ('R', var('I') * param('γ')),
+        ];
+        let sys: PolynomialSystem<_, _, _> = terms.into_iter().collect();
+
+        let jacobian = sys.jacobian();
+        let expected = expect![[r#"
+            J[S, S] = -β I
+            J[S, I] = -β S
+            J[I, S] = β I
+            J[I, I] = -γ 1 + β S
+            J[R, I] = γ 1
+        "#]];
+        expected.assert_eq(&jacobian.to_string());
+
+        let jacobian = jacobian.extend_scalars(|p| p.eval(|_| 1.0));
+        assert_eq!(jacobian.eval(|_| 2.0)[(1, 1)], 1.0);
+        assert_eq!(jacobian.to_latex_equations().len(), 5);
+    }
+
     #[test]
     fn sir() {
         let param = |c: char| Parameter::<_>::generator(c);
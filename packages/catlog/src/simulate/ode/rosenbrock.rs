@@ -0,0 +1,308 @@
+//! Rosenbrock method for stiff ODEs.
+//!
+//! The explicit Runge-Kutta methods provided by `ode_solvers` are unsuitable for
+//! stiff problems, such as chemical reaction networks with rate constants
+//! spanning many orders of magnitude. This module implements the linearly
+//! implicit Rosenbrock method of order 2(3) due to [Shampine & Reichelt
+//! 1997](https://doi.org/10.1137/S1064827594276424), the method behind MATLAB's
+//! `ode23s`. It requires the Jacobian of the vector field, which is computed
+//! analytically for [polynomial systems](super::NumericalPolynomialSystem).
+
+use nalgebra::{DMatrix, DVector};
+use num_traits::Float;
+use ode_solvers::dop_shared::{IntegrationError, SolverResult, Stats};
+
+use super::{ODEScalar, ODESystem};
+
+/// Stepper for the Rosenbrock method of order 2(3).
+///
+/// The method is L-stable and uses a third-order error estimate to control the
+/// step size. Output is produced at equally spaced times using the method's
+/// dense output formula, in the same manner as the Dormand-Prince stepper of
+/// `ode_solvers`.
+pub struct Rosenbrock23<'a, Sys, T = f32> {
+    system: &'a Sys,
+    t: T,
+    t_start: T,
+    t_end: T,
+    dt_out: T,
+    y: DVector<T>,
+    rtol: T,
+    atol: T,
+    n_max: u32,
+    stop: Option<StopFn<'a, T>>,
+    results: SolverResult<T, DVector<T>>,
+    stats: Stats,
+}
+
+/// Function deciding whether to stop integration at an output.
+type StopFn<'a, T> = Box<dyn FnMut(T, &DVector<T>) -> bool + 'a>;
+
+/// Diagonal coefficient of the method, `1/(2 + √2)`.
+const D: f64 = 0.292_893_218_813_452_5;
+
+/// Coefficient `6 + √2` appearing in the error estimate.
+const E32: f64 = 7.414_213_562_373_095;
+
+/// Converts a constant into the floating point type of the stepper.
+fn c<T: ODEScalar>(x: f64) -> T {
+    nalgebra::convert(x)
+}
+
+impl<'a, Sys: ODESystem<T>, T: ODEScalar> Rosenbrock23<'a, Sys, T> {
+    /// Constructs a new stepper.
+    ///
+    /// The arguments have the same meaning as for `ode_solvers::Dopri5::new`.
+    pub fn new(
+        system: &'a Sys,
+        t: T,
+        t_end: T,
+        dt_out: T,
+        y: DVector<T>,
+        rtol: T,
+        atol: T,
+    ) -> Self {
+        Self {
+            system,
+            t,
+            t_start: t,
+            t_end,
+            dt_out,
+            y,
+            rtol,
+            atol,
+            n_max: 100000,
+            stop: None,
+            results: SolverResult::default(),
+            stats: Stats {
+                num_eval: 0,
+                accepted_steps: 0,
+                rejected_steps: 0,
+            },
+        }
+    }
+
+    /// Sets the maximum number of steps before integration is aborted.
+    pub fn max_steps(mut self, n_max: u32) -> Self {
+        self.n_max = n_max;
+        self
+    }
+
+    /// Sets a function called on the last output after each accepted step,
+    /// which stops the integration by returning true.
+    ///
+    /// This plays the same role as `System::solout` for the solvers of
+    /// `ode_solvers`.
+    pub fn stop_when(mut self, stop: impl FnMut(T, &DVector<T>) -> bool + 'a) -> Self {
+        self.stop = Some(Box::new(stop));
+        self
+    }
+
+    /// Integrates the system from the start time to the end time.
+    pub fn integrate(&mut self) -> Result<Stats, IntegrationError> {
+        let n = self.y.len();
+        let duration = self.t_end - self.t;
+        self.results.push(self.t, self.y.clone());
+        if duration <= T::zero() || n == 0 {
+            return Ok(self.stats);
+        }
+        let mut n_out = 1;
+
+        let (d, e32) = (c::<T>(D), c::<T>(E32));
+        let (half, two) = (c::<T>(0.5), c::<T>(2.0));
+        let eps = <T as Float>::epsilon();
+        let mut f0 = self.eval(&self.y.clone(), self.t);
+        let mut h = Float::min(self.initial_step_size(&f0), duration);
+        let identity = DMatrix::<T>::identity(n, n);
+
+        while self.t < self.t_end {
+            if self.stats.accepted_steps + self.stats.rejected_steps > self.n_max {
+                return Err(IntegrationError::MaxNumStepReached {
+                    x: nalgebra::convert(self.t),
+                    n_step: self.n_max,
+                });
+            }
+            if h <= c::<T>(10.0) * eps * Float::abs(self.t) || h == T::zero() {
+                return Err(IntegrationError::StepSizeUnderflow { x: nalgebra::convert(self.t) });
+            }
+            let last = self.t + h >= self.t_end;
+            if last {
+                h = self.t_end - self.t;
+            }
+
+            // Jacobian and time derivative of the vector field.
+            let jac = self.system.jacobian(&self.y, self.t);
+            let dt = Float::sqrt(eps) * Float::max(Float::abs(self.t), T::one());
+            let f_dt = (self.eval(&self.y.clone(), self.t + dt) - &f0) / dt;
+
+            let lu = (&identity - &jac * (h * d)).lu();
+            if !lu.is_invertible() {
+                self.stats.rejected_steps += 1;
+                h *= half;
+                continue;
+            }
+            let solve = |b: DVector<T>| lu.solve(&b).expect("Matrix should be invertible");
+
+            let k1 = solve(&f0 + &f_dt * (h * d));
+            let f1 = self.eval(&(&self.y + &k1 * (half * h)), self.t + half * h);
+            let k2 = solve(&f1 - &k1) + &k1;
+            let y_new = &self.y + &k2 * h;
+            let f2 = self.eval(&y_new, self.t + h);
+            let k3 = solve(&f2 - (&k2 - &f1) * e32 - (&k1 - &f0) * two + &f_dt * (h * d));
+
+            let err_vec = (&k1 - &k2 * two + &k3) * (h / c(6.0));
+            let err = Float::sqrt(
+                err_vec
+                    .iter()
+                    .zip(self.y.iter().zip(y_new.iter()))
+                    .map(|(e, (y, y_new))| {
+                        let scale =
+                            self.atol + self.rtol * Float::max(Float::abs(*y), Float::abs(*y_new));
+                        Float::powi(*e / scale, 2)
+                    })
+                    .sum::<T>()
+                    / c(n as f64),
+            );
+
+            if Float::is_finite(err) && err <= T::one() {
+                self.stats.accepted_steps += 1;
+                let t_new = if last { self.t_end } else { self.t + h };
+
+                // Dense output at the requested output times within the step.
+                loop {
+                    let t_out = self.t_start + c::<T>(n_out as f64) * self.dt_out;
+                    let at_end = Float::abs(t_out - self.t_end) <= c::<T>(1e-6) * duration;
+                    if t_out > t_new && !(last && at_end) {
+                        break;
+                    }
+                    let s = Float::min(t_out, t_new) - self.t;
+                    let s = Float::min(Float::max(s / h, T::zero()), T::one());
+                    let y_out = &self.y
+                        + (&k1 * (s * (T::one() - s)) + &k2 * (s * (s - two * d)))
+                            * (h / (T::one() - two * d));
+                    self.results.push(Float::min(t_out, self.t_end), y_out);
+                    n_out += 1;
+                    if at_end {
+                        break;
+                    }
+                }
+
+                self.t = t_new;
+                self.y = y_new;
+                f0 = f2;
+
+                if let Some(stop) = self.stop.as_mut() {
+                    let (t_out, y_out) = self.results.get();
+                    if stop(*t_out.last().unwrap(), y_out.last().unwrap()) {
+                        break;
+                    }
+                }
+            } else {
+                self.stats.rejected_steps += 1;
+            }
+
+            let factor = if !Float::is_finite(err) {
+                c(0.2)
+            } else if err == T::zero() {
+                c(5.0)
+            } else {
+                let factor = c::<T>(0.8) * Float::powf(err, c(-1.0 / 3.0));
+                Float::min(Float::max(factor, c(0.2)), c(5.0))
+            };
+            h *= if err <= T::one() {
+                factor
+            } else {
+                Float::min(factor, T::one())
+            };
+        }
+        Ok(self.stats)
+    }
+
+    fn eval(&mut self, y: &DVector<T>, t: T) -> DVector<T> {
+        self.stats.num_eval += 1;
+        self.system.eval_vector_field(y, t)
+    }
+
+    /// Computes an initial step size from the scale of the state and derivative.
+    fn initial_step_size(&self, f0: &DVector<T>) -> T {
+        let norm = |v: &DVector<T>| {
+            let sum: T = v
+                .iter()
+                .zip(self.y.iter())
+                .map(|(v, y)| Float::powi(*v / (self.atol + self.rtol * Float::abs(*y)), 2))
+                .sum();
+            Float::sqrt(sum / c(v.len() as f64))
+        };
+        let (d0, d1) = (norm(&self.y), norm(f0));
+        if d0 < c(1e-5) || d1 < c(1e-5) {
+            c(1e-6)
+        } else {
+            c::<T>(0.01) * d0 / d1
+        }
+    }
+}
+
+impl<Sys, T> From<Rosenbrock23<'_, Sys, T>> for SolverResult<T, DVector<T>> {
+    fn from(stepper: Rosenbrock23<'_, Sys, T>) -> Self {
+        stepper.results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::DVector;
+
+    use super::super::{ODEProblem, PolynomialSystem};
+    use crate::zero::alg::Polynomial;
+
+    /// Robertson's chemical kinetics problem, a classic stiff test problem.
+    fn robertson() -> PolynomialSystem<char, f32, u8> {
+        let var = |c: char| Polynomial::<_, f32, u8>::generator(c);
+        let terms = [
+            ('A', var('A') * -0.04),
+            ('A', var('B') * var('C') * 1e4),
+            ('B', var('A') * 0.04),
+            ('B', var('B') * var('C') * -1e4),
+            ('B', var('B') * var('B') * -3e7),
+            ('C', var('B') * var('B') * 3e7),
+        ];
+        terms.into_iter().collect()
+    }
+
+    #[test]
+    fn robertson_problem() {
+        let x0 = DVector::from_column_slice(&[1.0f32, 0.0, 0.0]);
+        let problem = ODEProblem::new(robertson().to_numerical(), x0).end_time(40.0);
+
+        // Stiffness only develops once the intermediate species is produced, which
+        // the stiffness check must probe for.
+        assert!(problem.is_stiff());
+        let x0 = DVector::from_column_slice(&[1.0, 1e-5, 0.0]);
+        assert!(ODEProblem::new(robertson().to_numerical(), x0).end_time(40.0).is_stiff());
+        let x0 = DVector::from_column_slice(&[1.0, 0.0, 0.0]);
+        assert!(!ODEProblem::new(robertson().to_numerical(), x0).end_time(1e-3).is_stiff());
+
+        let result = problem.solve_rosenbrock23(1.0).unwrap();
+        let (t_out, x_out) = result.get();
+        assert_eq!(t_out.len(), 41);
+        assert_eq!(*t_out.last().unwrap(), 40.0);
+
+        // Reference values from Hairer & Wanner, Section IV.10.
+        let x = x_out.last().unwrap();
+        assert!((x[0] - 0.7158).abs() < 1e-3);
+        assert!((x[2] - 0.2842).abs() < 1e-3);
+        // Total concentration is conserved.
+        assert!(x_out.iter().all(|x| (x.sum() - 1.0).abs() < 1e-3));
+    }
+
+    #[test]
+    fn constant_problem() {
+        let var = |c: char| Polynomial::<_, f32, u8>::generator(c);
+        let sys: PolynomialSystem<_, _, _> = [('A', var('A') * 0.0)].into_iter().collect();
+        let x0 = DVector::from_column_slice(&[1.0f32]);
+        let problem = ODEProblem::new(sys.to_numerical(), x0).end_time(10.0);
+
+        // The Jacobian is zero, which must not stall the eigenvalue computation.
+        assert!(!problem.is_stiff());
+    }
+}
@@ -0,0 +1,187 @@
+//! Forward sensitivity analysis of polynomial ODE systems.
+//!
+//! The *sensitivity* of the state `x` of an ODE system `dx/dt = f(x; p)` to a
+//! parameter `p_k` is the derivative `s_k = ∂x/∂p_k`. Differentiating the system
+//! with respect to the parameter shows that the sensitivities satisfy the linear
+//! ODEs:
+//!
+//! ```text
+//! ds_k/dt = J(x; p) s_k + ∂f/∂p_k(x; p),
+//! ```
+//!
+//! where `J` is the Jacobian of `f` with respect to `x`. Solving these equations
+//! alongside the original system is the *forward* method of sensitivity analysis.
+//! For polynomial systems, all of the derivatives are computed symbolically.
+
+use std::ops::{Add, Sub};
+
+use nalgebra::{DMatrix, DVector};
+use num_traits::{One, Pow, ToPrimitive, Zero};
+
+use super::{ODEScalar, ODESystem, cast_scalar};
+use crate::zero::alg::Polynomial;
+
+/// Sparse row of a matrix of polynomials, as pairs of column indices and entries.
+type SparseRow<Exp> = Vec<(usize, Polynomial<usize, f32, Exp>)>;
+
+/// A polynomial system augmented with its forward sensitivity equations.
+///
+/// The variables of the polynomials are indices, with the first indices
+/// referring to the state variables and the remaining ones to the parameters.
+/// Sensitivities are computed with respect to the first few parameters, called
+/// the *sensitive* parameters. The state of the augmented system is the state
+/// of the original system followed by the sensitivity vector for each sensitive
+/// parameter in turn.
+#[derive(Clone)]
+pub struct SensitivitySystem<Exp> {
+    num_states: usize,
+    parameters: Vec<f32>,
+    num_sensitive: usize,
+    components: Vec<Polynomial<usize, f32, Exp>>,
+    state_derivatives: Vec<SparseRow<Exp>>,
+    param_derivatives: Vec<Vec<Polynomial<usize, f32, Exp>>>,
+}
+
+impl<Exp> SensitivitySystem<Exp>
+where
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + ToPrimitive,
+{
+    /// Constructs a sensitivity system from the components of a vector field.
+    ///
+    /// The variables in the components below `num_states` are state variables
+    /// and the others are parameters, with values given by `parameters`.
+    /// Sensitivities are computed for the first `num_sensitive` parameters.
+    pub fn new(
+        components: Vec<Polynomial<usize, f32, Exp>>,
+        parameters: Vec<f32>,
+        num_sensitive: usize,
+    ) -> Self {
+        let num_states = components.len();
+        assert!(num_sensitive <= parameters.len(), "Sensitive parameters should have values");
+        let state_derivatives = components
+            .iter()
+            .map(|component| {
+                let mut vars: Vec<_> = component
+                    .monomials()
+                    .flat_map(|m| m.variables())
+                    .copied()
+                    .filter(|v| *v < num_states)
+                    .collect();
+                vars.sort();
+                vars.dedup();
+                vars.into_iter().map(|j| (j, component.partial_derivative(&j))).collect()
+            })
+            .collect();
+        let param_derivatives = components
+            .iter()
+            .map(|component| {
+                (0..num_sensitive)
+                    .map(|k| component.partial_derivative(&(num_states + k)))
+                    .collect()
+            })
+            .collect();
+        Self {
+            num_states,
+            parameters,
+            num_sensitive,
+            components,
+            state_derivatives,
+            param_derivatives,
+        }
+    }
+}
+
+impl<Exp> SensitivitySystem<Exp> {
+    /// Number of state variables in the original system.
+    pub fn num_states(&self) -> usize {
+        self.num_states
+    }
+
+    /// Number of parameters for which sensitivities are computed.
+    pub fn num_sensitive(&self) -> usize {
+        self.num_sensitive
+    }
+
+    /// Values of the sensitive parameters.
+    pub fn sensitive_parameters(&self) -> &[f32] {
+        &self.parameters[..self.num_sensitive]
+    }
+
+    /// Sets the values of the sensitive parameters.
+    pub fn set_sensitive_parameters(&mut self, values: &[f32]) {
+        self.parameters[..self.num_sensitive].copy_from_slice(values);
+    }
+
+    /// Augments initial values for the original system with zero sensitivities.
+    ///
+    /// This assumes that the initial values do not depend on the parameters.
+    pub fn initial_values(&self, x0: &DVector<f32>) -> DVector<f32> {
+        let mut y0 = DVector::zeros(self.num_states * (1 + self.num_sensitive));
+        y0.rows_mut(0, self.num_states).copy_from(x0);
+        y0
+    }
+
+    /// Splits a state of the augmented system into the original state and the
+    /// matrix of sensitivities, with one column per sensitive parameter.
+    pub fn split(&self, y: &DVector<f32>) -> (DVector<f32>, DMatrix<f32>) {
+        let n = self.num_states;
+        let x = y.rows(0, n).into_owned();
+        let s = DMatrix::from_column_slice(n, self.num_sensitive, &y.as_slice()[n..]);
+        (x, s)
+    }
+}
+
+impl<Exp, T> ODESystem<T> for SensitivitySystem<Exp>
+where
+    Exp: Clone + Ord,
+    T: ODEScalar + Pow<Exp, Output = T>,
+{
+    fn vector_field(&self, dy: &mut DVector<T>, y: &DVector<T>, _t: T) {
+        let n = self.num_states;
+        let value = |v: &usize| {
+            if *v < n {
+                y[*v]
+            } else {
+                cast_scalar(self.parameters[*v - n])
+            }
+        };
+        let eval = |poly: &Polynomial<usize, f32, Exp>| {
+            poly.eval_with_scalars(value, |coef| cast_scalar(*coef))
+        };
+        for i in 0..n {
+            dy[i] = eval(&self.components[i]);
+        }
+        for (i, row) in self.state_derivatives.iter().enumerate() {
+            let row: Vec<_> = row.iter().map(|(j, d)| (*j, eval(d))).collect();
+            for (k, d_param) in self.param_derivatives[i].iter().enumerate() {
+                let s_k = &y.as_slice()[n * (k + 1)..n * (k + 2)];
+                let jac_s: T = row.iter().map(|(j, d)| *d * s_k[*j]).sum();
+                dy[n * (k + 1) + i] = jac_s + eval(d_param);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ODEProblem;
+    use super::*;
+
+    #[test]
+    fn exponential_decay() {
+        // dx/dt = -k x, so that x = x0 exp(-k t) and dx/dk = -t x.
+        let x = Polynomial::<usize, f32, i8>::generator(0);
+        let k = Polynomial::<usize, f32, i8>::generator(1);
+        let sys = SensitivitySystem::new(vec![-(k * x)], vec![0.5], 1);
+        let y0 = sys.initial_values(&DVector::from_element(1, 2.0));
+        let problem = ODEProblem::new(sys.clone(), y0).end_time(2.0);
+
+        let states = problem.solve_at_times(&[1.0, 2.0]).unwrap();
+        for (t, y) in [1.0f32, 2.0].into_iter().zip(states) {
+            let (x, s) = sys.split(&y);
+            let expected = 2.0 * (-0.5 * t).exp();
+            assert!((x[0] - expected).abs() < 1e-4);
+            assert!((s[(0, 0)] + t * expected).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,901 @@
+//! Functorial data migration between instances of schemas.
+//!
+//! A [mapping of schemas](DiscreteDblModelMapping) `F: C → D` induces three
+//! ways of moving [instances](Instance) between the two schemas
+//! ([Spivak 2012](crate::refs::FunctorialDataMigration)):
+//!
+//! - the *pullback* `Δ_F`, taking instances of `D` to instances of `C` by
+//!   precomposition with `F`;
+//! - the *left pushforward* `Σ_F`, its left adjoint, which takes instances of
+//!   `C` to instances of `D` by gluing together rows as dictated by `F`;
+//! - the *right pushforward* `Π_F`, its right adjoint, which takes instances of
+//!   `C` to instances of `D` by forming compatible families of rows.
+//!
+//! Pullback is always computable. The pushforwards of a finite instance can be
+//! infinite, for instance when the codomain schema has cycles of mappings that
+//! are not cut down by path equations, and they can be very large even when
+//! finite, so their computation is bounded by a maximum number of rows.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::instance::{AttrValue, Instance, InvalidInstance, PathValue};
+use crate::dbl::model::{DiscreteDblModel, FpDblModel};
+use crate::dbl::model_morphism::{DblModelMorphism, DiscreteDblModelMapping};
+use crate::one::{Category, FgCategory, Path, QualifiedPath, graph::FinGraph};
+use crate::validate::Validate;
+use crate::zero::{Mapping, QualifiedName, name};
+
+/// A failure to migrate an instance along a mapping of schemas.
+#[derive(Clone, Debug, Error, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "tag", content = "content"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum DataMigrationError {
+    /// The mapping is not a valid morphism between the schemas.
+    #[error("Mapping is not a valid morphism of schemas")]
+    Mapping,
+
+    /// The instance to be migrated is not a valid instance of its schema.
+    #[error("Instance is not valid: {}", .0.iter().join("; "))]
+    Instance(Vec<InvalidInstance>),
+
+    /// A mapping or attribute is sent to a path that cannot be evaluated on
+    /// instances, such as one passing through a morphism between attribute
+    /// types.
+    #[error("`{0}` is sent to a path that cannot be evaluated on instances")]
+    Path(QualifiedName),
+
+    /// An attribute would have two different values at the same row.
+    #[error("Row {row} of `{attribute}` would have conflicting values")]
+    Conflict {
+        /// ID of the attribute in the target schema.
+        attribute: QualifiedName,
+        /// Row of the entity in the migrated instance.
+        row: usize,
+    },
+
+    /// An entity would have more rows than allowed, possibly infinitely many.
+    #[error("Entity `{0}` would have too many rows")]
+    TooLarge(QualifiedName),
+
+    /// An entity has more paths out of it than allowed, possibly infinitely
+    /// many, so that its right pushforward cannot be computed.
+    #[error("Entity `{0}` has too many paths out of it")]
+    TooManyPaths(QualifiedName),
+}
+
+/// Data migration along a mapping of schemas.
+pub struct DataMigration<'a> {
+    mapping: &'a DiscreteDblModelMapping,
+    dom: &'a DiscreteDblModel,
+    cod: &'a DiscreteDblModel,
+    max_rows: usize,
+    max_paths: usize,
+}
+
+impl<'a> DataMigration<'a> {
+    /// Constructs a data migration along a mapping from one schema to another.
+    pub fn new(
+        mapping: &'a DiscreteDblModelMapping,
+        dom: &'a DiscreteDblModel,
+        cod: &'a DiscreteDblModel,
+    ) -> Self {
+        Self {
+            mapping,
+            dom,
+            cod,
+            max_rows: 10_000,
+            max_paths: 1_000,
+        }
+    }
+
+    /// Sets the maximum number of rows of an entity in a pushforward.
+    pub fn max_rows(mut self, n: usize) -> Self {
+        self.max_rows = n;
+        self
+    }
+
+    /// Sets the maximum number of distinct paths out of an entity that are
+    /// considered when computing a right pushforward.
+    pub fn max_paths(mut self, n: usize) -> Self {
+        self.max_paths = n;
+        self
+    }
+
+    /// Pulls back an instance of the codomain schema to the domain schema.
+    ///
+    /// Each entity gets the rows of its image, and each mapping or attribute
+    /// the values of its image path.
+    pub fn delta(&self, instance: &Instance) -> Result<Instance, DataMigrationError> {
+        self.validate(instance, self.cod)?;
+        let mut result = Instance::default();
+        for x in entities(self.dom) {
+            let n = instance.num_rows(&self.ob_map(&x));
+            for f in columns(self.dom, &x) {
+                let path = self.mor_map(&f);
+                let values = (0..n).map(|row| instance.eval_path(&path, row));
+                let error = || DataMigrationError::Path(f.clone());
+                if is_fk(self.dom, &f) {
+                    let column = values
+                        .map(|value| match value {
+                            Some(PathValue::Row(i)) => Some(i),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(error)?;
+                    result.foreign_keys.insert(f, column);
+                } else {
+                    let column = values
+                        .map(|value| match value {
+                            Some(PathValue::Value(v)) => Some(v.clone()),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(error)?;
+                    result.attributes.insert(f, column);
+                }
+            }
+            result.rows.insert(x, n);
+        }
+        Ok(result)
+    }
+
+    /// Pushes forward an instance of the domain schema along the mapping, as
+    /// the left adjoint of pullback.
+    ///
+    /// Each row of the instance becomes a row of the image of its entity, and
+    /// fresh rows are added wherever a mapping of the codomain schema has no
+    /// value. Rows are then identified as required by the mapping and by the
+    /// path equations of the codomain schema, until no more identifications are
+    /// needed. This procedure is known as the *chase*. Attribute values are
+    /// carried along and propagated across path equations, and values that are
+    /// not determined in this way are null.
+    pub fn sigma(&self, instance: &Instance) -> Result<Instance, DataMigrationError> {
+        self.validate(instance, self.dom)?;
+        let mut chase = Chase::new(self.cod, self.max_rows);
+
+        let mut generators: HashMap<QualifiedName, Vec<usize>> = HashMap::new();
+        for x in entities(self.dom) {
+            let y = self.ob_map(&x);
+            let elems: Result<Vec<_>, _> =
+                (0..instance.num_rows(&x)).map(|_| chase.add(&y)).collect();
+            generators.insert(x, elems?);
+        }
+        for x in entities(self.dom) {
+            for f in columns(self.dom, &x).into_iter().filter(|f| is_fk(self.dom, f)) {
+                let path = self.mor_map(&f);
+                let targets = &generators[&self.dom.mor_generator_cod(&f)];
+                for (row, elem) in generators[&x].iter().enumerate() {
+                    let lhs = chase.follow(*elem, path.iter())?;
+                    chase.union(lhs, targets[instance.foreign_keys[&f][row]]);
+                }
+            }
+        }
+        chase.run()?;
+        let (mut result, index) = chase.instance();
+
+        // Assign the attribute values carried along from the instance.
+        let mut values: HashMap<(usize, QualifiedName), AttrValue> = HashMap::new();
+        for x in entities(self.dom) {
+            for alpha in columns(self.dom, &x).into_iter().filter(|f| !is_fk(self.dom, f)) {
+                let (prefix, attr) = split_attr_path(self.cod, &self.mor_map(&alpha))
+                    .ok_or_else(|| DataMigrationError::Path(alpha.clone()))?;
+                for (row, value) in instance.attributes[&alpha].iter().enumerate() {
+                    if !matches!(value, AttrValue::Null) {
+                        let elem = chase.follow(generators[&x][row], prefix.iter())?;
+                        assign_value(&mut values, &index, chase.find(elem), &attr, value)?;
+                    }
+                }
+            }
+        }
+
+        // Propagate values across the path equations between attributes.
+        let equations: Vec<_> = self
+            .cod
+            .category
+            .equations()
+            .filter_map(|eq| {
+                Some((
+                    self.cod.dom(&eq.lhs),
+                    split_attr_path(self.cod, &eq.lhs)?,
+                    split_attr_path(self.cod, &eq.rhs)?,
+                ))
+            })
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (src, (lprefix, lattr), (rprefix, rattr)) in &equations {
+                for elem in chase.roots_of(src) {
+                    let lhs = chase.follow(elem, lprefix.iter())?;
+                    let rhs = chase.follow(elem, rprefix.iter())?;
+                    let lkey = (chase.find(lhs), lattr.clone());
+                    let rkey = (chase.find(rhs), rattr.clone());
+                    if let Some(value) = values.get(&lkey).cloned() {
+                        changed |= assign_value(&mut values, &index, rkey.0, rattr, &value)?;
+                    } else if let Some(value) = values.get(&rkey).cloned() {
+                        changed |= assign_value(&mut values, &index, lkey.0, lattr, &value)?;
+                    }
+                }
+            }
+        }
+
+        for y in entities(self.cod) {
+            for a in columns(self.cod, &y).into_iter().filter(|f| !is_fk(self.cod, f)) {
+                let mut column = vec![AttrValue::Null; result.num_rows(&y)];
+                for elem in chase.roots_of(&y) {
+                    if let Some(value) = values.get(&(elem, a.clone())) {
+                        column[index[elem]] = value.clone();
+                    }
+                }
+                result.attributes.insert(a, column);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Pushes forward an instance of the domain schema along the mapping, as
+    /// the right adjoint of pullback.
+    ///
+    /// A row of an entity `d` of the codomain schema is a family of rows of the
+    /// instance, one for each entity `c` of the domain schema and each path
+    /// from `d` to the image of `c`, that is compatible with the mappings of
+    /// the instance and agrees on attributes that the path equations of the
+    /// codomain schema identify. Paths out of `d` are enumerated up to the path
+    /// equations, so there must be finitely many of them. Attributes of `d` get
+    /// the value of the family at a path equal to the attribute, or null if
+    /// there is none.
+    pub fn pi(&self, instance: &Instance) -> Result<Instance, DataMigrationError> {
+        self.validate(instance, self.dom)?;
+        let families: HashMap<_, _> = entities(self.cod)
+            .into_iter()
+            .map(|d| Ok((d.clone(), self.families(instance, &d)?)))
+            .collect::<Result<_, DataMigrationError>>()?;
+
+        let mut result = Instance::default();
+        for (d, fam) in families.iter() {
+            result.rows.insert(d.clone(), fam.rows.len());
+            for h in columns(self.cod, d) {
+                if is_fk(self.cod, &h) {
+                    // Object of the family at `d` restricting each object at `d'`.
+                    let target = &families[&self.cod.mor_generator_cod(&h)];
+                    let restriction: Vec<_> = target
+                        .objects
+                        .iter()
+                        .map(|(c, j)| {
+                            let path = self
+                                .cod
+                                .compose2(Path::single(h.clone()), target.paths[*j].clone());
+                            let k = fam
+                                .find_path(self.cod, &path)
+                                .expect("Paths should be closed under composition");
+                            fam.object_index[&(c.clone(), k)]
+                        })
+                        .collect();
+                    let column = fam
+                        .rows
+                        .iter()
+                        .map(|x| {
+                            let y: Vec<_> = restriction.iter().map(|o| x[*o]).collect();
+                            target.row_index[&y]
+                        })
+                        .collect();
+                    result.foreign_keys.insert(h, column);
+                } else {
+                    let path = Path::single(h.clone());
+                    let group = fam.groups.iter().find(|(p, _)| equal_paths(self.cod, p, &path));
+                    let column = fam
+                        .rows
+                        .iter()
+                        .map(|x| match group {
+                            Some((_, members)) => group_value(instance, members, x)
+                                .cloned()
+                                .unwrap_or(AttrValue::Null),
+                            None => AttrValue::Null,
+                        })
+                        .collect();
+                    result.attributes.insert(h, column);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Computes the compatible families of rows at an entity of the codomain.
+    fn families(
+        &self,
+        instance: &Instance,
+        d: &QualifiedName,
+    ) -> Result<Families, DataMigrationError> {
+        let mut fam = Families::default();
+
+        // Enumerate the paths out of the entity, up to equality.
+        fam.paths.push(Path::Id(d.clone()));
+        let mut i = 0;
+        while i < fam.paths.len() {
+            let path = fam.paths[i].clone();
+            i += 1;
+            for g in columns(self.cod, &self.cod.cod(&path)) {
+                if !is_fk(self.cod, &g) {
+                    continue;
+                }
+                let next = self.cod.compose2(path.clone(), Path::single(g));
+                if fam.find_path(self.cod, &next).is_none() {
+                    if fam.paths.len() >= self.max_paths {
+                        return Err(DataMigrationError::TooManyPaths(d.clone()));
+                    }
+                    fam.paths.push(next);
+                }
+            }
+        }
+
+        // Objects of the comma category, with the constraints imposed by the
+        // mappings of the instance and the groups of identified attributes.
+        for (j, path) in fam.paths.iter().enumerate() {
+            let y = self.cod.cod(path);
+            for c in entities(self.dom).into_iter().filter(|c| self.ob_map(c) == y) {
+                fam.object_index.insert((c.clone(), j), fam.objects.len());
+                fam.objects.push((c, j));
+            }
+        }
+        let mut constraints = vec![Vec::new(); fam.objects.len()];
+        for (o, (c, j)) in fam.objects.clone().into_iter().enumerate() {
+            for f in columns(self.dom, &c) {
+                let path = self.cod.compose2(fam.paths[j].clone(), self.mor_map(&f));
+                if is_fk(self.dom, &f) {
+                    let k = fam
+                        .find_path(self.cod, &path)
+                        .expect("Paths should be closed under composition");
+                    let target = fam.object_index[&(self.dom.mor_generator_cod(&f), k)];
+                    constraints[o].push((f, target));
+                } else if let Some((_, members)) =
+                    fam.groups.iter_mut().find(|(p, _)| equal_paths(self.cod, p, &path))
+                {
+                    members.push((o, f));
+                } else {
+                    fam.groups.push((path, vec![(o, f)]));
+                }
+            }
+        }
+
+        // Enumerate the families by backtracking, propagating the constraints
+        // and pruning partial families whose attributes already disagree.
+        let mut stack = vec![vec![None; fam.objects.len()]];
+        while let Some(partial) = stack.pop() {
+            let Some(o) = partial.iter().position(|r| r.is_none()) else {
+                if fam.rows.len() >= self.max_rows {
+                    return Err(DataMigrationError::TooLarge(d.clone()));
+                }
+                let family: Vec<_> = partial.into_iter().flatten().collect();
+                fam.row_index.insert(family.clone(), fam.rows.len());
+                fam.rows.push(family);
+                continue;
+            };
+            let n = instance.num_rows(&fam.objects[o].0);
+            'rows: for row in (0..n).rev() {
+                let mut next = partial.clone();
+                next[o] = Some(row);
+                let mut queue = vec![o];
+                while let Some(u) = queue.pop() {
+                    for (f, v) in &constraints[u] {
+                        let value = instance.foreign_keys[f][next[u].unwrap()];
+                        match next[*v] {
+                            None => {
+                                next[*v] = Some(value);
+                                queue.push(*v);
+                            }
+                            Some(current) if current != value => continue 'rows,
+                            Some(_) => {}
+                        }
+                    }
+                }
+                if groups_agree(instance, &fam.groups, &next) {
+                    stack.push(next);
+                }
+            }
+        }
+        Ok(fam)
+    }
+
+    fn validate(
+        &self,
+        instance: &Instance,
+        model: &DiscreteDblModel,
+    ) -> Result<(), DataMigrationError> {
+        DblModelMorphism(self.mapping, self.dom, self.cod)
+            .validate()
+            .map_err(|_| DataMigrationError::Mapping)?;
+        instance
+            .validate_in(model)
+            .map_err(|errs| DataMigrationError::Instance(errs.into()))
+    }
+
+    fn ob_map(&self, x: &QualifiedName) -> QualifiedName {
+        self.mapping
+            .0
+            .ob_generator_map
+            .apply_to_ref(x)
+            .expect("Mapping should be valid")
+    }
+
+    fn mor_map(&self, f: &QualifiedName) -> QualifiedPath {
+        self.mapping
+            .0
+            .mor_generator_map
+            .apply_to_ref(f)
+            .expect("Mapping should be valid")
+    }
+}
+
+/// The compatible families of rows at an entity, in a right pushforward.
+#[derive(Default)]
+struct Families {
+    /// Paths out of the entity to other entities, up to equality.
+    paths: Vec<QualifiedPath>,
+    /// Entities of the domain whose image is the target of a path.
+    objects: Vec<(QualifiedName, usize)>,
+    object_index: HashMap<(QualifiedName, usize), usize>,
+    /// Attributes of objects that are sent to equal paths.
+    groups: Vec<(QualifiedPath, Vec<(usize, QualifiedName)>)>,
+    /// The families, each assigning a row to every object.
+    rows: Vec<Vec<usize>>,
+    row_index: HashMap<Vec<usize>, usize>,
+}
+
+impl Families {
+    fn find_path(&self, model: &DiscreteDblModel, path: &QualifiedPath) -> Option<usize> {
+        self.paths.iter().position(|p| equal_paths(model, p, path))
+    }
+}
+
+/// State of the chase, used to compute a left pushforward.
+///
+/// Elements are rows under construction, which are merged using a union-find
+/// structure. The row of each element is its root, the least element merged
+/// with it.
+struct Chase<'a> {
+    model: &'a DiscreteDblModel,
+    max_rows: usize,
+    entities: Vec<QualifiedName>,
+    parents: Vec<usize>,
+    num_roots: HashMap<QualifiedName, usize>,
+    /// Values of the mappings of the schema at elements.
+    maps: HashMap<(usize, QualifiedName), usize>,
+}
+
+impl<'a> Chase<'a> {
+    fn new(model: &'a DiscreteDblModel, max_rows: usize) -> Self {
+        Self {
+            model,
+            max_rows,
+            entities: Vec::new(),
+            parents: Vec::new(),
+            num_roots: HashMap::new(),
+            maps: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, x: &QualifiedName) -> Result<usize, DataMigrationError> {
+        let n = self.num_roots.entry(x.clone()).or_default();
+        if *n >= self.max_rows {
+            return Err(DataMigrationError::TooLarge(x.clone()));
+        }
+        *n += 1;
+        self.entities.push(x.clone());
+        self.parents.push(self.parents.len());
+        Ok(self.parents.len() - 1)
+    }
+
+    fn find(&mut self, mut elem: usize) -> usize {
+        while self.parents[elem] != elem {
+            self.parents[elem] = self.parents[self.parents[elem]];
+            elem = self.parents[elem];
+        }
+        elem
+    }
+
+    /// Merges two elements, returning whether they were distinct.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        let (root, child) = (a.min(b), a.max(b));
+        self.parents[child] = root;
+        *self.num_roots.get_mut(&self.entities[root]).unwrap() -= 1;
+        true
+    }
+
+    /// Follows a sequence of mappings from an element, adding fresh elements
+    /// where values are missing.
+    fn follow<'b>(
+        &mut self,
+        mut elem: usize,
+        path: impl Iterator<Item = &'b QualifiedName>,
+    ) -> Result<usize, DataMigrationError> {
+        for g in path {
+            let key = (self.find(elem), g.clone());
+            elem = match self.maps.get(&key) {
+                Some(target) => *target,
+                None => {
+                    let target = self.add(&self.model.mor_generator_cod(g))?;
+                    self.maps.insert(key, target);
+                    target
+                }
+            };
+        }
+        Ok(elem)
+    }
+
+    fn roots_of(&mut self, x: &QualifiedName) -> Vec<usize> {
+        (0..self.parents.len())
+            .filter(|e| self.find(*e) == *e && self.entities[*e] == *x)
+            .collect()
+    }
+
+    /// Runs the chase until the mappings are total, well defined, and satisfy
+    /// the path equations between entities.
+    fn run(&mut self) -> Result<(), DataMigrationError> {
+        let equations: Vec<_> = self
+            .model
+            .category
+            .equations()
+            .filter(|eq| eq.lhs.iter().chain(eq.rhs.iter()).all(|g| is_fk(self.model, g)))
+            .cloned()
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let maps = std::mem::take(&mut self.maps);
+            for ((elem, g), target) in maps.into_iter().sorted() {
+                let (elem, target) = (self.find(elem), self.find(target));
+                match self.maps.get(&(elem, g.clone())) {
+                    Some(other) => changed |= self.union(target, *other),
+                    None => {
+                        self.maps.insert((elem, g), target);
+                    }
+                }
+            }
+            for elem in 0..self.parents.len() {
+                if self.find(elem) != elem {
+                    continue;
+                }
+                for g in columns(self.model, &self.entities[elem]) {
+                    if is_fk(self.model, &g) && !self.maps.contains_key(&(elem, g.clone())) {
+                        self.follow(elem, std::iter::once(&g))?;
+                        changed = true;
+                    }
+                }
+            }
+            for eq in &equations {
+                for elem in self.roots_of(&self.model.dom(&eq.lhs)) {
+                    let lhs = self.follow(elem, eq.lhs.iter())?;
+                    let rhs = self.follow(elem, eq.rhs.iter())?;
+                    changed |= self.union(lhs, rhs);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Extracts the instance built by the chase, without attributes, along
+    /// with the row of each element.
+    fn instance(&mut self) -> (Instance, Vec<usize>) {
+        let mut result = Instance::default();
+        for y in entities(self.model) {
+            result.rows.insert(y, 0);
+        }
+        let mut root_index = HashMap::new();
+        for elem in 0..self.parents.len() {
+            if self.find(elem) == elem {
+                let n = result.rows.get_mut(&self.entities[elem]).unwrap();
+                root_index.insert(elem, *n);
+                *n += 1;
+            }
+        }
+        let index: Vec<_> =
+            (0..self.parents.len()).map(|elem| root_index[&self.find(elem)]).collect();
+        for y in entities(self.model) {
+            for g in columns(self.model, &y) {
+                if !is_fk(self.model, &g) {
+                    continue;
+                }
+                let mut column = vec![0; result.num_rows(&y)];
+                for elem in self.roots_of(&y) {
+                    column[index[elem]] = index[self.maps[&(elem, g.clone())]];
+                }
+                result.foreign_keys.insert(g, column);
+            }
+        }
+        (result, index)
+    }
+}
+
+/// Assigns a value to an attribute at a row of the chase, returning whether the
+/// value is new.
+fn assign_value(
+    values: &mut HashMap<(usize, QualifiedName), AttrValue>,
+    index: &[usize],
+    elem: usize,
+    attr: &QualifiedName,
+    value: &AttrValue,
+) -> Result<bool, DataMigrationError> {
+    match values.get(&(elem, attr.clone())) {
+        Some(current) if current != value => Err(DataMigrationError::Conflict {
+            attribute: attr.clone(),
+            row: index[elem],
+        }),
+        Some(_) => Ok(false),
+        None => {
+            values.insert((elem, attr.clone()), value.clone());
+            Ok(true)
+        }
+    }
+}
+
+/// Entities of a schema, in a fixed order.
+fn entities(model: &DiscreteDblModel) -> Vec<QualifiedName> {
+    model.ob_generators_with_type(&name("Entity")).sorted().collect()
+}
+
+/// Mappings and attributes out of an entity, in a fixed order.
+fn columns(model: &DiscreteDblModel, x: &QualifiedName) -> Vec<QualifiedName> {
+    model.generating_graph().out_edges(x).sorted().collect()
+}
+
+/// Whether a morphism generator is a mapping between entities.
+fn is_fk(model: &DiscreteDblModel, f: &QualifiedName) -> bool {
+    model.ob_generator_type(&model.mor_generator_cod(f)) == name("Entity")
+}
+
+/// Splits a path into mappings between entities followed by an attribute.
+fn split_attr_path(
+    model: &DiscreteDblModel,
+    path: &QualifiedPath,
+) -> Option<(Vec<QualifiedName>, QualifiedName)> {
+    let mut edges: Vec<_> = path.iter().cloned().collect();
+    let attr = edges.pop()?;
+    let entity = name("Entity");
+    (edges.iter().all(|g| is_fk(model, g))
+        && model.ob_generator_type(&model.mor_generator_dom(&attr)) == entity
+        && !is_fk(model, &attr))
+    .then_some((edges, attr))
+}
+
+fn equal_paths(model: &DiscreteDblModel, p: &QualifiedPath, q: &QualifiedPath) -> bool {
+    model.cod(p) == model.cod(q) && model.category.morphisms_are_equal(p.clone(), q.clone())
+}
+
+/// Whether the rows assigned in a partial family agree on the attributes in
+/// each group, ignoring nulls.
+fn groups_agree(
+    instance: &Instance,
+    groups: &[(QualifiedPath, Vec<(usize, QualifiedName)>)],
+    partial: &[Option<usize>],
+) -> bool {
+    groups.iter().all(|(_, members)| {
+        let mut values = members
+            .iter()
+            .filter_map(|(o, alpha)| partial[*o].map(|row| &instance.attributes[alpha][row]))
+            .filter(|value| !matches!(value, AttrValue::Null));
+        values.next().is_none_or(|first| values.all(|value| value == first))
+    })
+}
+
+/// The common value of a group of attributes in a family, if not null.
+fn group_value<'a>(
+    instance: &'a Instance,
+    members: &[(usize, QualifiedName)],
+    family: &[usize],
+) -> Option<&'a AttrValue> {
+    members
+        .iter()
+        .map(|(o, alpha)| &instance.attributes[alpha][family[*o]])
+        .find(|value| !matches!(value, AttrValue::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::one::PathEq;
+    use crate::stdlib::th_schema;
+    use crate::tt;
+
+    fn schema(text: &str) -> DiscreteDblModel {
+        let th = Rc::new(th_schema());
+        let model = tt::modelgen::Model::from_text(&th.into(), text);
+        model.unwrap().as_discrete().unwrap()
+    }
+
+    fn text(values: &[&str]) -> Vec<AttrValue> {
+        values.iter().map(|s| AttrValue::Text(s.to_string())).collect()
+    }
+
+    #[test]
+    fn delta() {
+        let dom = schema("[E : Entity, grand : (Hom Entity)[E, E], S : AttrType, n : Attr[E, S]]");
+        let cod = schema(
+            "[Emp : Entity, manager : (Hom Entity)[Emp, Emp], Str : AttrType, name : Attr[Emp, Str]]",
+        );
+        let (manager, name_) = (name("manager"), name("name"));
+        let mapping = DiscreteDblModelMapping::new(
+            [(name("E"), name("Emp")), (name("S"), name("Str"))],
+            [
+                (name("grand"), Path::pair(manager.clone(), manager.clone())),
+                (name("n"), Path::pair(manager.clone(), name_.clone())),
+            ],
+        );
+        let instance = Instance {
+            rows: [(name("Emp"), 3)].into(),
+            foreign_keys: [(manager, vec![1, 2, 2])].into(),
+            attributes: [(name_, text(&["a", "b", "c"]))].into(),
+        };
+        let result = DataMigration::new(&mapping, &dom, &cod).delta(&instance).unwrap();
+        assert_eq!(result.num_rows(&name("E")), 3);
+        assert_eq!(result.foreign_keys[&name("grand")], vec![2, 2, 2]);
+        assert_eq!(result.attributes[&name("n")], text(&["b", "c", "c"]));
+        assert!(result.validate_in(&dom).is_ok());
+
+        let bad = Instance {
+            rows: [(name("Emp"), 1)].into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            DataMigration::new(&mapping, &dom, &cod).delta(&bad),
+            Err(DataMigrationError::Instance(_))
+        ));
+    }
+
+    #[test]
+    fn sigma() {
+        // Quotient of the rows of two entities sent to a single entity.
+        let dom = schema(
+            "[
+                A : Entity, B : Entity, f : (Hom Entity)[A, B],
+                S : AttrType, a : Attr[A, S], b : Attr[B, S],
+            ]",
+        );
+        let cod = schema("[X : Entity, S : AttrType, x : Attr[X, S]]");
+        let mapping = DiscreteDblModelMapping::new(
+            [(name("A"), name("X")), (name("B"), name("X")), (name("S"), name("S"))],
+            [
+                (name("f"), Path::Id(name("X"))),
+                (name("a"), Path::single(name("x"))),
+                (name("b"), Path::single(name("x"))),
+            ],
+        );
+        let mut instance = Instance {
+            rows: [(name("A"), 2), (name("B"), 2)].into(),
+            foreign_keys: [(name("f"), vec![0, 0])].into(),
+            attributes: [(name("a"), text(&["p", "p"])), (name("b"), text(&["p", "q"]))].into(),
+        };
+        let migration = DataMigration::new(&mapping, &dom, &cod);
+        let result = migration.sigma(&instance).unwrap();
+        assert_eq!(result.num_rows(&name("X")), 2);
+        assert_eq!(result.attributes[&name("x")], text(&["p", "q"]));
+
+        instance.attributes.insert(name("a"), text(&["p", "r"]));
+        assert_eq!(
+            migration.sigma(&instance),
+            Err(DataMigrationError::Conflict { attribute: name("x"), row: 0 })
+        );
+
+        // Fresh rows created by the chase, with and without a path equation.
+        let dom = schema("[A : Entity, S : AttrType, a : Attr[A, S]]");
+        let mut cod = schema(
+            "[
+                A : Entity, B : Entity, g : (Hom Entity)[A, B], s : (Hom Entity)[B, B],
+                S : AttrType, a : Attr[A, S], b : Attr[B, S],
+            ]",
+        );
+        let mapping = DiscreteDblModelMapping::new(
+            [(name("A"), name("A")), (name("S"), name("S"))],
+            [(name("a"), Path::single(name("a")))],
+        );
+        let instance = Instance {
+            rows: [(name("A"), 2)].into(),
+            attributes: [(name("a"), text(&["p", "q"]))].into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            DataMigration::new(&mapping, &dom, &cod).max_rows(100).sigma(&instance),
+            Err(DataMigrationError::TooLarge(name("B")))
+        );
+
+        let (g, s) = (name("g"), name("s"));
+        cod.add_equation(PathEq::new(Path::pair(s.clone(), s.clone()), Path::single(s.clone())));
+        cod.add_equation(PathEq::new(Path::pair(g.clone(), name("b")), Path::single(name("a"))));
+        let result = DataMigration::new(&mapping, &dom, &cod).sigma(&instance).unwrap();
+        assert_eq!(result.num_rows(&name("A")), 2);
+        assert_eq!(result.num_rows(&name("B")), 4);
+        assert_eq!(result.foreign_keys[&g], vec![0, 1]);
+        assert_eq!(result.foreign_keys[&s], vec![2, 3, 2, 3]);
+        assert_eq!(result.attributes[&name("b")][..2], text(&["p", "q"]));
+        assert_eq!(result.attributes[&name("b")][2], AttrValue::Null);
+        assert!(result.validate_in(&cod).is_ok());
+    }
+
+    #[test]
+    fn pi() {
+        // Product of two entities sent to a single entity.
+        let dom = schema("[A : Entity, B : Entity, S : AttrType, a : Attr[A, S], b : Attr[B, S]]");
+        let cod = schema("[X : Entity, S : AttrType, xa : Attr[X, S], xb : Attr[X, S]]");
+        let mapping = DiscreteDblModelMapping::new(
+            [(name("A"), name("X")), (name("B"), name("X")), (name("S"), name("S"))],
+            [(name("a"), Path::single(name("xa"))), (name("b"), Path::single(name("xb")))],
+        );
+        let instance = Instance {
+            rows: [(name("A"), 2), (name("B"), 3)].into(),
+            attributes: [(name("a"), text(&["p", "q"])), (name("b"), text(&["u", "v", "w"]))]
+                .into(),
+            ..Default::default()
+        };
+        let result = DataMigration::new(&mapping, &dom, &cod).pi(&instance).unwrap();
+        assert_eq!(result.num_rows(&name("X")), 6);
+        assert_eq!(result.attributes[&name("xa")], text(&["p", "p", "p", "q", "q", "q"]));
+        assert_eq!(result.attributes[&name("xb")], text(&["u", "v", "w", "u", "v", "w"]));
+
+        // Attributes sent to the same attribute are matched.
+        let cod = schema("[X : Entity, S : AttrType, xs : Attr[X, S]]");
+        let mapping = DiscreteDblModelMapping::new(
+            [(name("A"), name("X")), (name("B"), name("X")), (name("S"), name("S"))],
+            [(name("a"), Path::single(name("xs"))), (name("b"), Path::single(name("xs")))],
+        );
+        let instance = Instance {
+            rows: [(name("A"), 2), (name("B"), 3)].into(),
+            attributes: [(name("a"), text(&["p", "q"])), (name("b"), text(&["q", "r", "p"]))]
+                .into(),
+            ..Default::default()
+        };
+        let result = DataMigration::new(&mapping, &dom, &cod).pi(&instance).unwrap();
+        assert_eq!(result.num_rows(&name("X")), 2);
+        assert_eq!(result.attributes[&name("xs")], text(&["p", "q"]));
+
+        // Equalizer of rows related by a mapping sent to an identity.
+        let dom = schema("[A : Entity, B : Entity, f : (Hom Entity)[A, B]]");
+        let cod = schema("[X : Entity]");
+        let mapping = DiscreteDblModelMapping::new(
+            [(name("A"), name("X")), (name("B"), name("X"))],
+            [(name("f"), Path::Id(name("X")))],
+        );
+        let instance = Instance {
+            rows: [(name("A"), 3), (name("B"), 2)].into(),
+            foreign_keys: [(name("f"), vec![0, 1, 1])].into(),
+            ..Default::default()
+        };
+        let result = DataMigration::new(&mapping, &dom, &cod).pi(&instance).unwrap();
+        assert_eq!(result.num_rows(&name("X")), 3);
+
+        // Entity outside the image of the mapping, with a mapping into it.
+        let dom = schema("[B : Entity]");
+        let cod = schema("[X : Entity, Y : Entity, h : (Hom Entity)[X, Y]]");
+        let mapping = DiscreteDblModelMapping::new([(name("B"), name("Y"))], []);
+        let instance = Instance {
+            rows: [(name("B"), 2)].into(),
+            ..Default::default()
+        };
+        let result = DataMigration::new(&mapping, &dom, &cod).pi(&instance).unwrap();
+        assert_eq!(result.num_rows(&name("X")), 2);
+        assert_eq!(result.num_rows(&name("Y")), 2);
+        assert_eq!(result.foreign_keys[&name("h")], vec![0, 1]);
+        assert!(result.validate_in(&cod).is_ok());
+
+        // Cycle of mappings without path equations.
+        let cod = schema("[Y : Entity, s : (Hom Entity)[Y, Y]]");
+        assert_eq!(
+            DataMigration::new(&mapping, &dom, &cod).max_paths(10).pi(&instance),
+            Err(DataMigrationError::TooManyPaths(name("Y")))
+        );
+    }
+}
@@ -0,0 +1,424 @@
+//! Instance data for schemas.
+//!
+//! An *instance* of a schema, also known as a *C-set* or *copresheaf*, assigns
+//! to each entity a finite set of rows, to each mapping between entities a
+//! function between their rows, and to each attribute a function from the rows
+//! of its entity to values. The functions must satisfy the path equations of
+//! the schema. Instances are the data that populate the database described by
+//! the schema; see ([Spivak 2014](crate::refs::CTForTheSciences), Section 4.5).
+//!
+//! Here the rows of each entity are numbered consecutively from zero, and each
+//! function is stored as a column listing, for each row of its domain, the row
+//! or value that it maps to.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use nonempty::NonEmpty;
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use crate::dbl::model::{DiscreteDblModel, FpDblModel};
+use crate::one::{Category, FgCategory, Path, QualifiedPath};
+use crate::validate;
+use crate::zero::{QualifiedName, name};
+
+/// A value of an attribute.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum AttrValue {
+    /// A Boolean value.
+    Bool(bool),
+    /// An integer value.
+    Int(i64),
+    /// A floating point value.
+    Float(f64),
+    /// A text value.
+    Text(String),
+    /// An unknown value, as in SQL.
+    Null,
+}
+
+impl AttrValue {
+    /// Whether two values have the same kind, ignoring their contents.
+    ///
+    /// The null value has the same kind as every value.
+    pub fn same_kind(&self, other: &Self) -> bool {
+        matches!(self, AttrValue::Null)
+            || matches!(other, AttrValue::Null)
+            || std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// The value of a path in a schema at a row of its source entity.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathValue<'a> {
+    /// A row of the target entity of the path.
+    Row(usize),
+    /// An attribute value.
+    Value(&'a AttrValue),
+}
+
+/// An instance of a schema.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct Instance {
+    /// Map from entity IDs to numbers of rows.
+    ///
+    /// Entities not in the map have no rows.
+    pub rows: HashMap<QualifiedName, usize>,
+
+    /// Map from mapping IDs to foreign-key columns, listing for each row of the
+    /// domain entity a row of the codomain entity.
+    #[cfg_attr(feature = "serde", serde(rename = "foreignKeys", default))]
+    pub foreign_keys: HashMap<QualifiedName, Vec<usize>>,
+
+    /// Map from attribute IDs to columns, listing the value of the attribute at
+    /// each row of its entity.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: HashMap<QualifiedName, Vec<AttrValue>>,
+}
+
+/// A failure of an instance to be valid for a schema.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "tag", content = "content"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum InvalidInstance {
+    /// Rows are given for something that is not an entity of the schema.
+    #[error("`{0}` is not an entity of the schema")]
+    Entity(QualifiedName),
+
+    /// A column is given for something that is not a mapping or attribute of
+    /// the schema.
+    #[error("`{0}` is not a mapping or attribute of the schema")]
+    Column(QualifiedName),
+
+    /// A column is missing even though its entity has rows.
+    #[error("Column `{0}` is missing")]
+    MissingColumn(QualifiedName),
+
+    /// A column has the wrong number of rows.
+    #[error("Column `{column}` has {actual} rows but its entity has {expected}")]
+    ColumnLength {
+        /// ID of the mapping or attribute.
+        column: QualifiedName,
+        /// Number of rows of the entity.
+        expected: usize,
+        /// Number of rows of the column.
+        actual: usize,
+    },
+
+    /// A foreign key refers to a row that does not exist.
+    #[error("Row {row} of `{mapping}` refers to nonexistent row {target}")]
+    ForeignKey {
+        /// ID of the mapping.
+        mapping: QualifiedName,
+        /// Row of the domain entity.
+        row: usize,
+        /// Row referred to in the codomain entity.
+        target: usize,
+    },
+
+    /// An attribute value has a different kind than other values of the same
+    /// attribute type.
+    #[error("Row {row} of `{attribute}` has a value of the wrong type")]
+    AttrType {
+        /// ID of the attribute.
+        attribute: QualifiedName,
+        /// Row of the entity.
+        row: usize,
+    },
+
+    /// A row violates a path equation of the schema.
+    #[error("Row {row} of `{entity}` violates path equation {equation}")]
+    Equation {
+        /// Index of the path equation in the schema.
+        equation: usize,
+        /// ID of the source entity of the equation.
+        entity: QualifiedName,
+        /// Row of the source entity.
+        row: usize,
+    },
+}
+
+impl Instance {
+    /// Number of rows of an entity.
+    pub fn num_rows(&self, entity: &QualifiedName) -> usize {
+        self.rows.get(entity).copied().unwrap_or_default()
+    }
+
+    /// Evaluates a path in the schema at a row of its source entity.
+    ///
+    /// Returns `None` if the path passes through a column that is missing or
+    /// too short, or through a morphism whose values are not given by the
+    /// instance, such as a morphism between attribute types.
+    pub fn eval_path<'a>(&'a self, path: &QualifiedPath, row: usize) -> Option<PathValue<'a>> {
+        let Path::Seq(edges) = path else {
+            return Some(PathValue::Row(row));
+        };
+        let mut value = PathValue::Row(row);
+        for e in edges.iter() {
+            let PathValue::Row(i) = value else {
+                return None;
+            };
+            value = if let Some(column) = self.foreign_keys.get(e) {
+                PathValue::Row(*column.get(i)?)
+            } else {
+                PathValue::Value(self.attributes.get(e)?.get(i)?)
+            };
+        }
+        Some(value)
+    }
+
+    /// Validates that the instance is an instance of the given schema.
+    pub fn validate_in(&self, model: &DiscreteDblModel) -> Result<(), NonEmpty<InvalidInstance>> {
+        validate::wrap_errors(self.iter_invalid_in(model).into_iter())
+    }
+
+    /// Lists the failures of the instance to be valid for the given schema.
+    ///
+    /// The path equations are checked only when the columns are well formed,
+    /// by evaluating both sides of each generating equation of the schema at
+    /// every row of its source entity. No e-graph is needed: the equations
+    /// holding between paths of the schema form the congruence generated by
+    /// these equations, and the equations satisfied by an instance form a
+    /// congruence, so an instance satisfying the generating equations
+    /// satisfies every equation of the schema.
+    pub fn iter_invalid_in(&self, model: &DiscreteDblModel) -> Vec<InvalidInstance> {
+        let entity = name("Entity");
+        let is_entity = |x: &QualifiedName| model.has_ob(x) && model.ob_generator_type(x) == entity;
+        let mut errors = Vec::new();
+
+        errors.extend(
+            self.rows
+                .keys()
+                .sorted()
+                .filter(|x| !is_entity(x))
+                .cloned()
+                .map(InvalidInstance::Entity),
+        );
+        let columns = self.foreign_keys.keys().chain(self.attributes.keys()).sorted();
+        errors.extend(
+            columns
+                .filter(|f| !self.is_column_of(model, f))
+                .cloned()
+                .map(InvalidInstance::Column),
+        );
+
+        let mut kinds: HashMap<QualifiedName, &AttrValue> = HashMap::new();
+        for f in model.mor_generators().sorted() {
+            let dom = model.mor_generator_dom(&f);
+            if !is_entity(&dom) {
+                continue;
+            }
+            let expected = self.num_rows(&dom);
+            let (len, cod) = (self.column_len(&f), model.mor_generator_cod(&f));
+            let Some(actual) = len else {
+                if expected > 0 {
+                    errors.push(InvalidInstance::MissingColumn(f));
+                }
+                continue;
+            };
+            if actual != expected {
+                errors.push(InvalidInstance::ColumnLength { column: f.clone(), expected, actual });
+            }
+            // Columns of the wrong kind are already reported, so skip them.
+            let is_foreign_key = is_entity(&cod);
+            if let Some(column) = self.foreign_keys.get(&f).filter(|_| is_foreign_key) {
+                let bound = self.num_rows(&cod);
+                errors.extend(column.iter().enumerate().filter(|(_, t)| **t >= bound).map(
+                    |(row, target)| InvalidInstance::ForeignKey {
+                        mapping: f.clone(),
+                        row,
+                        target: *target,
+                    },
+                ));
+            } else if let Some(column) = self.attributes.get(&f).filter(|_| !is_foreign_key) {
+                for (row, value) in
+                    column.iter().enumerate().filter(|(_, v)| !matches!(v, AttrValue::Null))
+                {
+                    let kind = *kinds.entry(cod.clone()).or_insert(value);
+                    if !kind.same_kind(value) {
+                        errors.push(InvalidInstance::AttrType { attribute: f.clone(), row });
+                    }
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return errors;
+        }
+
+        for (equation, eq) in model.category.equations().enumerate() {
+            let src = model.dom(&eq.lhs);
+            for row in 0..self.num_rows(&src) {
+                let (lhs, rhs) = (self.eval_path(&eq.lhs, row), self.eval_path(&eq.rhs, row));
+                if lhs.is_some() && rhs.is_some() && lhs != rhs {
+                    errors.push(InvalidInstance::Equation { equation, entity: src.clone(), row });
+                }
+            }
+        }
+        errors
+    }
+
+    /// Whether a column of the instance is for a morphism out of an entity.
+    fn is_column_of(&self, model: &DiscreteDblModel, f: &QualifiedName) -> bool {
+        let entity = name("Entity");
+        model.has_mor(&Path::single(f.clone()))
+            && model.ob_generator_type(&model.mor_generator_dom(f)) == entity
+            && (self.foreign_keys.contains_key(f)
+                == (model.ob_generator_type(&model.mor_generator_cod(f)) == entity))
+    }
+
+    fn column_len(&self, f: &QualifiedName) -> Option<usize> {
+        self.foreign_keys
+            .get(f)
+            .map(|column| column.len())
+            .or_else(|| self.attributes.get(f).map(|column| column.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::model::MutDblModel;
+    use crate::one::PathEq;
+    use crate::stdlib::th_schema;
+
+    /// Employees with managers and departments, where each department has a
+    /// secretary and every employee works in the same department as their
+    /// manager and as the secretary of their department.
+    fn employees() -> DiscreteDblModel {
+        let mut model = DiscreteDblModel::new(Rc::new(th_schema()));
+        let (emp, dept, text) = (name("Employee"), name("Department"), name("Text"));
+        model.add_ob(emp.clone(), name("Entity"));
+        model.add_ob(dept.clone(), name("Entity"));
+        model.add_ob(text.clone(), name("AttrType"));
+        let hom = Path::Id(name("Entity"));
+        model.add_mor(name("manager"), emp.clone(), emp.clone(), hom.clone());
+        model.add_mor(name("works_in"), emp.clone(), dept.clone(), hom.clone());
+        model.add_mor(name("secretary"), dept.clone(), emp.clone(), hom);
+        model.add_mor(name("name"), emp, text.clone(), name("Attr").into());
+        model.add_mor(name("title"), dept, text, name("Attr").into());
+        model.add_equation(PathEq::new(
+            Path::pair(name("manager"), name("works_in")),
+            Path::single(name("works_in")),
+        ));
+        model.add_equation(PathEq::new(
+            Path::pair(name("secretary"), name("works_in")),
+            Path::Id(name("Department")),
+        ));
+        model
+    }
+
+    fn text(s: &str) -> AttrValue {
+        AttrValue::Text(s.into())
+    }
+
+    #[test]
+    fn valid_instance() {
+        let model = employees();
+        let instance = Instance {
+            rows: [(name("Employee"), 3), (name("Department"), 2)].into(),
+            foreign_keys: [
+                (name("manager"), vec![0, 0, 2]),
+                (name("works_in"), vec![0, 0, 1]),
+                (name("secretary"), vec![1, 2]),
+            ]
+            .into(),
+            attributes: [
+                (name("name"), vec![text("Alice"), text("Bob"), text("Carol")]),
+                (name("title"), vec![text("Sales"), text("Research")]),
+            ]
+            .into(),
+        };
+        assert_eq!(instance.validate_in(&model), Ok(()));
+        let path = Path::pair(name("secretary"), name("name"));
+        assert_eq!(instance.eval_path(&path, 1), Some(PathValue::Value(&text("Carol"))));
+    }
+
+    #[test]
+    fn invalid_instance() {
+        let model = employees();
+        let mut instance = Instance {
+            rows: [(name("Employee"), 2), (name("Department"), 1), (name("Text"), 1)].into(),
+            foreign_keys: [
+                (name("manager"), vec![0, 2]),
+                (name("works_in"), vec![0]),
+                (name("title"), vec![3]),
+            ]
+            .into(),
+            attributes: [(name("name"), vec![text("Alice"), AttrValue::Int(2)])].into(),
+        };
+        let errors = validate::unwrap_errors(instance.validate_in(&model));
+        assert_eq!(
+            errors,
+            vec![
+                InvalidInstance::Entity(name("Text")),
+                InvalidInstance::Column(name("title")),
+                InvalidInstance::ForeignKey {
+                    mapping: name("manager"),
+                    row: 1,
+                    target: 2
+                },
+                InvalidInstance::AttrType { attribute: name("name"), row: 1 },
+                InvalidInstance::MissingColumn(name("secretary")),
+                InvalidInstance::ColumnLength {
+                    column: name("works_in"),
+                    expected: 2,
+                    actual: 1
+                },
+            ]
+        );
+
+        // Once the columns are well formed, the path equations are checked.
+        instance.rows = [(name("Employee"), 2), (name("Department"), 2)].into();
+        instance.foreign_keys = [
+            (name("manager"), vec![0, 0]),
+            (name("works_in"), vec![0, 1]),
+            (name("secretary"), vec![1, 0]),
+        ]
+        .into();
+        instance.attributes = [
+            (name("name"), vec![text("Alice"), text("Bob")]),
+            (name("title"), vec![text("Sales"), text("Research")]),
+        ]
+        .into();
+        let errors = validate::unwrap_errors(instance.validate_in(&model));
+        assert_eq!(
+            errors,
+            vec![
+                InvalidInstance::Equation {
+                    equation: 0,
+                    entity: name("Employee"),
+                    row: 1
+                },
+                InvalidInstance::Equation {
+                    equation: 1,
+                    entity: name("Department"),
+                    row: 0
+                },
+                InvalidInstance::Equation {
+                    equation: 1,
+                    entity: name("Department"),
+                    row: 1
+                },
+            ]
+        );
+    }
+}
@@ -5,8 +5,18 @@ pub(crate) mod petri;
 #[cfg(feature = "ode")]
 pub mod ode;
 
+pub mod data_migration;
+
+pub mod instance;
+
+pub mod power_flow;
+
 pub mod reachability;
 
+pub mod structural;
+
+pub mod temporal;
+
 #[cfg(feature = "sql")]
 pub mod sql;
 
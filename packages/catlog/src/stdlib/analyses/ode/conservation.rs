@@ -0,0 +1,215 @@
+//! Conservation laws of mass-action networks.
+//!
+//! The *stoichiometric matrix* of a Petri net has a row for each place and a
+//! column for each transition, recording the net number of tokens that the
+//! transition produces at the place. Under balanced mass-action semantics, the
+//! vector field is the stoichiometric matrix times the vector of reaction
+//! rates, so that any vector `y` in the left null space of the matrix gives a
+//! *linear conservation law*: the weighted total `yᵀ x` is constant in time.
+//! Conservation laws with nonnegative integer coefficients are the
+//! *P-semiflows* of the net.
+//!
+//! Conservation laws are important diagnostics in their own right, and they are
+//! needed to reduce a system before solving for its steady states, since the
+//! equilibria of a system with conservation laws are never isolated.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::{ODESolution, PetriNetMassActionAnalysis};
+use crate::dbl::model::{FpDblModel, ModalDblModel};
+use crate::dbl::theory::Unital;
+use crate::stdlib::analyses::petri::{PetriNet, semiflows};
+use crate::zero::QualifiedName;
+
+/// Stoichiometric matrix of a Petri net.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct StoichiometricMatrix {
+    /// IDs of the places, indexing the rows of the matrix.
+    pub places: Vec<QualifiedName>,
+
+    /// IDs of the transitions, indexing the columns of the matrix.
+    pub transitions: Vec<QualifiedName>,
+
+    /// Entries of the matrix, as a list of rows.
+    pub entries: Vec<Vec<i32>>,
+}
+
+/// A linear conservation law of a mass-action network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct ConservationLaw {
+    /// Map from place IDs to (positive) coefficients in the conserved total.
+    ///
+    /// Places not in the map have coefficient zero.
+    pub coefficients: HashMap<QualifiedName, u32>,
+}
+
+/// Conservation laws of a mass-action network, with its stoichiometry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct ConservationLaws {
+    /// Stoichiometric matrix of the network.
+    pub stoichiometry: StoichiometricMatrix,
+
+    /// Minimal nonnegative conservation laws, which generate all others.
+    pub laws: Vec<ConservationLaw>,
+}
+
+impl PetriNetMassActionAnalysis {
+    /// Computes the stoichiometric matrix of a Petri net.
+    ///
+    /// Places and transitions are sorted by ID.
+    pub fn stoichiometric_matrix(&self, model: &ModalDblModel<Unital>) -> StoichiometricMatrix {
+        let net = PetriNet::from_generators(
+            model,
+            model.ob_generators_with_type(&self.place_ob_type),
+            model.mor_generators_with_type(&self.transition_mor_type),
+        );
+        let entries = net.incidence();
+        StoichiometricMatrix {
+            places: net.places,
+            transitions: net.transitions,
+            entries,
+        }
+    }
+
+    /// Computes the conservation laws of a Petri net.
+    ///
+    /// The laws hold for the ODEs of the
+    /// [balanced](super::MassConservationType::Balanced) mass-action
+    /// semantics, whatever the rate coefficients.
+    pub fn conservation_laws(&self, model: &ModalDblModel<Unital>) -> ConservationLaws {
+        let stoichiometry = self.stoichiometric_matrix(model);
+        let laws = stoichiometry.conservation_laws();
+        ConservationLaws { stoichiometry, laws }
+    }
+}
+
+impl StoichiometricMatrix {
+    /// Computes a basis of nonnegative conservation laws.
+    ///
+    /// The laws returned are the P-semiflows of minimal support, which generate
+    /// all nonnegative conservation laws as a cone, though not necessarily all
+    /// conservation laws as a vector space.
+    pub fn conservation_laws(&self) -> Vec<ConservationLaw> {
+        semiflows(&self.entries)
+            .into_iter()
+            .map(|y| ConservationLaw {
+                coefficients: std::iter::zip(&self.places, y)
+                    .filter(|(_, c)| *c != 0)
+                    .map(|(place, c)| (place.clone(), c))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+impl ConservationLaw {
+    /// Evaluates the conserved total at each time of an ODE solution.
+    ///
+    /// Places without a state in the solution are taken to be zero.
+    pub fn totals(&self, solution: &ODESolution) -> Vec<f32> {
+        let mut totals = vec![0.0; solution.time.len()];
+        for (place, c) in self.coefficients.iter() {
+            if let Some(states) = solution.states.get(place) {
+                for (total, x) in std::iter::zip(totals.iter_mut(), states) {
+                    *total += (*c as f32) * x;
+                }
+            }
+        }
+        totals
+    }
+
+    /// Largest deviation of the conserved total from its initial value over
+    /// the duration of an ODE solution.
+    pub fn deviation(&self, solution: &ODESolution) -> f32 {
+        let totals = self.totals(solution);
+        let Some(initial) = totals.first() else {
+            return 0.0;
+        };
+        totals.iter().map(|total| (total - initial).abs()).fold(0.0, f32::max)
+    }
+
+    /// Verifies numerically that the law holds for an ODE solution.
+    ///
+    /// The deviation of the conserved total must be within the given tolerance,
+    /// relative to the initial total when that is larger than one.
+    pub fn verify(&self, solution: &ODESolution, tolerance: f32) -> bool {
+        let initial = self.totals(solution).first().copied().unwrap_or_default();
+        self.deviation(solution) <= tolerance * initial.abs().max(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use nalgebra::DVector;
+    use std::rc::Rc;
+
+    use super::*;
+
+    use super::super::{FlowParameter, MassConservationType, ODEAnalysis};
+    use crate::simulate::ode::ODEProblem;
+    use crate::stdlib::{models::*, theories::*};
+    use crate::zero::name;
+
+    #[test]
+    fn sir_conservation() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let analysis = PetriNetMassActionAnalysis::default();
+        let ConservationLaws { stoichiometry, laws } = analysis.conservation_laws(&model);
+
+        assert_eq!(stoichiometry.places, vec![name("I"), name("R"), name("S")]);
+        assert_eq!(stoichiometry.transitions, vec![name("infect"), name("recover")]);
+        assert_eq!(stoichiometry.entries, vec![vec![1, -1], vec![0, 1], vec![-1, 0]]);
+        assert_eq!(laws.len(), 1);
+        let law = &laws[0];
+        let expected = [(name("S"), 1), (name("I"), 1), (name("R"), 1)].into_iter().collect();
+        assert_eq!(law.coefficients, expected);
+
+        // The total population is conserved along a simulation.
+        let sys = analysis.build_system(&model, MassConservationType::Balanced);
+        let rate = |param: &FlowParameter| match param {
+            FlowParameter::Balanced { transition } if *transition == name("infect") => 1.0,
+            _ => 0.5,
+        };
+        let sys = sys.extend_scalars(|poly| poly.eval(rate));
+        let variable_index: IndexMap<_, _> =
+            sys.components.keys().cloned().enumerate().map(|(i, x)| (x, i)).collect();
+        let initial = |ob: &QualifiedName| match ob.to_string().as_str() {
+            "S" => 9.0,
+            "I" => 1.0,
+            _ => 0.0,
+        };
+        let x0 = DVector::from_iterator(3, variable_index.keys().map(initial));
+        let problem = ODEProblem::new(sys.to_numerical(), x0).end_time(10.0);
+        let solution = ODEAnalysis::new(problem, variable_index).solve().unwrap();
+        assert!((law.totals(&solution)[0] - 10.0).abs() < 1e-6);
+        assert!(law.verify(&solution, 1e-4));
+
+        // Not every weighting of the places is conserved.
+        let not_law = ConservationLaw {
+            coefficients: [(name("S"), 1)].into_iter().collect(),
+        };
+        assert!(!not_law.verify(&solution, 1e-4));
+    }
+}
@@ -0,0 +1,349 @@
+//! Deficiency theory of mass-action networks.
+//!
+//! Chemical reaction network theory draws conclusions about the equilibria of
+//! a mass-action system that hold for *all* values of the rate coefficients,
+//! from the graph of *complexes*: the formal sums of species appearing as
+//! inputs or outputs of transitions, with an edge for each transition. The
+//! *deficiency* of the network is `n - l - s`, where `n` is the number of
+//! complexes, `l` the number of connected components of the graph, called
+//! *linkage classes*, and `s` the dimension of the stoichiometric subspace,
+//! spanned by the net changes of the transitions.
+//!
+//! The deficiency zero and deficiency one theorems of Feinberg and Horn–Jackson
+//! are stated in [Feinberg 2019](https://doi.org/10.1007/978-3-030-03858-8).
+
+use std::collections::HashMap;
+
+use indexmap::IndexSet;
+use itertools::Itertools;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::PetriNetMassActionAnalysis;
+use crate::dbl::model::{ModalDblModel, ModalOb};
+use crate::dbl::theory::Unital;
+use crate::stdlib::analyses::petri::{gcd, transition_interface};
+use crate::zero::QualifiedName;
+
+/// A transition of a Petri net, viewed as an edge between complexes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ComplexReaction {
+    /// ID of the transition.
+    pub transition: QualifiedName,
+
+    /// Index of the input complex.
+    pub source: usize,
+
+    /// Index of the output complex.
+    pub target: usize,
+}
+
+/// A theorem of chemical reaction network theory applying to a network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum DeficiencyTheorem {
+    /// The deficiency zero theorem, applying to networks of deficiency zero.
+    ///
+    /// If the network is weakly reversible, then for any rate coefficients
+    /// there is exactly one positive equilibrium in each positive
+    /// stoichiometric compatibility class. It is complex balanced and locally
+    /// asymptotically stable, and there are no nontrivial periodic orbits with
+    /// positive composition. If the network is not weakly reversible, then for
+    /// any rate coefficients there are no positive equilibria and no periodic
+    /// orbits with positive composition.
+    DeficiencyZero,
+
+    /// The deficiency one theorem, applying to networks whose linkage classes
+    /// each have deficiency at most one, summing to the total deficiency, and
+    /// each contain a single terminal strong linkage class.
+    ///
+    /// For any rate coefficients, each positive stoichiometric compatibility
+    /// class contains at most one positive equilibrium, and exactly one if the
+    /// network is weakly reversible.
+    DeficiencyOne,
+}
+
+/// Deficiency analysis of a mass-action network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct DeficiencyAnalysis {
+    /// Complexes of the network, as maps from place IDs to (positive)
+    /// coefficients. The zero complex is the empty map.
+    pub complexes: Vec<HashMap<QualifiedName, u32>>,
+
+    /// Transitions of the network, as edges between complexes.
+    pub reactions: Vec<ComplexReaction>,
+
+    /// Linkage classes, as lists of indices of complexes.
+    #[cfg_attr(feature = "serde", serde(rename = "linkageClasses"))]
+    pub linkage_classes: Vec<Vec<usize>>,
+
+    /// Terminal strong linkage classes, as lists of indices of complexes.
+    ///
+    /// These are the strongly connected components of the graph of complexes
+    /// that no reaction leaves.
+    #[cfg_attr(feature = "serde", serde(rename = "terminalClasses"))]
+    pub terminal_classes: Vec<Vec<usize>>,
+
+    /// Whether every linkage class is strongly connected.
+    #[cfg_attr(feature = "serde", serde(rename = "weaklyReversible"))]
+    pub weakly_reversible: bool,
+
+    /// Dimension of the stoichiometric subspace.
+    #[cfg_attr(feature = "serde", serde(rename = "stoichiometricDimension"))]
+    pub stoichiometric_dimension: usize,
+
+    /// Deficiency of the network.
+    pub deficiency: usize,
+
+    /// Deficiencies of the linkage classes, in the same order.
+    #[cfg_attr(feature = "serde", serde(rename = "linkageDeficiencies"))]
+    pub linkage_deficiencies: Vec<usize>,
+
+    /// Theorems whose hypotheses the network satisfies.
+    pub theorems: Vec<DeficiencyTheorem>,
+}
+
+impl PetriNetMassActionAnalysis {
+    /// Computes the deficiency of a Petri net under mass-action semantics.
+    ///
+    /// Places and transitions are taken in the same order as in the
+    /// [stoichiometric matrix](Self::stoichiometric_matrix).
+    pub fn deficiency(&self, model: &ModalDblModel<Unital>) -> DeficiencyAnalysis {
+        let stoichiometry = self.stoichiometric_matrix(model);
+        let place_index: HashMap<_, _> =
+            stoichiometry.places.iter().enumerate().map(|(i, p)| (p.clone(), i)).collect();
+        let complex = |obs: Vec<ModalOb>| {
+            let mut counts = vec![0u32; place_index.len()];
+            for ob in obs {
+                if let ModalOb::Generator(place) = ob
+                    && let Some(i) = place_index.get(&place)
+                {
+                    counts[*i] += 1;
+                }
+            }
+            counts
+        };
+
+        let mut complexes = IndexSet::new();
+        let mut reactions = Vec::new();
+        for transition in stoichiometry.transitions.iter() {
+            let (inputs, outputs) = transition_interface(model, transition);
+            let (source, _) = complexes.insert_full(complex(inputs));
+            let (target, _) = complexes.insert_full(complex(outputs));
+            reactions.push(ComplexReaction {
+                transition: transition.clone(),
+                source,
+                target,
+            });
+        }
+        let n = complexes.len();
+
+        // Reachability between complexes along reactions, by a closure.
+        let mut reach = vec![vec![false; n]; n];
+        for (i, row) in reach.iter_mut().enumerate() {
+            row[i] = true;
+        }
+        for r in reactions.iter() {
+            reach[r.source][r.target] = true;
+        }
+        transitive_closure(&mut reach);
+
+        // Linkage classes are the components of the undirected graph.
+        let mut linked = vec![vec![false; n]; n];
+        for i in 0..n {
+            for j in 0..n {
+                linked[i][j] = reach[i][j] || reach[j][i];
+            }
+        }
+        transitive_closure(&mut linked);
+        let classes = |related: &[Vec<bool>]| {
+            (0..n)
+                .map(|i| (0..n).filter(|j| related[i][*j]).collect_vec())
+                .unique()
+                .collect_vec()
+        };
+        let linkage_classes = classes(&linked);
+        let strong = (0..n)
+            .map(|i| (0..n).map(|j| reach[i][j] && reach[j][i]).collect_vec())
+            .collect_vec();
+        let strong_classes = classes(&strong);
+        let terminal_classes = strong_classes
+            .iter()
+            .filter(|class| class.iter().all(|i| (0..n).all(|j| !reach[*i][j] || strong[*i][j])))
+            .cloned()
+            .collect_vec();
+        let weakly_reversible = strong_classes.len() == linkage_classes.len();
+
+        // Dimensions of the stoichiometric subspace and its parts.
+        let change = |r: &ComplexReaction| {
+            std::iter::zip(&complexes[r.target], &complexes[r.source])
+                .map(|(y, x)| i64::from(*y) - i64::from(*x))
+                .collect_vec()
+        };
+        let stoichiometric_dimension = rank(reactions.iter().map(change).collect());
+        let linkage_deficiencies = linkage_classes
+            .iter()
+            .map(|class| {
+                let changes =
+                    reactions.iter().filter(|r| class.contains(&r.source)).map(change).collect();
+                class.len() - 1 - rank(changes)
+            })
+            .collect_vec();
+        let deficiency = n - linkage_classes.len() - stoichiometric_dimension;
+
+        let mut theorems = Vec::new();
+        if deficiency == 0 {
+            theorems.push(DeficiencyTheorem::DeficiencyZero);
+        }
+        if linkage_deficiencies.iter().all(|d| *d <= 1)
+            && linkage_deficiencies.iter().sum::<usize>() == deficiency
+            && terminal_classes.len() == linkage_classes.len()
+        {
+            theorems.push(DeficiencyTheorem::DeficiencyOne);
+        }
+
+        let complexes = complexes
+            .into_iter()
+            .map(|counts| {
+                std::iter::zip(&stoichiometry.places, counts)
+                    .filter(|(_, c)| *c != 0)
+                    .map(|(place, c)| (place.clone(), c))
+                    .collect()
+            })
+            .collect();
+        DeficiencyAnalysis {
+            complexes,
+            reactions,
+            linkage_classes,
+            terminal_classes,
+            weakly_reversible,
+            stoichiometric_dimension,
+            deficiency,
+            linkage_deficiencies,
+            theorems,
+        }
+    }
+}
+
+/// Replaces a relation, given by its matrix, with its transitive closure.
+fn transitive_closure(relation: &mut [Vec<bool>]) {
+    for k in 0..relation.len() {
+        let via = relation[k].clone();
+        for row in relation.iter_mut() {
+            if row[k] {
+                std::iter::zip(row.iter_mut(), &via).for_each(|(x, y)| *x |= *y);
+            }
+        }
+    }
+}
+
+/// Computes the rank of an integer matrix by fraction-free Gaussian
+/// elimination.
+fn rank(mut rows: Vec<Vec<i64>>) -> usize {
+    let m = rows.first().map_or(0, |row| row.len());
+    let mut rank = 0;
+    for j in 0..m {
+        let Some(pivot) = (rank..rows.len()).find(|i| rows[*i][j] != 0) else {
+            continue;
+        };
+        rows.swap(rank, pivot);
+        let pivot_row = rows[rank].clone();
+        for row in rows.iter_mut().skip(rank + 1) {
+            if row[j] != 0 {
+                let (a, b) = (pivot_row[j], row[j]);
+                for (x, y) in std::iter::zip(row.iter_mut(), &pivot_row) {
+                    *x = a * *x - b * y;
+                }
+                let divisor = row.iter().fold(0, |d, x| gcd(d, *x));
+                if divisor > 1 {
+                    row.iter_mut().for_each(|x| *x /= divisor);
+                }
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::{model::*, theory::*};
+    use crate::stdlib::{models::*, theories::*};
+    use crate::zero::name;
+
+    #[test]
+    fn sir_deficiency() {
+        let model = sir_petri(Rc::new(th_sym_monoidal_category()));
+        let result = PetriNetMassActionAnalysis::default().deficiency(&model);
+
+        // Complexes S + I, 2 I, I, and R, in two linkage classes.
+        assert_eq!(result.complexes.len(), 4);
+        assert_eq!(result.complexes[0], HashMap::from_iter([(name("S"), 1), (name("I"), 1)]));
+        assert_eq!(result.complexes[1], HashMap::from_iter([(name("I"), 2)]));
+        assert_eq!(result.linkage_classes, vec![vec![0, 1], vec![2, 3]]);
+        assert_eq!(result.terminal_classes, vec![vec![1], vec![3]]);
+        assert!(!result.weakly_reversible);
+        assert_eq!(result.stoichiometric_dimension, 2);
+        assert_eq!(result.deficiency, 0);
+        assert_eq!(result.linkage_deficiencies, vec![0, 0]);
+        assert_eq!(
+            result.theorems,
+            vec![DeficiencyTheorem::DeficiencyZero, DeficiencyTheorem::DeficiencyOne]
+        );
+    }
+
+    #[test]
+    fn reversible_and_branching_deficiency() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let (ob_type, op) = (ModalObType::new(name("Object")), name("tensor"));
+        let complex = |places: &[&str]| {
+            let obs = places.iter().map(|p| ModalOb::from(name(*p))).collect();
+            ModalOb::App(ModalOb::List(List::Symmetric, obs).into(), op.clone())
+        };
+        let mor_type = ModalMorType::Zero(ob_type.clone());
+        let analysis = PetriNetMassActionAnalysis::default();
+
+        // Isomerization A <-> B is weakly reversible with deficiency zero.
+        let mut model = ModalDblModel::new(th.clone());
+        model.add_ob(name("A"), ob_type.clone());
+        model.add_ob(name("B"), ob_type.clone());
+        model.add_mor(name("f"), complex(&["A"]), complex(&["B"]), mor_type.clone());
+        model.add_mor(name("g"), complex(&["B"]), complex(&["A"]), mor_type.clone());
+        let result = analysis.deficiency(&model);
+        assert!(result.weakly_reversible);
+        assert_eq!((result.complexes.len(), result.deficiency), (2, 0));
+        assert_eq!(result.terminal_classes, vec![vec![0, 1]]);
+
+        // Birth A -> 2A and death A -> 0 have deficiency one, but two terminal
+        // classes in a single linkage class.
+        let mut model = ModalDblModel::new(th);
+        model.add_ob(name("A"), ob_type);
+        model.add_mor(name("birth"), complex(&["A"]), complex(&["A", "A"]), mor_type.clone());
+        model.add_mor(name("death"), complex(&["A"]), complex(&[]), mor_type);
+        let result = analysis.deficiency(&model);
+        assert_eq!(result.complexes.len(), 3);
+        assert_eq!(result.complexes[2], HashMap::new());
+        assert_eq!(result.linkage_classes.len(), 1);
+        assert_eq!(result.stoichiometric_dimension, 1);
+        assert_eq!(result.deficiency, 1);
+        assert_eq!(result.terminal_classes.len(), 2);
+        assert!(result.theorems.is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+//! Delay differential equation analyses of models.
+//!
+//! In a causal loop diagram with delays, such as a model of the theory of
+//! [delayable signed categories](crate::stdlib::theories::th_delayable_signed_category),
+//! the delayed links act on their targets only after a lag. The linear and
+//! Lotka-Volterra analyses then become systems of delay differential equations,
+//! in which each delayed link reads the state of its source at time `t - τ`,
+//! with a delay `τ` given separately for each link.
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use itertools::Itertools;
+use nalgebra::DVector;
+use ode_solvers::dop_shared::IntegrationError;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::{ODESolution, SignedCoefficientBuilder};
+use crate::simulate::dde::{DDEProblem, DDESystem, DelayedVar, PolynomialDDESystem};
+use crate::zero::{alg::Polynomial, rig::Monomial};
+use crate::{dbl::model::DiscreteDblModel, one::QualifiedPath, zero::QualifiedName};
+
+/// Data defining a delay differential equation problem for a model.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct DelayProblemData {
+    /// Map from morphism IDs to interaction coefficients (nonnegative reals).
+    coefficients: HashMap<QualifiedName, f32>,
+
+    /// Map from object IDs to growth rates (arbitrary real numbers).
+    ///
+    /// Only used in the Lotka-Volterra analysis.
+    #[cfg_attr(feature = "serde", serde(rename = "growthRates", default))]
+    growth_rates: HashMap<QualifiedName, f32>,
+
+    /// Map from morphism IDs of delayed links to delays (nonnegative reals).
+    ///
+    /// Delayed links without a delay of at least the solver's step size act
+    /// instantaneously.
+    delays: HashMap<QualifiedName, f32>,
+
+    /// Map from object IDs to initial values, which are also the constant
+    /// history before the start of the simulation.
+    #[cfg_attr(feature = "serde", serde(rename = "initialValues"))]
+    initial_values: HashMap<QualifiedName, f32>,
+
+    /// Duration of simulation.
+    duration: f32,
+}
+
+/// Data needed to simulate and interpret a DDE analysis of a model.
+pub struct DDEAnalysis<Sys> {
+    /// DDE problem for the analysis.
+    pub problem: DDEProblem<Sys>,
+
+    /// Map from object IDs to variable indices.
+    pub variable_index: IndexMap<QualifiedName, usize>,
+}
+
+impl<Sys: DDESystem> DDEAnalysis<Sys> {
+    /// Solves the DDE with reasonable default settings and collects results.
+    pub fn solve(self) -> Result<ODESolution, IntegrationError> {
+        if self.variable_index.is_empty() {
+            return Ok(Default::default());
+        }
+
+        let duration = self.problem.end_time - self.problem.start_time;
+        let result = self.problem.solve_rk4(step_size(duration))?;
+
+        let (t_out, x_out) = result.get();
+        Ok(ODESolution {
+            time: t_out.clone(),
+            states: self
+                .variable_index
+                .into_iter()
+                .map(|(ob, i)| (ob, x_out.iter().map(|x| x[i]).collect()))
+                .collect(),
+            events: Vec::new(),
+            stats: Default::default(),
+        })
+    }
+}
+
+impl SignedCoefficientBuilder<QualifiedName, QualifiedPath> {
+    /// Linear DDE analysis for a model of a double theory.
+    ///
+    /// Like the [linear ODE analysis](Self::linear_ode_analysis), except that
+    /// delayed links act on the state of their source at an earlier time.
+    pub fn linear_dde_analysis(
+        &self,
+        model: &DiscreteDblModel,
+        data: DelayProblemData,
+    ) -> DDEAnalysis<PolynomialDDESystem<u8>> {
+        let (system, ob_index) = self.delayed_interactions(model, &data);
+        dde_analysis(system, ob_index, &data)
+    }
+
+    /// Lotka-Volterra DDE analysis for a model of a double theory.
+    ///
+    /// Like the [Lotka-Volterra analysis](Self::lotka_volterra_analysis),
+    /// except that delayed links act on the state of their source at an earlier
+    /// time. The growth of each object still depends on its current state.
+    pub fn lotka_volterra_dde_analysis(
+        &self,
+        model: &DiscreteDblModel,
+        data: DelayProblemData,
+    ) -> DDEAnalysis<PolynomialDDESystem<u8>> {
+        let (interactions, ob_index) = self.delayed_interactions(model, &data);
+        let components = std::iter::zip(interactions.components, ob_index.iter())
+            .map(|(interaction, (ob, i))| {
+                let r = data.growth_rates.get(ob).copied().unwrap_or_default();
+                Polynomial::<_, f32, u8>::generator(DelayedVar::current(*i)) * (interaction + r)
+            })
+            .collect();
+        let system = PolynomialDDESystem { components, delays: interactions.delays };
+        dde_analysis(system, ob_index, &data)
+    }
+
+    /// Sums up the interactions acting on each object, both instantaneous and
+    /// delayed, with numerical coefficients.
+    ///
+    /// Returns a system whose components are the sums, along with the index of
+    /// the objects.
+    fn delayed_interactions(
+        &self,
+        model: &DiscreteDblModel,
+        data: &DelayProblemData,
+    ) -> (PolynomialDDESystem<u8>, IndexMap<QualifiedName, usize>) {
+        let coefficient =
+            |mor: &QualifiedName| data.coefficients.get(mor).copied().unwrap_or_default();
+        let (matrix, ob_index) = self.build_matrix(model);
+        let links = self.build_delayed_links(model, &ob_index);
+
+        // Delays that the solver cannot resolve are treated as instantaneous.
+        let min_delay = step_size(data.duration);
+        let link_delay =
+            |mor: &QualifiedName| data.delays.get(mor).copied().filter(|tau| *tau >= min_delay);
+        let delays = links
+            .iter()
+            .filter_map(|link| link_delay(&link.mor))
+            .sorted_by(f32::total_cmp)
+            .dedup()
+            .collect_vec();
+
+        let mut components: Vec<Polynomial<_, _, _>> = matrix
+            .row_iter()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .map(|(i, a)| {
+                        (a.eval(coefficient), Monomial::generator(DelayedVar::current(i)))
+                    })
+                    .collect()
+            })
+            .collect();
+        for link in links {
+            let var = match link_delay(&link.mor) {
+                Some(tau) => {
+                    let k = delays.partition_point(|other| *other < tau);
+                    DelayedVar::delayed(link.dom, k)
+                }
+                None => DelayedVar::current(link.dom),
+            };
+            let term = Polynomial::generator(var) * (link.sign * coefficient(&link.mor));
+            components[link.cod] = std::mem::take(&mut components[link.cod]) + term;
+        }
+        let components = components.into_iter().map(|poly| poly.normalize()).collect();
+        (PolynomialDDESystem { components, delays }, ob_index)
+    }
+}
+
+/// Step size of the solver for a DDE analysis of the given duration.
+fn step_size(duration: f32) -> f32 {
+    (duration / 100.0).min(0.01)
+}
+
+/// Builds the DDE analysis from a numerical system.
+fn dde_analysis(
+    system: PolynomialDDESystem<u8>,
+    ob_index: IndexMap<QualifiedName, usize>,
+    data: &DelayProblemData,
+) -> DDEAnalysis<PolynomialDDESystem<u8>> {
+    let initial_values = ob_index
+        .keys()
+        .map(|ob| data.initial_values.get(ob).copied().unwrap_or_default());
+    let x0 = DVector::from_iterator(ob_index.len(), initial_values);
+    let problem = DDEProblem::new(system, x0).end_time(data.duration);
+    DDEAnalysis { problem, variable_index: ob_index }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::one::Path;
+    use crate::stdlib::{models::*, theories::*};
+    use crate::zero::name;
+
+    fn builder() -> SignedCoefficientBuilder<QualifiedName, QualifiedPath> {
+        SignedCoefficientBuilder::new(name("Object"))
+            .add_positive(Path::Id(name("Object")))
+            .add_negative(name("Negative").into())
+            .add_delayed_positive(name("Slow").into())
+            .add_delayed_negative(Path::pair(name("Negative"), name("Slow")))
+    }
+
+    #[test]
+    fn delayed_negative_loop_linear() {
+        let th = Rc::new(th_delayable_signed_category());
+        let model = delayed_negative_loop(th);
+        let data = DelayProblemData {
+            coefficients: [(name("loop"), 1.0)].into_iter().collect(),
+            growth_rates: Default::default(),
+            delays: [(name("loop"), 1.0)].into_iter().collect(),
+            initial_values: [(name("x"), 1.0)].into_iter().collect(),
+            duration: 2.0,
+        };
+        let analysis = builder().linear_dde_analysis(&model, data.clone());
+        assert_eq!(analysis.problem.system.delays, vec![1.0]);
+        let solution = analysis.solve().unwrap();
+
+        // dx/dt = -x(t - 1), so x(2) = -1/2.
+        assert_eq!(solution.time.last(), Some(&2.0));
+        let x = *solution.states[&name("x")].last().unwrap();
+        assert!((x + 0.5).abs() < 1e-4);
+
+        // Without a delay, the link acts instantaneously: dx/dt = -x.
+        let data = DelayProblemData { delays: Default::default(), ..data };
+        let analysis = builder().linear_dde_analysis(&model, data.clone());
+        assert!(analysis.problem.system.delays.is_empty());
+        let solution = analysis.solve().unwrap();
+        let x = *solution.states[&name("x")].last().unwrap();
+        assert!((x - (-2f32).exp()).abs() < 1e-4);
+
+        // So does a link whose delay is shorter than the step size.
+        let data = DelayProblemData {
+            delays: [(name("loop"), 1e-9)].into_iter().collect(),
+            ..data
+        };
+        let analysis = builder().linear_dde_analysis(&model, data);
+        assert!(analysis.problem.system.delays.is_empty());
+    }
+
+    #[test]
+    fn delayed_logistic_growth() {
+        // Hutchinson's equation: dx/dt = x (r - x(t - τ)), with r τ > π/2 so
+        // that the equilibrium at x = r is unstable and the solution oscillates.
+        let th = Rc::new(th_delayable_signed_category());
+        let model = delayed_negative_loop(th);
+        let data = DelayProblemData {
+            coefficients: [(name("loop"), 1.0)].into_iter().collect(),
+            growth_rates: [(name("x"), 1.0)].into_iter().collect(),
+            delays: [(name("loop"), 2.0)].into_iter().collect(),
+            initial_values: [(name("x"), 0.5)].into_iter().collect(),
+            duration: 50.0,
+        };
+        let solution = builder().lotka_volterra_dde_analysis(&model, data).solve();
+        let x = &solution.unwrap().states[&name("x")];
+        let late = &x[x.len() / 2..];
+        let (min, max) = late
+            .iter()
+            .fold((f32::INFINITY, 0.0f32), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+        assert!(min > 0.0 && min < 0.5 && max > 1.5);
+    }
+}
@@ -0,0 +1,196 @@
+//! Equilibria of ODE analyses.
+//!
+//! Equilibria are found by [Newton's method](newton_equilibrium) from multiple
+//! starting points: the initial values of the problem, any points supplied by
+//! the user, and a deterministic quasi-random sample of further points. Each
+//! equilibrium found is reported together with its [linear
+//! stability](LinearStability).
+
+use std::collections::HashMap;
+
+use nalgebra::DVector;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::{LinearStability, ODEAnalysis};
+use crate::simulate::ode::{ODESystem, newton_equilibrium};
+use crate::zero::QualifiedName;
+
+/// Data defining a search for equilibria.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct EquilibriumData {
+    /// Starting points for Newton's method, as maps from object IDs to values.
+    ///
+    /// Variables not in a map take their initial values. The initial values
+    /// themselves are always used as a starting point.
+    pub starts: Vec<HashMap<QualifiedName, f32>>,
+
+    /// Number of additional starting points to sample.
+    #[cfg_attr(feature = "serde", serde(rename = "numSamples"))]
+    pub num_samples: usize,
+
+    /// Whether to discard equilibria with negative values.
+    ///
+    /// This is appropriate when the variables are populations or concentrations.
+    pub nonnegative: bool,
+}
+
+/// An equilibrium of an ODE system.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct Equilibrium {
+    /// Map from object IDs to values at the equilibrium.
+    pub values: HashMap<QualifiedName, f32>,
+
+    /// Linear stability of the equilibrium.
+    pub stability: LinearStability,
+}
+
+/// Tolerance, relative to the scale of the state, for equilibria to coincide.
+const DISTINCT_TOLERANCE: f32 = 1e-3;
+
+impl<Sys: ODESystem> ODEAnalysis<Sys> {
+    /// Finds equilibria of the system by Newton's method from multiple starts.
+    ///
+    /// Additional starting points are sampled from a box containing the initial
+    /// values, using a Halton sequence so that results are reproducible. Note
+    /// that when the system has conserved quantities, the equilibria found need
+    /// not be reachable from the initial values.
+    pub fn equilibria(&self, data: &EquilibriumData) -> Vec<Equilibrium> {
+        let x0 = &self.problem.initial_values;
+        let n = x0.len();
+        let t = self.problem.start_time;
+
+        let mut starts = vec![x0.clone()];
+        starts.extend(data.starts.iter().map(|start| {
+            let mut x = x0.clone();
+            for (ob, i) in self.variable_index.iter() {
+                if let Some(value) = start.get(ob) {
+                    x[*i] = *value;
+                }
+            }
+            x
+        }));
+        let scale = 2.0 * x0.amax().max(1.0);
+        let lower = if data.nonnegative { 0.0 } else { -scale };
+        let bases = primes(n);
+        starts
+            .extend((1..=data.num_samples).map(|k| {
+                DVector::from_fn(n, |i, _| lower + (scale - lower) * halton(k, bases[i]))
+            }));
+
+        let mut found: Vec<DVector<f32>> = Vec::new();
+        for start in starts {
+            let Some(x) = newton_equilibrium(&self.problem.system, start, t, Default::default())
+            else {
+                continue;
+            };
+            let tol = DISTINCT_TOLERANCE * (1.0 + x.norm());
+            if data.nonnegative && x.iter().any(|v| *v < -tol) {
+                continue;
+            }
+            if found.iter().all(|y| (y - &x).norm() > tol) {
+                found.push(x);
+            }
+        }
+
+        found
+            .into_iter()
+            .map(|x| Equilibrium {
+                values: self.variable_index.iter().map(|(ob, i)| (ob.clone(), x[*i])).collect(),
+                stability: self.linear_stability_at(&x),
+            })
+            .collect()
+    }
+}
+
+/// The first `n` primes, used as bases of the Halton sequence, one per dimension.
+///
+/// The bases must be distinct, as otherwise the coordinates in dimensions with
+/// the same base coincide and the sample lies on a hyperplane.
+fn primes(n: usize) -> Vec<usize> {
+    let mut primes = Vec::with_capacity(n);
+    let mut candidate = 2;
+    while primes.len() < n {
+        if primes.iter().take_while(|p| *p * *p <= candidate).all(|p| candidate % p != 0) {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+/// The `k`th element of the van der Corput sequence in the given base.
+fn halton(mut k: usize, base: usize) -> f32 {
+    let (mut result, mut f) = (0.0, 1.0);
+    while k > 0 {
+        f /= base as f32;
+        result += f * (k % base) as f32;
+        k /= base;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::simulate::ode::{NumericalPolynomialSystem, ODEProblem};
+    use crate::zero::{alg::Polynomial, name};
+
+    #[test]
+    fn halton_sequence() {
+        let seq: Vec<_> = (1..=4).map(|k| halton(k, 2)).collect();
+        assert_eq!(seq, vec![0.5, 0.25, 0.75, 0.125]);
+        assert_eq!(halton(2, 3), 2.0 / 3.0);
+    }
+
+    #[test]
+    fn distinct_bases() {
+        assert_eq!(primes(6), vec![2, 3, 5, 7, 11, 13]);
+        let bases = primes(40);
+        assert_eq!(bases.iter().unique().count(), 40);
+        assert_eq!(bases[39], 173);
+    }
+
+    #[test]
+    fn nonnegative_equilibria() {
+        // dx_i/dt = 1 - x_i^2 in many dimensions, with equilibria at x_i = ±1.
+        let n = 20;
+        let components = (0..n)
+            .map(|i| {
+                let x = Polynomial::<_, f32, i8>::generator(i);
+                x.clone() * x * (-1.0) + 1.0
+            })
+            .collect();
+        let system = NumericalPolynomialSystem { components };
+        let problem = ODEProblem::new(system, DVector::from_element(n, 0.5));
+        let variable_index = (0..n).map(|i| (name(format!("x{i}").as_str()), i)).collect();
+        let analysis = ODEAnalysis::new(problem, variable_index);
+        let data = EquilibriumData {
+            num_samples: 10,
+            nonnegative: true,
+            ..Default::default()
+        };
+
+        // Only the equilibrium in the nonnegative orthant is kept.
+        let equilibria = analysis.equilibria(&data);
+        assert_eq!(equilibria.len(), 1);
+        assert!(equilibria[0].values.values().all(|x| (x - 1.0).abs() < 1e-4));
+    }
+}
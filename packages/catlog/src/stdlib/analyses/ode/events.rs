@@ -0,0 +1,95 @@
+//! Events in ODE analyses of models.
+//!
+//! Events are specified in terms of the objects of a model and then converted
+//! into [events](crate::simulate::ode::ODEEvent) of the numerical ODE problem.
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use crate::simulate::ode::{EventAction, EventDirection, ODEEvent};
+use crate::zero::{QualifiedName, alg::Polynomial, rig::Monomial};
+
+/// A term in the condition of an event: a coefficient times a monomial.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct EventTerm {
+    /// Coefficient of the term.
+    pub coefficient: f32,
+
+    /// Map from object IDs to their exponents in the monomial.
+    ///
+    /// The term is constant when the map is empty.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub exponents: HashMap<QualifiedName, i8>,
+}
+
+/// Specification of an event in an ODE analysis of a model.
+///
+/// The event occurs when its condition, the sum of its terms, crosses zero in
+/// the given direction.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct EventSpec {
+    /// Terms of the condition, a polynomial in the objects.
+    pub condition: Vec<EventTerm>,
+
+    /// Direction in which the condition must cross zero.
+    pub direction: EventDirection,
+
+    /// Action taken when the event occurs, referring to objects by ID.
+    pub action: EventAction<QualifiedName>,
+}
+
+impl EventSpec {
+    /// Converts the specification into an event of a numerical ODE problem.
+    ///
+    /// Objects without a variable index are taken to be zero in the condition.
+    /// Actions on such objects only record the time of the event.
+    pub fn to_event(&self, variable_index: &IndexMap<QualifiedName, usize>) -> ODEEvent {
+        let condition: Polynomial<_, _, _> = self
+            .condition
+            .iter()
+            .filter_map(|term| {
+                let monomial: Option<Monomial<_, _>> = term
+                    .exponents
+                    .iter()
+                    .map(|(ob, exp)| variable_index.get(ob).map(|i| (*i, *exp)))
+                    .collect();
+                Some((term.coefficient, monomial?))
+            })
+            .collect();
+        let action = match &self.action {
+            EventAction::Record => EventAction::Record,
+            EventAction::Terminate => EventAction::Terminate,
+            EventAction::Set { var, value } => match variable_index.get(var) {
+                Some(i) => EventAction::Set { var: *i, value: *value },
+                None => EventAction::Record,
+            },
+            EventAction::Scale { var, factor } => match variable_index.get(var) {
+                Some(i) => EventAction::Scale { var: *i, factor: *factor },
+                None => EventAction::Record,
+            },
+        };
+        ODEEvent {
+            condition: condition.normalize(),
+            direction: self.direction,
+            action,
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Fitting parameters of ODE analyses to observed data.
+//!
+//! Unknown parameters of a symbolic polynomial system are estimated by
+//! nonlinear least squares, minimizing the squared differences between the
+//! simulated and observed values of the variables. The minimization uses the
+//! Levenberg-Marquardt method, with derivatives of the simulated values with
+//! respect to the parameters computed by [forward sensitivity
+//! analysis](crate::simulate::ode::SensitivitySystem). Since rate parameters are
+//! positive, the parameters are optimized on a logarithmic scale.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use nalgebra::{DMatrix, DVector};
+use ode_solvers::dop_shared::IntegrationError;
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::{Parameter, SolverOptions};
+use crate::simulate::ode::{ODEProblem, PolynomialSystem};
+use crate::zero::QualifiedName;
+
+/// An observed value of a variable at a given time.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct Observation {
+    /// Time of the observation.
+    pub time: f32,
+
+    /// ID of the observed object.
+    pub ob: QualifiedName,
+
+    /// Observed value.
+    pub value: f32,
+}
+
+/// An error in fitting parameters to observations.
+#[derive(Debug, Error)]
+pub enum FittingError {
+    /// An observation refers to a variable not in the system.
+    #[error("Observed object `{0}` is not a variable of the system")]
+    UnknownVariable(QualifiedName),
+
+    /// An observation has a time before the start of the simulation.
+    #[error("Observation time `{0}` is negative")]
+    NegativeTime(f32),
+
+    /// An observation has a time after the end of the simulation.
+    #[error("Observation time `{0}` is after the end of the simulation")]
+    LateTime(f32),
+
+    /// The problem has events, which are not supported when fitting.
+    #[error("Parameters cannot be fitted to a simulation with events")]
+    Events,
+
+    /// The ODE solver failed at the initial parameter values.
+    #[error("Simulation failed: {0}")]
+    Integration(#[from] IntegrationError),
+}
+
+/// Result of fitting parameters to observations.
+#[derive(Clone, Debug)]
+pub struct ParameterFit {
+    /// Fitted values of the unknown parameters, in the order given.
+    pub values: Vec<f32>,
+
+    /// Residuals (simulated minus observed values) at the fitted parameters, in
+    /// the order of the observations.
+    pub residuals: Vec<f32>,
+
+    /// Half the sum of squared residuals.
+    pub cost: f32,
+
+    /// Number of iterations taken.
+    pub iterations: usize,
+
+    /// Whether the method converged within the maximum number of iterations.
+    pub converged: bool,
+
+    /// Whether the method gave up because no step decreased the cost, even
+    /// with the largest damping. The fit has then not converged.
+    pub stalled: bool,
+}
+
+/// Maximum number of Levenberg-Marquardt iterations.
+const MAX_ITERS: usize = 100;
+
+/// Relative decrease in cost below which the method is deemed to converge.
+const COST_TOLERANCE: f32 = 1e-6;
+
+/// Fits unknown parameters of a polynomial system to observations.
+///
+/// Parameters not among the `unknowns` are fixed at the values given by
+/// `values`, which also supplies the initial guesses for the unknowns.
+/// Nonpositive initial guesses are replaced by one. Variables missing from
+/// `initial_values` start at zero and the simulation runs from time zero for
+/// the given `duration`, which the observations must lie within. The system is
+/// simulated with the given solver settings.
+pub fn fit_parameters<P, F>(
+    sys: &PolynomialSystem<QualifiedName, Parameter<P>, i8>,
+    values: F,
+    unknowns: &[P],
+    initial_values: &HashMap<QualifiedName, f32>,
+    duration: f32,
+    solver: &SolverOptions,
+    observations: &[Observation],
+) -> Result<ParameterFit, FittingError>
+where
+    P: Clone + Hash + Ord,
+    F: FnMut(&P) -> f32,
+{
+    let mut sens = sys.to_sensitivity_system(values, unknowns);
+    let var_index: HashMap<_, _> =
+        sys.components.keys().enumerate().map(|(i, ob)| (ob, i)).collect();
+    let x0 = DVector::from_iterator(
+        var_index.len(),
+        sys.components
+            .keys()
+            .map(|ob| initial_values.get(ob).copied().unwrap_or_default()),
+    );
+
+    let mut times: Vec<f32> = observations.iter().map(|obs| obs.time).collect();
+    times.sort_by(f32::total_cmp);
+    times.dedup();
+    if let Some(t) = times.first().filter(|t| **t < 0.0) {
+        return Err(FittingError::NegativeTime(*t));
+    }
+    if let Some(t) = times.last().filter(|t| **t > duration) {
+        return Err(FittingError::LateTime(*t));
+    }
+    let targets = observations
+        .iter()
+        .map(|obs| {
+            let i = *var_index
+                .get(&obs.ob)
+                .ok_or_else(|| FittingError::UnknownVariable(obs.ob.clone()))?;
+            let k = times.partition_point(|t| *t < obs.time);
+            Ok((k, i, obs.value))
+        })
+        .collect::<Result<Vec<_>, FittingError>>()?;
+
+    let mut log_params = DVector::from_iterator(
+        unknowns.len(),
+        sens.sensitive_parameters().iter().map(|p| if *p > 0.0 { p.ln() } else { 0.0 }),
+    );
+
+    // Residuals and their Jacobian with respect to the log-parameters.
+    let mut evaluate = |log_params: &DVector<f32>| {
+        let params: Vec<f32> = log_params.iter().map(|phi| phi.exp()).collect();
+        sens.set_sensitive_parameters(&params);
+        let problem = ODEProblem::new(sens.clone(), sens.initial_values(&x0)).end_time(duration);
+        let states = solver.solve_at_times(&problem, &times)?;
+        let states: Vec<_> = states.iter().map(|y| sens.split(y)).collect();
+        let residuals = DVector::from_iterator(
+            targets.len(),
+            targets.iter().map(|(k, i, value)| states[*k].0[*i] - value),
+        );
+        let jacobian = DMatrix::from_fn(targets.len(), params.len(), |row, j| {
+            let (k, i, _) = targets[row];
+            states[k].1[(i, j)] * params[j]
+        });
+        Ok::<_, IntegrationError>((residuals, jacobian))
+    };
+
+    let (mut residuals, mut jacobian) = evaluate(&log_params)?;
+    let mut cost = residuals.norm_squared() / 2.0;
+    let mut lambda = 1e-3;
+    let (mut iterations, mut converged) = (0, unknowns.is_empty() || cost == 0.0);
+    let mut stalled = false;
+
+    while !converged && !stalled && iterations < MAX_ITERS {
+        iterations += 1;
+        let jtj = jacobian.transpose() * &jacobian;
+        let gradient = jacobian.transpose() * &residuals;
+        let mut damped = jtj.clone();
+        for j in 0..damped.nrows() {
+            damped[(j, j)] += lambda * jtj[(j, j)].max(f32::EPSILON);
+        }
+        let step = damped.lu().solve(&(-gradient));
+        // Limit each parameter to change by at most a factor of `e` per step.
+        let step = step.map(|step| step.map(|v| v.clamp(-1.0, 1.0)));
+        let trial = step.map(|step| &log_params + step).and_then(|trial| {
+            let (r, jac) = evaluate(&trial).ok()?;
+            let trial_cost = r.norm_squared() / 2.0;
+            trial_cost.is_finite().then_some((trial, r, jac, trial_cost))
+        });
+        match trial {
+            Some((trial, r, jac, trial_cost)) if trial_cost <= cost => {
+                converged = cost - trial_cost <= COST_TOLERANCE * cost;
+                (log_params, residuals, jacobian, cost) = (trial, r, jac, trial_cost);
+                lambda /= 10.0;
+            }
+            _ => {
+                lambda *= 10.0;
+                // The cost cannot be decreased even along the gradient.
+                stalled = lambda > 1e10;
+            }
+        }
+    }
+
+    Ok(ParameterFit {
+        values: log_params.iter().map(|phi| phi.exp()).collect(),
+        residuals: residuals.iter().copied().collect(),
+        cost,
+        iterations,
+        converged,
+        stalled,
+    })
+}
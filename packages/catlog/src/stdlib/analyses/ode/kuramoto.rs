@@ -4,16 +4,17 @@ use std::collections::HashMap;
 
 use indexmap::IndexMap;
 use nalgebra::{DMatrix, DVector};
+use ode_solvers::dop_shared::IntegrationError;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "serde-wasm")]
 use tsify::Tsify;
 
-use super::{ODEAnalysis, ODEProblem};
+use super::{ODEAnalysis, ODEProblem, ODESolution, SolverOptions};
 use crate::dbl::model::{DiscreteDblModel, FpDblModel};
 use crate::one::{FgCategory, QualifiedPath};
-use crate::simulate::ode::{KuramotoOrder, KuramotoSystem};
+use crate::simulate::ode::{KuramotoOrder, KuramotoSystem, order_parameter};
 use crate::zero::QualifiedName;
 
 /// Data defining a Kuramoto ODE problem for a model.
@@ -84,6 +85,163 @@ pub struct CommonKuramotoProblemData {
 
     /// Duration of simulation.
     duration: f32,
+
+    /// Settings for solving the ODE problem.
+    #[cfg_attr(feature = "serde", serde(default))]
+    solver: SolverOptions,
+}
+
+/// A phase-locked state of a Kuramoto system.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct PhaseLockedState {
+    /// Common angular frequency of the oscillators.
+    pub frequency: f32,
+
+    /// Map from object IDs to phases, measured relative to their mean.
+    pub phases: HashMap<QualifiedName, f32>,
+
+    /// Eigenvalues, in increasing order, of the Laplacian of the coupling
+    /// graph linearized at the phase-locked state.
+    ///
+    /// The Jacobian of the coupling terms is the negative of this Laplacian.
+    /// The smallest eigenvalue is always zero, corresponding to shifting all
+    /// phases together.
+    #[cfg_attr(feature = "serde", serde(rename = "laplacianEigenvalues"))]
+    pub laplacian_eigenvalues: Vec<f32>,
+
+    /// Second smallest eigenvalue of the linearized Laplacian.
+    #[cfg_attr(feature = "serde", serde(rename = "algebraicConnectivity"))]
+    pub algebraic_connectivity: f32,
+
+    /// Whether the phase-locked state is linearly stable, up to shifting all
+    /// phases together.
+    ///
+    /// With positive damping, in both the first- and second-order systems,
+    /// this holds if and only if the algebraic connectivity is positive.
+    pub stable: bool,
+}
+
+/// Solution to a Kuramoto ODE problem together with synchronization metrics.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct KuramotoSolution {
+    /// Solution to the ODE problem, giving the phase trajectories.
+    pub solution: ODESolution,
+
+    /// Kuramoto order parameter `r(t)` at each time of the solution.
+    #[cfg_attr(feature = "serde", serde(rename = "orderParameter"))]
+    pub order_parameter: Vec<f32>,
+
+    /// Map from object IDs to angular frequencies at the end of the simulation.
+    #[cfg_attr(feature = "serde", serde(rename = "finalFrequencies"))]
+    pub final_frequencies: HashMap<QualifiedName, f32>,
+
+    /// Whether the oscillators have synchronized their frequencies by the end
+    /// of the simulation.
+    #[cfg_attr(feature = "serde", serde(rename = "frequencySynchronized"))]
+    pub frequency_synchronized: bool,
+
+    /// The phase-locked state of the system, if one is found.
+    #[cfg_attr(feature = "serde", serde(rename = "phaseLocked"))]
+    pub phase_locked: Option<PhaseLockedState>,
+
+    /// Multiple of the coupling coefficients above which a stable phase-locked
+    /// state is guaranteed to exist.
+    ///
+    /// See [`KuramotoSystem::critical_coupling`].
+    #[cfg_attr(feature = "serde", serde(rename = "criticalCoupling"))]
+    pub critical_coupling: Option<f32>,
+}
+
+/// Relative tolerance for the spread of frequencies of synchronized oscillators.
+const FREQUENCY_TOLERANCE: f32 = 1e-3;
+
+/// Relative tolerance below which a Laplacian eigenvalue is zero.
+const LAPLACIAN_TOLERANCE: f64 = 1e-8;
+
+impl ODEAnalysis<KuramotoSystem> {
+    /// Finds and analyzes the stability of a phase-locked state.
+    pub fn phase_locked_state(&self) -> Option<PhaseLockedState> {
+        let system = &self.problem.system;
+        let frequency = system.locked_frequency()?;
+        let phases = system.phase_locked_state()?;
+
+        let phases_f64 = phases.map(f64::from);
+        let mut spectrum = system.laplacian(phases_f64.as_slice()).symmetric_eigenvalues();
+        spectrum.as_mut_slice().sort_by(f64::total_cmp);
+        let scale = spectrum.amax().max(1.0);
+        let algebraic_connectivity = spectrum.get(1).copied().unwrap_or_default();
+        let stable = spectrum.len() == 1 || algebraic_connectivity > LAPLACIAN_TOLERANCE * scale;
+
+        Some(PhaseLockedState {
+            frequency,
+            phases: self.variable_index.iter().map(|(ob, i)| (ob.clone(), phases[*i])).collect(),
+            laplacian_eigenvalues: spectrum.iter().map(|mu| *mu as f32).collect(),
+            algebraic_connectivity: algebraic_connectivity as f32,
+            stable,
+        })
+    }
+
+    /// Solves the Kuramoto system and computes its synchronization metrics.
+    pub fn solve_with_synchronization(self) -> Result<KuramotoSolution, IntegrationError> {
+        let phase_locked = self.phase_locked_state();
+        let critical_coupling = self.problem.system.critical_coupling();
+        if self.variable_index.is_empty() {
+            return Ok(KuramotoSolution {
+                solution: Default::default(),
+                order_parameter: Vec::new(),
+                final_frequencies: HashMap::new(),
+                frequency_synchronized: true,
+                phase_locked,
+                critical_coupling,
+            });
+        }
+
+        let (output, stats) = self.solver.solve(&self.problem)?;
+        let (t_out, x_out) = output.result.get();
+        let n = self.problem.system.num_oscillators();
+        let order_parameter = x_out.iter().map(|x| order_parameter(&x.as_slice()[..n])).collect();
+
+        let frequencies = x_out
+            .last()
+            .map(|x| self.problem.system.frequencies(x))
+            .unwrap_or_else(|| DVector::zeros(n));
+        let spread = frequencies.max() - frequencies.min();
+        let frequency_synchronized = spread <= FREQUENCY_TOLERANCE * frequencies.amax().max(1.0);
+
+        Ok(KuramotoSolution {
+            solution: ODESolution {
+                time: t_out.clone(),
+                states: self
+                    .variable_index
+                    .iter()
+                    .map(|(ob, i)| (ob.clone(), x_out.iter().map(|x| x[*i]).collect()))
+                    .collect(),
+                events: output.events,
+                stats,
+            },
+            order_parameter,
+            final_frequencies: self
+                .variable_index
+                .iter()
+                .map(|(ob, i)| (ob.clone(), frequencies[*i]))
+                .collect(),
+            frequency_synchronized,
+            phase_locked,
+            critical_coupling,
+        })
+    }
 }
 
 /// Kuramoto ODE analysis of a model.
@@ -157,6 +315,67 @@ impl KuramotoAnalysis {
             }
         };
         let problem = ODEProblem::new(system, initial_values).end_time(common.duration);
-        ODEAnalysis::new(problem, ob_index)
+        ODEAnalysis::new(problem, ob_index).with_solver(common.solver.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_6;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::model::MutDblModel;
+    use crate::one::Path;
+    use crate::stdlib::theories::th_power_system;
+    use crate::zero::name;
+
+    #[test]
+    fn synchronization() {
+        // A generator supplying a load over a single line.
+        let mut model = DiscreteDblModel::new(Rc::new(th_power_system()));
+        let (gen_, load) = (name("generator"), name("load"));
+        model.add_ob(gen_.clone(), name("Bus"));
+        model.add_ob(load.clone(), name("Bus"));
+        model.add_mor(name("line"), gen_.clone(), load.clone(), Path::Id(name("Bus")));
+
+        let common = |coupling: f32| CommonKuramotoProblemData {
+            coupling_coeffs: [(name("line"), coupling)].into(),
+            damping_coeffs: [(gen_.clone(), 1.0), (load.clone(), 1.0)].into(),
+            forcing_params: [(gen_.clone(), 1.0), (load.clone(), -1.0)].into(),
+            initial_phases: HashMap::new(),
+            duration: 20.0,
+            solver: Default::default(),
+        };
+        let analysis = KuramotoAnalysis::new(name("Bus")).add_link_type(Path::Id(name("Bus")));
+        let solve = |coupling| {
+            let data = KuramotoProblemData::SecondOrder {
+                common: common(coupling),
+                initial_frequencies: HashMap::new(),
+            };
+            analysis.build_system(&model, &data).solve_with_synchronization().unwrap()
+        };
+
+        let result = solve(2.0);
+        assert!(result.frequency_synchronized);
+        assert_eq!(result.critical_coupling, Some(0.5));
+        let locked = result.phase_locked.unwrap();
+        assert!(locked.stable);
+        assert_eq!(locked.frequency, 0.0);
+        assert!((locked.phases[&gen_] - locked.phases[&load] - FRAC_PI_6).abs() < 1e-5);
+        assert!((locked.algebraic_connectivity - 4.0 * FRAC_PI_6.cos()).abs() < 1e-4);
+        assert_eq!(locked.laplacian_eigenvalues.len(), 2);
+        assert!(locked.laplacian_eigenvalues[0].abs() < 1e-6);
+
+        // The order parameter converges to the cosine of half the phase gap.
+        let r = *result.order_parameter.last().unwrap();
+        assert!((r - (FRAC_PI_6 / 2.0).cos()).abs() < 1e-3);
+        assert_eq!(result.order_parameter.len(), result.solution.time.len());
+
+        // Without enough coupling, the phases drift apart forever.
+        let result = solve(0.4);
+        assert!(!result.frequency_synchronized);
+        assert!(result.phase_locked.is_none());
+        assert_eq!(result.critical_coupling, Some(2.5));
     }
 }
@@ -17,8 +17,12 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "serde-wasm")]
 use tsify::Tsify;
 
-use super::{ODEAnalysis, Parameter, SignedCoefficientBuilder};
-use crate::simulate::ode::{NumericalPolynomialSystem, ODEProblem, PolynomialSystem};
+use super::{
+    AssignParameters, AssignmentError, LinearStability, ODEAnalysis, Parameter,
+    ParameterAssignment, ParameterSweep, SignedCoefficientBuilder, SolverOptions, SweepRun,
+    parameter_sweep,
+};
+use crate::simulate::ode::{NumericalPolynomialSystem, ODEProblem, PolynomialSystem, null_space};
 use crate::{
     dbl::model::DiscreteDblModel,
     one::QualifiedPath,
@@ -26,6 +30,7 @@ use crate::{
 };
 
 /// Data defining a linear ODE problem for a model.
+#[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde-wasm", derive(Tsify))]
 #[cfg_attr(
@@ -43,8 +48,42 @@ pub struct LinearODEProblemData {
 
     /// Duration of simulation.
     duration: f32,
+
+    /// Settings for solving the ODE problem.
+    #[cfg_attr(feature = "serde", serde(default))]
+    solver: SolverOptions,
 }
 
+impl AssignParameters for LinearODEProblemData {
+    fn assign(&mut self, assignment: &ParameterAssignment) -> Result<(), AssignmentError> {
+        assignment.check_no_growth_rates()?;
+        self.coefficients.extend(assignment.rates.iter().map(|(k, v)| (k.clone(), *v)));
+        self.initial_values
+            .extend(assignment.initial_values.iter().map(|(k, v)| (k.clone(), *v)));
+        Ok(())
+    }
+}
+
+/// Equilibria of a linear ODE system.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct LinearODEEquilibria {
+    /// Basis of the subspace of equilibria, as maps from object IDs to values.
+    ///
+    /// The basis is empty when the only equilibrium is the origin.
+    pub basis: Vec<HashMap<QualifiedName, f32>>,
+
+    /// Linear stability, which is the same at every equilibrium.
+    pub stability: LinearStability,
+}
+
+/// Relative tolerance below which singular values are treated as zero.
+const NULL_SPACE_TOLERANCE: f32 = 1e-5;
+
 /// Construct a linear (first-order) dynamical system;
 /// a semantics for causal loop diagrams.
 pub fn linear_polynomial_system<Var, Coef>(
@@ -85,20 +124,45 @@ impl SignedCoefficientBuilder<QualifiedName, QualifiedPath> {
         data: LinearODEProblemData,
     ) -> ODEAnalysis<NumericalPolynomialSystem<u8>> {
         let (system, ob_index) = self.linear_ode_system(model);
-        let n = ob_index.len();
+        linear_ode_numerical_analysis(system, ob_index, data)
+    }
 
-        let initial_values = ob_index
-            .keys()
-            .map(|ob| data.initial_values.get(ob).copied().unwrap_or_default());
-        let x0 = DVector::from_iterator(n, initial_values);
+    /// Runs a parameter sweep of the linear ODE system for a model.
+    pub fn linear_ode_sweep(
+        &self,
+        model: &DiscreteDblModel,
+        data: &LinearODEProblemData,
+        sweep: &ParameterSweep,
+    ) -> Vec<SweepRun> {
+        let (system, ob_index) = self.linear_ode_system(model);
+        parameter_sweep(data, sweep, |data| {
+            linear_ode_numerical_analysis(system.clone(), ob_index.clone(), data)
+        })
+    }
 
-        let system = system
-            .extend_scalars(|poly| {
-                poly.eval(|id| data.coefficients.get(id).copied().unwrap_or_default())
-            })
-            .to_numerical();
-        let problem = ODEProblem::new(system, x0).end_time(data.duration);
-        ODEAnalysis::new(problem, ob_index)
+    /// Equilibria of the linear ODE system for a model of a double theory.
+    ///
+    /// The equilibria of a linear system form a subspace, namely the null space
+    /// of the coefficient matrix, which is computed in closed form.
+    pub fn linear_ode_equilibria(
+        &self,
+        model: &DiscreteDblModel,
+        data: LinearODEProblemData,
+    ) -> LinearODEEquilibria {
+        let (matrix, ob_index) = self.build_matrix(model);
+        let matrix = matrix
+            .map(|poly| poly.eval(|id| data.coefficients.get(id).copied().unwrap_or_default()));
+        let basis = null_space(&matrix, NULL_SPACE_TOLERANCE)
+            .into_iter()
+            .map(|v| ob_index.iter().map(|(ob, i)| (ob.clone(), v[*i])).collect())
+            .collect();
+
+        let n = ob_index.len();
+        let analysis = self.linear_ode_analysis(model, data);
+        LinearODEEquilibria {
+            basis,
+            stability: analysis.linear_stability_at(&DVector::zeros(n)),
+        }
     }
 
     /// Linear ODE system for a model of a double theory.
@@ -115,6 +179,28 @@ impl SignedCoefficientBuilder<QualifiedName, QualifiedPath> {
     }
 }
 
+/// Substitutes numerical coefficients into a symbolic linear ODE system.
+fn linear_ode_numerical_analysis(
+    system: PolynomialSystem<QualifiedName, Parameter<QualifiedName>, u8>,
+    ob_index: IndexMap<QualifiedName, usize>,
+    data: LinearODEProblemData,
+) -> ODEAnalysis<NumericalPolynomialSystem<u8>> {
+    let n = ob_index.len();
+
+    let initial_values = ob_index
+        .keys()
+        .map(|ob| data.initial_values.get(ob).copied().unwrap_or_default());
+    let x0 = DVector::from_iterator(n, initial_values);
+
+    let system = system
+        .extend_scalars(|poly| {
+            poly.eval(|id| data.coefficients.get(id).copied().unwrap_or_default())
+        })
+        .to_numerical();
+    let problem = ODEProblem::new(system, x0).end_time(data.duration);
+    ODEAnalysis::new(problem, ob_index).with_solver(data.solver.clone())
+}
+
 #[cfg(test)]
 mod test {
     use expect_test::expect;
@@ -122,6 +208,7 @@ mod test {
 
     use super::*;
     use crate::stdlib;
+    use crate::stdlib::analyses::ode::{LinearStabilityData, StabilityType};
     use crate::{one::Path, zero::name};
 
     fn builder() -> SignedCoefficientBuilder<QualifiedName, QualifiedPath> {
@@ -151,6 +238,7 @@ mod test {
             coefficients: [(name("positive"), 2.0), (name("negative"), 1.0)].into_iter().collect(),
             initial_values: [(name("x"), 1.0), (name("y"), 1.0)].into_iter().collect(),
             duration: 10.0,
+            solver: Default::default(),
         };
 
         let sys = builder().linear_ode_analysis(&neg_feedback, data).problem.system;
@@ -160,4 +248,61 @@ mod test {
         "#]];
         expected.assert_eq(&sys.to_string());
     }
+
+    #[test]
+    fn feedback_stability() {
+        let th = Rc::new(stdlib::theories::th_signed_category());
+        let data = |coefficients: &[(&str, f32)]| LinearODEProblemData {
+            coefficients: coefficients.iter().map(|(id, c)| (name(*id), *c)).collect(),
+            initial_values: [(name("x"), 1.0), (name("y"), 1.0)].into_iter().collect(),
+            duration: 10.0,
+            solver: Default::default(),
+        };
+
+        // Negative feedback oscillates around the origin.
+        let neg_feedback = stdlib::models::negative_feedback(th.clone());
+        let data_neg = data(&[("positive", 1.0), ("negative", 4.0)]);
+        let analysis = builder().linear_ode_analysis(&neg_feedback, data_neg);
+        let origin = LinearStabilityData {
+            point: [(name("x"), 0.0), (name("y"), 0.0)].into_iter().collect(),
+        };
+        let result = analysis.linear_stability(&origin);
+        assert_eq!(result.classification, StabilityType::Centre);
+        let im: Vec<_> = result.eigenvalues.iter().map(|lambda| lambda.im.abs()).collect();
+        assert_eq!(im, vec![2.0, 2.0]);
+
+        // Positive feedback is unstable, and the initial values are not at rest.
+        let pos_feedback = stdlib::models::positive_feedback(th);
+        let data_pos = data(&[("positive1", 1.0), ("positive2", 4.0)]);
+        let analysis = builder().linear_ode_analysis(&pos_feedback, data_pos);
+        let result = analysis.linear_stability(&Default::default());
+        assert_eq!(result.classification, StabilityType::Saddle);
+        assert!(result.residual > 0.0);
+    }
+
+    #[test]
+    fn feedback_equilibria() {
+        let th = Rc::new(stdlib::theories::th_signed_category());
+        let neg_feedback = stdlib::models::negative_feedback(th);
+        let data = |positive: f32| LinearODEProblemData {
+            coefficients: [(name("positive"), positive), (name("negative"), 1.0)]
+                .into_iter()
+                .collect(),
+            initial_values: [(name("x"), 1.0), (name("y"), 1.0)].into_iter().collect(),
+            duration: 10.0,
+            solver: Default::default(),
+        };
+
+        // Only the origin is an equilibrium.
+        let result = builder().linear_ode_equilibria(&neg_feedback, data(2.0));
+        assert!(result.basis.is_empty());
+        assert_eq!(result.stability.classification, StabilityType::Centre);
+
+        // Without the positive link, the x axis consists of equilibria.
+        let result = builder().linear_ode_equilibria(&neg_feedback, data(0.0));
+        assert_eq!(result.basis.len(), 1);
+        assert_eq!(result.basis[0][&name("x")], 1.0);
+        assert_eq!(result.basis[0][&name("y")], 0.0);
+        assert_eq!(result.stability.classification, StabilityType::NonHyperbolic);
+    }
 }
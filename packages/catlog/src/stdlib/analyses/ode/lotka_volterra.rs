@@ -17,7 +17,10 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "serde-wasm")]
 use tsify::Tsify;
 
-use super::{ODEAnalysis, Parameter, SignedCoefficientBuilder};
+use super::{
+    AssignParameters, AssignmentError, ODEAnalysis, Parameter, ParameterAssignment, ParameterSweep,
+    SignedCoefficientBuilder, SolverOptions, SweepRun, parameter_sweep,
+};
 use crate::simulate::ode::{NumericalPolynomialSystem, ODEProblem, PolynomialSystem};
 use crate::{
     dbl::model::DiscreteDblModel,
@@ -26,6 +29,7 @@ use crate::{
 };
 
 /// Data defining a Lotka-Volterra ODE problem for a model.
+#[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde-wasm", derive(Tsify))]
 #[cfg_attr(
@@ -47,6 +51,24 @@ pub struct LotkaVolterraProblemData {
 
     /// Duration of simulation.
     duration: f32,
+
+    /// Settings for solving the ODE problem.
+    #[cfg_attr(feature = "serde", serde(default))]
+    solver: SolverOptions,
+}
+
+/// Rates in a parameter assignment are interaction coefficients, while growth
+/// rates are assigned separately.
+impl AssignParameters for LotkaVolterraProblemData {
+    fn assign(&mut self, assignment: &ParameterAssignment) -> Result<(), AssignmentError> {
+        self.interaction_coeffs
+            .extend(assignment.rates.iter().map(|(k, v)| (k.clone(), *v)));
+        self.growth_rates
+            .extend(assignment.growth_rates.iter().map(|(k, v)| (k.clone(), *v)));
+        self.initial_values
+            .extend(assignment.initial_values.iter().map(|(k, v)| (k.clone(), *v)));
+        Ok(())
+    }
 }
 
 /// Construct a Lotka-Volterra dynamical system.
@@ -97,26 +119,20 @@ impl SignedCoefficientBuilder<QualifiedName, QualifiedPath> {
         data: LotkaVolterraProblemData,
     ) -> ODEAnalysis<NumericalPolynomialSystem<u8>> {
         let (system, ob_index) = self.lotka_volterra_system(model);
-        let n = ob_index.len();
+        lotka_volterra_numerical_analysis(system, ob_index, data)
+    }
 
-        let initial_values = ob_index
-            .keys()
-            .map(|ob| data.initial_values.get(ob).copied().unwrap_or_default());
-        let x0 = DVector::from_iterator(n, initial_values);
-
-        let system = system
-            .extend_scalars(|poly| {
-                poly.eval(|id| {
-                    data.interaction_coeffs
-                        .get(id)
-                        .or(data.growth_rates.get(id))
-                        .copied()
-                        .unwrap_or_default()
-                })
-            })
-            .to_numerical();
-        let problem = ODEProblem::new(system, x0).end_time(data.duration);
-        ODEAnalysis::new(problem, ob_index)
+    /// Runs a parameter sweep of the Lotka-Volterra system for a model.
+    pub fn lotka_volterra_sweep(
+        &self,
+        model: &DiscreteDblModel,
+        data: &LotkaVolterraProblemData,
+        sweep: &ParameterSweep,
+    ) -> Vec<SweepRun> {
+        let (system, ob_index) = self.lotka_volterra_system(model);
+        parameter_sweep(data, sweep, |data| {
+            lotka_volterra_numerical_analysis(system.clone(), ob_index.clone(), data)
+        })
     }
 
     /// Lotka-Volterra ODE system for an model of a double theory.
@@ -140,6 +156,34 @@ impl SignedCoefficientBuilder<QualifiedName, QualifiedPath> {
     }
 }
 
+/// Substitutes numerical parameters into a symbolic Lotka-Volterra system.
+fn lotka_volterra_numerical_analysis(
+    system: PolynomialSystem<QualifiedName, Parameter<QualifiedName>, u8>,
+    ob_index: IndexMap<QualifiedName, usize>,
+    data: LotkaVolterraProblemData,
+) -> ODEAnalysis<NumericalPolynomialSystem<u8>> {
+    let n = ob_index.len();
+
+    let initial_values = ob_index
+        .keys()
+        .map(|ob| data.initial_values.get(ob).copied().unwrap_or_default());
+    let x0 = DVector::from_iterator(n, initial_values);
+
+    let system = system
+        .extend_scalars(|poly| {
+            poly.eval(|id| {
+                data.interaction_coeffs
+                    .get(id)
+                    .or(data.growth_rates.get(id))
+                    .copied()
+                    .unwrap_or_default()
+            })
+        })
+        .to_numerical();
+    let problem = ODEProblem::new(system, x0).end_time(data.duration);
+    ODEAnalysis::new(problem, ob_index).with_solver(data.solver.clone())
+}
+
 #[cfg(test)]
 mod test {
     use expect_test::expect;
@@ -147,6 +191,7 @@ mod test {
 
     use super::*;
     use crate::stdlib;
+    use crate::stdlib::analyses::ode::{EquilibriumData, LinearStabilityData, StabilityType};
     use crate::{one::Path, zero::name};
 
     fn builder() -> SignedCoefficientBuilder<QualifiedName, QualifiedPath> {
@@ -180,6 +225,7 @@ mod test {
             growth_rates: [(name("x"), 2.0), (name("y"), -1.0)].into_iter().collect(),
             initial_values: [(name("x"), 1.0), (name("y"), 1.0)].into_iter().collect(),
             duration: 10.0,
+            solver: Default::default(),
         };
 
         let sys = builder().lotka_volterra_analysis(&neg_feedback, data).problem.system;
@@ -189,4 +235,95 @@ mod test {
         "#]);
         expected.assert_eq(&sys.to_string());
     }
+
+    #[test]
+    fn explicit_assignment() {
+        // An ID may key both an interaction coefficient and a growth rate.
+        let mut data = LotkaVolterraProblemData {
+            interaction_coeffs: [(name("x"), 1.0)].into_iter().collect(),
+            growth_rates: [(name("x"), 2.0)].into_iter().collect(),
+            initial_values: [(name("x"), 1.0)].into_iter().collect(),
+            duration: 10.0,
+            solver: Default::default(),
+        };
+        let assignment = ParameterAssignment {
+            rates: [(name("x"), 3.0)].into_iter().collect(),
+            growth_rates: [(name("x"), 4.0)].into_iter().collect(),
+            initial_values: Default::default(),
+        };
+        data.assign(&assignment).unwrap();
+        assert_eq!(data.interaction_coeffs[&name("x")], 3.0);
+        assert_eq!(data.growth_rates[&name("x")], 4.0);
+        assert_eq!(data.initial_values[&name("x")], 1.0);
+    }
+
+    #[test]
+    fn predator_prey_stability() {
+        let th = Rc::new(stdlib::theories::th_signed_category());
+        let neg_feedback = stdlib::models::negative_feedback(th);
+
+        let data = LotkaVolterraProblemData {
+            interaction_coeffs: [(name("positive"), 1.0), (name("negative"), 1.0)]
+                .into_iter()
+                .collect(),
+            growth_rates: [(name("x"), 2.0), (name("y"), -1.0)].into_iter().collect(),
+            initial_values: [(name("x"), 1.0), (name("y"), 1.0)].into_iter().collect(),
+            duration: 10.0,
+            solver: Default::default(),
+        };
+        let analysis = builder().lotka_volterra_analysis(&neg_feedback, data);
+        let at = |x: f32, y: f32| LinearStabilityData {
+            point: [(name("x"), x), (name("y"), y)].into_iter().collect(),
+        };
+
+        // The coexistence equilibrium is a centre.
+        let result = analysis.linear_stability(&at(1.0, 2.0));
+        assert_eq!(result.residual, 0.0);
+        assert_eq!(result.jacobian[&name("x")][&name("y")], -1.0);
+        assert_eq!(result.jacobian[&name("y")][&name("x")], 2.0);
+        assert_eq!(result.classification, StabilityType::Centre);
+        assert!((result.eigenvalues[0].im.abs() - 2f32.sqrt()).abs() < 1e-5);
+
+        // The extinction equilibrium is a saddle.
+        let result = analysis.linear_stability(&at(0.0, 0.0));
+        assert_eq!(result.residual, 0.0);
+        assert_eq!(result.classification, StabilityType::Saddle);
+        let re: Vec<_> = result.eigenvalues.iter().map(|lambda| lambda.re).collect();
+        assert_eq!(re, vec![-1.0, 2.0]);
+    }
+
+    #[test]
+    fn predator_prey_equilibria() {
+        let th = Rc::new(stdlib::theories::th_signed_category());
+        let neg_feedback = stdlib::models::negative_feedback(th);
+
+        let data = LotkaVolterraProblemData {
+            interaction_coeffs: [(name("positive"), 1.0), (name("negative"), 1.0)]
+                .into_iter()
+                .collect(),
+            growth_rates: [(name("x"), 2.0), (name("y"), -1.0)].into_iter().collect(),
+            initial_values: [(name("x"), 3.0), (name("y"), 3.0)].into_iter().collect(),
+            duration: 10.0,
+            solver: Default::default(),
+        };
+        let analysis = builder().lotka_volterra_analysis(&neg_feedback, data);
+        let search = EquilibriumData {
+            num_samples: 20,
+            nonnegative: true,
+            ..Default::default()
+        };
+        let mut equilibria: Vec<_> = analysis
+            .equilibria(&search)
+            .into_iter()
+            .map(|eq| {
+                let (x, y) = (eq.values[&name("x")], eq.values[&name("y")]);
+                ((x * 1e3).round() / 1e3, (y * 1e3).round() / 1e3, eq.stability.classification)
+            })
+            .collect();
+        equilibria.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(
+            equilibria,
+            vec![(0.0, 0.0, StabilityType::Saddle), (1.0, 2.0, StabilityType::Centre)]
+        );
+    }
 }
@@ -16,7 +16,11 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "serde-wasm")]
 use tsify::Tsify;
 
-use super::{ODEAnalysis, Parameter};
+use super::{
+    AssignParameters, AssignmentError, EventSpec, FittingError, ODEAnalysis, ODESolution,
+    ODESolutionWithSensitivities, Observation, Parameter, ParameterAssignment, ParameterSweep,
+    SensitivityError, SolverOptions, SweepRun,
+};
 use crate::dbl::{
     model::{DiscreteTabModel, FpDblModel, ModalDblModel, TabEdge},
     theory::{ModalMorType, ModalObType, TabMorType, TabObType, Unital},
@@ -59,7 +63,11 @@ pub enum RateGranularity {
 
 /// Parameters in the generated polynomial equations are *undirected* in the
 /// balanced case and *directed* in the unbalanced case.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "tag"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
 pub enum FlowParameter {
     /// If mass is conserved, we don't need to worry whether a flow is incoming or outgoing.
     Balanced {
@@ -76,7 +84,11 @@ pub enum FlowParameter {
 }
 
 /// Depending on the rate granularity, the parameters are specified by different structures.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "tag"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
 pub enum RateParameter {
     /// For per transition rates, we simply need to know the associated transition.
     PerTransition {
@@ -97,7 +109,10 @@ pub enum RateParameter {
 /// The associated direction of a "flow" term. Note that this is *opposite* from
 /// the terminology of "input" and "output", i.e. a flow A=>B gives rise to an
 /// *incoming flow to B* and an *outgoing flow from A*.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
 pub enum Direction {
     /// The parameter corresponds to an incoming flow to a specific output.
     IncomingFlow,
@@ -141,6 +156,7 @@ impl fmt::Display for FlowParameter {
 }
 
 /// Data defining an unbalanced mass-action ODE problem for a model.
+#[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde-wasm", derive(Tsify))]
 #[cfg_attr(
@@ -184,6 +200,92 @@ pub struct MassActionProblemData {
 
     /// Duration of simulation.
     pub duration: f32,
+
+    /// Events during the simulation.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub events: Vec<EventSpec>,
+
+    /// Settings for solving the ODE problem.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub solver: SolverOptions,
+}
+
+impl MassActionProblemData {
+    /// Gets the rate coefficient for a parameter, defaulting to zero.
+    pub fn rate(&self, param: &FlowParameter) -> f32 {
+        match param {
+            FlowParameter::Balanced { transition } => {
+                self.transition_rates.get(transition).copied().unwrap_or_default()
+            }
+            FlowParameter::Unbalanced { direction, parameter } => match (direction, parameter) {
+                (Direction::IncomingFlow, RateParameter::PerTransition { transition }) => {
+                    self.transition_production_rates.get(transition).copied().unwrap_or_default()
+                }
+                (Direction::OutgoingFlow, RateParameter::PerTransition { transition }) => {
+                    self.transition_consumption_rates.get(transition).copied().unwrap_or_default()
+                }
+                (Direction::IncomingFlow, RateParameter::PerPlace { transition, place }) => self
+                    .place_production_rates
+                    .get(transition)
+                    .and_then(|rate| rate.get(place))
+                    .copied()
+                    .unwrap_or_default(),
+                (Direction::OutgoingFlow, RateParameter::PerPlace { transition, place }) => self
+                    .place_consumption_rates
+                    .get(transition)
+                    .and_then(|rate| rate.get(place))
+                    .copied()
+                    .unwrap_or_default(),
+            },
+        }
+    }
+
+    /// Sets the rate coefficient for a parameter.
+    pub fn set_rate(&mut self, param: &FlowParameter, value: f32) {
+        match param {
+            FlowParameter::Balanced { transition } => {
+                self.transition_rates.insert(transition.clone(), value);
+            }
+            FlowParameter::Unbalanced { direction, parameter } => match (direction, parameter) {
+                (Direction::IncomingFlow, RateParameter::PerTransition { transition }) => {
+                    self.transition_production_rates.insert(transition.clone(), value);
+                }
+                (Direction::OutgoingFlow, RateParameter::PerTransition { transition }) => {
+                    self.transition_consumption_rates.insert(transition.clone(), value);
+                }
+                (Direction::IncomingFlow, RateParameter::PerPlace { transition, place }) => {
+                    self.place_production_rates
+                        .entry(transition.clone())
+                        .or_default()
+                        .insert(place.clone(), value);
+                }
+                (Direction::OutgoingFlow, RateParameter::PerPlace { transition, place }) => {
+                    self.place_consumption_rates
+                        .entry(transition.clone())
+                        .or_default()
+                        .insert(place.clone(), value);
+                }
+            },
+        }
+    }
+}
+
+/// Rates in a parameter assignment are keyed by transition, so they can only be
+/// assigned in the balanced case. In the unbalanced case, a transition has
+/// several rates and assigning any rate is an error.
+impl AssignParameters for MassActionProblemData {
+    fn assign(&mut self, assignment: &ParameterAssignment) -> Result<(), AssignmentError> {
+        assignment.check_no_growth_rates()?;
+        for (transition, rate) in assignment.rates.iter() {
+            if self.mass_conservation_type != MassConservationType::Balanced {
+                return Err(AssignmentError::AmbiguousRate(transition.clone()));
+            }
+            self.transition_rates.insert(transition.clone(), *rate);
+        }
+        self.initial_values
+            .extend(assignment.initial_values.iter().map(|(k, v)| (k.clone(), *v)));
+        Ok(())
+    }
 }
 
 /// Mass-action ODE analysis for Petri nets.
@@ -373,7 +475,7 @@ impl StockFlowMassActionAnalysis {
     }
 
     /// Constructs a monomial for each flow in the model.
-    pub(super) fn flow_monomials(
+    pub(crate) fn flow_monomials(
         &self,
         model: &DiscreteTabModel,
     ) -> HashMap<QualifiedName, Monomial<QualifiedName, i8>> {
@@ -415,33 +517,7 @@ pub fn extend_mass_action_scalars(
     sys: PolynomialSystem<QualifiedName, Parameter<FlowParameter>, i8>,
     data: &MassActionProblemData,
 ) -> PolynomialSystem<QualifiedName, f32, i8> {
-    let sys = sys.extend_scalars(|poly| {
-        poly.eval(|flow| match flow {
-            FlowParameter::Balanced { transition } => {
-                data.transition_rates.get(transition).cloned().unwrap_or_default()
-            }
-            FlowParameter::Unbalanced { direction, parameter } => match (direction, parameter) {
-                (Direction::IncomingFlow, RateParameter::PerTransition { transition }) => {
-                    data.transition_production_rates.get(transition).cloned().unwrap_or_default()
-                }
-                (Direction::OutgoingFlow, RateParameter::PerTransition { transition }) => {
-                    data.transition_consumption_rates.get(transition).cloned().unwrap_or_default()
-                }
-                (Direction::IncomingFlow, RateParameter::PerPlace { transition, place }) => data
-                    .place_production_rates
-                    .get(transition)
-                    .and_then(|rate| rate.get(place))
-                    .copied()
-                    .unwrap_or_default(),
-                (Direction::OutgoingFlow, RateParameter::PerPlace { transition, place }) => data
-                    .place_consumption_rates
-                    .get(transition)
-                    .and_then(|rate| rate.get(place))
-                    .copied()
-                    .unwrap_or_default(),
-            },
-        })
-    });
+    let sys = sys.extend_scalars(|poly| poly.eval(|flow| data.rate(flow)));
 
     sys.normalize()
 }
@@ -464,6 +540,142 @@ pub fn into_mass_action_analysis(
     let problem = ODEProblem::new(num_sys, x0).end_time(data.duration);
 
     ODEAnalysis::new(problem, ob_index)
+        .with_events(&data.events)
+        .with_solver(data.solver.clone())
+}
+
+/// Runs a parameter sweep of a symbolic mass-action system.
+pub fn mass_action_sweep(
+    sys: &PolynomialSystem<QualifiedName, Parameter<FlowParameter>, i8>,
+    data: &MassActionProblemData,
+    sweep: &ParameterSweep,
+) -> Vec<SweepRun> {
+    super::parameter_sweep(data, sweep, |data| {
+        into_mass_action_analysis(extend_mass_action_scalars(sys.clone(), &data), data)
+    })
+}
+
+/// Solves a symbolic mass-action system along with its sensitivities to all
+/// rate parameters.
+///
+/// The problem must not have events.
+pub fn mass_action_sensitivities(
+    sys: &PolynomialSystem<QualifiedName, Parameter<FlowParameter>, i8>,
+    data: &MassActionProblemData,
+) -> Result<ODESolutionWithSensitivities<FlowParameter>, SensitivityError> {
+    if !data.events.is_empty() {
+        return Err(SensitivityError::Events);
+    }
+    let result = super::solve_with_sensitivities(
+        sys,
+        |param| data.rate(param),
+        &data.initial_values,
+        data.duration,
+        &data.solver,
+    )?;
+    Ok(result)
+}
+
+/// Data defining the estimation of rate coefficients from observations.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct MassActionFitData {
+    /// Observed values of objects over time.
+    pub observations: Vec<Observation>,
+
+    /// Rate parameters to be estimated.
+    ///
+    /// The values of these parameters in the problem data serve as initial
+    /// guesses, while all other parameters are held fixed.
+    pub unknowns: Vec<FlowParameter>,
+}
+
+/// An estimated rate coefficient.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct FittedParameter {
+    /// The rate parameter.
+    pub parameter: FlowParameter,
+
+    /// Estimated value of the parameter.
+    pub value: f32,
+}
+
+/// Result of estimating rate coefficients of a mass-action system.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct MassActionFit {
+    /// Estimated values of the unknown parameters.
+    pub parameters: Vec<FittedParameter>,
+
+    /// Residuals (simulated minus observed values), one per observation.
+    pub residuals: Vec<f32>,
+
+    /// Half the sum of squared residuals.
+    pub cost: f32,
+
+    /// Number of iterations of the optimizer.
+    pub iterations: usize,
+
+    /// Whether the optimizer converged.
+    pub converged: bool,
+
+    /// Whether the optimizer gave up because it could not decrease the cost.
+    pub stalled: bool,
+
+    /// Solution of the ODE problem with the estimated parameters.
+    pub solution: ODESolution,
+}
+
+/// Estimates rate coefficients of a symbolic mass-action system from observations.
+///
+/// See [`fit_parameters`](super::fit_parameters) for details. The observations
+/// must lie within the duration of the problem, which must not have events.
+pub fn mass_action_fit(
+    sys: &PolynomialSystem<QualifiedName, Parameter<FlowParameter>, i8>,
+    data: &MassActionProblemData,
+    fit: &MassActionFitData,
+) -> Result<MassActionFit, FittingError> {
+    if !data.events.is_empty() {
+        return Err(FittingError::Events);
+    }
+    let result = super::fit_parameters(
+        sys,
+        |param| data.rate(param),
+        &fit.unknowns,
+        &data.initial_values,
+        data.duration,
+        &data.solver,
+        &fit.observations,
+    )?;
+
+    let mut fitted_data = data.clone();
+    for (param, value) in fit.unknowns.iter().zip(&result.values) {
+        fitted_data.set_rate(param, *value);
+    }
+    let analysis = into_mass_action_analysis(
+        extend_mass_action_scalars(sys.clone(), &fitted_data),
+        fitted_data,
+    );
+    let solution = analysis.solve()?;
+
+    Ok(MassActionFit {
+        parameters: std::iter::zip(&fit.unknowns, result.values)
+            .map(|(param, value)| FittedParameter { parameter: param.clone(), value })
+            .collect(),
+        residuals: result.residuals,
+        cost: result.cost,
+        iterations: result.iterations,
+        converged: result.converged,
+        stalled: result.stalled,
+        solution,
+    })
 }
 
 #[cfg(test)]
@@ -472,7 +684,8 @@ mod tests {
     use std::rc::Rc;
 
     use super::*;
-    use crate::simulate::ode::LatexEquation;
+    use crate::simulate::ode::{EventAction, EventDirection, LatexEquation};
+    use crate::stdlib::analyses::ode::EventTerm;
     use crate::stdlib::{analyses, models::*, theories::*};
 
     // Tests for stock-flow diagrams. These all use the backward_link() model,
@@ -594,6 +807,303 @@ mod tests {
         expected.assert_eq(&sys.to_string());
     }
 
+    /// Data for a balanced mass-action problem without events, with rates and
+    /// initial values given by object and morphism names.
+    fn balanced_data(
+        rates: &[(&str, f32)],
+        initial_values: &[(&str, f32)],
+        duration: f32,
+    ) -> MassActionProblemData {
+        let named = |pairs: &[(&str, f32)]| pairs.iter().map(|(id, x)| (name(*id), *x)).collect();
+        MassActionProblemData {
+            mass_conservation_type: MassConservationType::Balanced,
+            transition_rates: named(rates),
+            transition_consumption_rates: Default::default(),
+            transition_production_rates: Default::default(),
+            place_consumption_rates: Default::default(),
+            place_production_rates: Default::default(),
+            initial_values: named(initial_values),
+            duration,
+            events: Vec::new(),
+            solver: Default::default(),
+        }
+    }
+
+    // Test for stiff dynamics: a fast reversible isomerization x <-> y feeding a
+    // slow reaction y -> z, with rate constants spanning six orders of magnitude.
+    #[test]
+    fn stiff_petri_simulation() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = crate::tt::modelgen::Model::from_text(
+            &th.into(),
+            "[
+                x : Object,
+                y : Object,
+                z : Object,
+                fwd : (Hom Object)[x, y],
+                bwd : (Hom Object)[y, x],
+                slow : (Hom Object)[y, z],
+            ]",
+        );
+        let model = model.unwrap().as_modal().unwrap();
+        let sys = PetriNetMassActionAnalysis::default()
+            .build_system(&model, analyses::ode::MassConservationType::Balanced);
+        let data = balanced_data(&[("fwd", 1e5), ("bwd", 1e5), ("slow", 0.1)], &[("x", 1.0)], 10.0);
+        let sys = extend_mass_action_scalars(sys, &data);
+        let analysis = into_mass_action_analysis(sys, data);
+        assert!(analysis.problem.is_stiff());
+
+        let solution = analysis.solve().unwrap();
+        assert_eq!(solution.time.last(), Some(&10.0));
+        // Slow manifold: x = y = (1 - z)/2 and dz/dt = 0.1 y, so z = 1 - exp(-t/20).
+        let z = *solution.states[&name("z")].last().unwrap();
+        assert!((z - (1.0 - (-0.5f32).exp())).abs() < 1e-2);
+    }
+
+    #[test]
+    fn sir_sweep() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let sys = PetriNetMassActionAnalysis::default()
+            .build_system(&model, analyses::ode::MassConservationType::Balanced);
+        let data =
+            balanced_data(&[("infect", 1.0), ("recover", 1.0)], &[("S", 0.99), ("I", 0.01)], 20.0);
+        let grid = analyses::ode::ParameterGrid {
+            rates: [(name("infect"), vec![0.5, 2.0, 4.0])].into_iter().collect(),
+            ..Default::default()
+        };
+        let runs = mass_action_sweep(&sys, &data, &ParameterSweep::Grid(grid));
+        assert_eq!(runs.len(), 3);
+        assert!(runs.iter().all(|run| run.error.is_none()));
+
+        // Below the epidemic threshold, infections peak at the start.
+        let peak_infected = |run: &SweepRun| run.summary[&name("I")];
+        assert_eq!(peak_infected(&runs[0]).peak_time, 0.0);
+
+        // Above it, the epidemic peaks sooner and higher as the infection rate grows.
+        let (slow, fast) = (peak_infected(&runs[1]), peak_infected(&runs[2]));
+        assert!(slow.peak_time > fast.peak_time && fast.peak_time > 0.0);
+        assert!(fast.peak > slow.peak && slow.peak > 0.01);
+
+        // Mass is conserved in each run.
+        for run in runs.iter() {
+            let total: f32 = run.summary.values().map(|summary| summary.final_value).sum();
+            assert!((total - 1.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn unbalanced_sweep() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let mass_conservation_type = MassConservationType::Unbalanced(RateGranularity::PerPlace);
+        let sys =
+            PetriNetMassActionAnalysis::default().build_system(&model, mass_conservation_type);
+        let data = MassActionProblemData {
+            mass_conservation_type,
+            place_consumption_rates: [
+                (name("infect"), [(name("S"), 1.0), (name("I"), 1.0)].into_iter().collect()),
+                (name("recover"), [(name("I"), 1.0)].into_iter().collect()),
+            ]
+            .into_iter()
+            .collect(),
+            place_production_rates: [
+                (name("infect"), [(name("I"), 2.0)].into_iter().collect()),
+                (name("recover"), [(name("R"), 1.0)].into_iter().collect()),
+            ]
+            .into_iter()
+            .collect(),
+            ..balanced_data(&[], &[("S", 0.99), ("I", 0.01)], 1.0)
+        };
+
+        // Initial values can be swept, but a rate keyed by transition is ambiguous.
+        let grid = analyses::ode::ParameterGrid {
+            initial_values: [(name("I"), vec![0.01, 0.1])].into_iter().collect(),
+            ..Default::default()
+        };
+        let runs = mass_action_sweep(&sys, &data, &ParameterSweep::Grid(grid));
+        assert!(runs.iter().all(|run| run.error.is_none()));
+
+        let grid = analyses::ode::ParameterGrid {
+            rates: [(name("infect"), vec![0.5])].into_iter().collect(),
+            ..Default::default()
+        };
+        let runs = mass_action_sweep(&sys, &data, &ParameterSweep::Grid(grid));
+        assert!(runs[0].error.is_some() && runs[0].solution.is_none());
+    }
+
+    #[test]
+    fn sir_quarantine() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let sys = PetriNetMassActionAnalysis::default()
+            .build_system(&model, analyses::ode::MassConservationType::Balanced);
+
+        // Half of the infected are quarantined once a fifth of the population
+        // is infected.
+        let quarantine = EventSpec {
+            condition: vec![
+                EventTerm {
+                    coefficient: 1.0,
+                    exponents: [(name("I"), 1)].into_iter().collect(),
+                },
+                EventTerm {
+                    coefficient: -0.2,
+                    exponents: Default::default(),
+                },
+            ],
+            direction: EventDirection::Rising,
+            action: EventAction::Scale { var: name("I"), factor: 0.5 },
+        };
+        let data = MassActionProblemData {
+            events: vec![quarantine],
+            ..balanced_data(&[("infect", 4.0), ("recover", 1.0)], &[("S", 0.99), ("I", 0.01)], 10.0)
+        };
+        let analysis = into_mass_action_analysis(extend_mass_action_scalars(sys, &data), data);
+        let solution = analysis.solve().unwrap();
+
+        assert!(!solution.events.is_empty());
+        let event = solution.events[0];
+        assert_eq!(event.event, 0);
+        let k = solution.time.iter().position(|t| *t == event.time).unwrap();
+        let infected = &solution.states[&name("I")];
+        assert!((infected[k] - 0.2).abs() < 1e-3);
+        assert!((infected[k + 1] - 0.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn logistic_equilibria() {
+        // Birth x -> 2x and competition 2x -> x give dx/dt = 2x - x^2.
+        use crate::dbl::{model::*, theory::*};
+        let th = Rc::new(th_sym_monoidal_category());
+        let (ob_type, op) = (ModalObType::new(name("Object")), name("tensor"));
+        let x = ModalOb::from(name("x"));
+        let xx =
+            ModalOb::App(ModalOb::List(List::Symmetric, vec![x.clone(), x.clone()]).into(), op);
+        let mut model = ModalDblModel::new(th);
+        model.add_ob(name("x"), ob_type.clone());
+        model.add_mor(name("birth"), x.clone(), xx.clone(), ModalMorType::Zero(ob_type.clone()));
+        model.add_mor(name("compete"), xx, x, ModalMorType::Zero(ob_type));
+        let sys = PetriNetMassActionAnalysis::default()
+            .build_system(&model, analyses::ode::MassConservationType::Balanced);
+        let data = balanced_data(&[("birth", 2.0), ("compete", 1.0)], &[("x", 1.0)], 10.0);
+        let analysis = into_mass_action_analysis(extend_mass_action_scalars(sys, &data), data);
+
+        let search = analyses::ode::EquilibriumData { num_samples: 10, ..Default::default() };
+        let mut equilibria: Vec<_> = analysis
+            .equilibria(&search)
+            .into_iter()
+            .map(|eq| (eq.values[&name("x")], eq.stability.classification))
+            .collect();
+        equilibria.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(equilibria.len(), 2);
+        assert!(equilibria[0].0.abs() < 1e-4 && (equilibria[1].0 - 2.0).abs() < 1e-4);
+        assert_eq!(equilibria[0].1, analyses::ode::StabilityType::UnstableNode);
+        assert_eq!(equilibria[1].1, analyses::ode::StabilityType::StableNode);
+    }
+
+    #[test]
+    fn sir_sensitivities() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let sys = PetriNetMassActionAnalysis::default()
+            .build_system(&model, analyses::ode::MassConservationType::Balanced);
+        let mut data = MassActionProblemData {
+            solver: SolverOptions {
+                method: analyses::ode::SolverMethod::Rk4,
+                output_step_size: Some(0.5),
+                ..Default::default()
+            },
+            ..balanced_data(&[("infect", 2.0), ("recover", 0.5)], &[("S", 0.99), ("I", 0.01)], 10.0)
+        };
+
+        // The solution agrees with a plain simulation using the same settings.
+        let result = mass_action_sensitivities(&sys, &data).unwrap();
+        let analysis =
+            into_mass_action_analysis(extend_mass_action_scalars(sys.clone(), &data), data.clone());
+        let solution = analysis.solve().unwrap();
+        assert_eq!(result.solution.time, solution.time);
+        assert_eq!(result.solution.time.len(), 21);
+        for (ob, states) in &solution.states {
+            let diff = std::iter::zip(states, &result.solution.states[ob]);
+            assert!(diff.into_iter().all(|(x, y)| (x - y).abs() < 1e-4));
+        }
+
+        // Events are not supported.
+        data.events.push(EventSpec {
+            condition: Vec::new(),
+            direction: EventDirection::Rising,
+            action: EventAction::Terminate,
+        });
+        let result = mass_action_sensitivities(&sys, &data);
+        assert!(matches!(result, Err(SensitivityError::Events)));
+    }
+
+    #[test]
+    fn sir_fit() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let sys = PetriNetMassActionAnalysis::default()
+            .build_system(&model, analyses::ode::MassConservationType::Balanced);
+        let mut data =
+            balanced_data(&[("infect", 2.0), ("recover", 0.5)], &[("S", 0.99), ("I", 0.01)], 10.0);
+
+        // Observe the true solution, then fit from perturbed initial guesses.
+        let analysis =
+            into_mass_action_analysis(extend_mass_action_scalars(sys.clone(), &data), data.clone());
+        let solution = analysis.solve().unwrap();
+        let observations = (1..=10)
+            .flat_map(|k| {
+                let i = (0..solution.time.len())
+                    .min_by(|i, j| {
+                        let dist = |i: &usize| (solution.time[*i] - k as f32).abs();
+                        dist(i).total_cmp(&dist(j))
+                    })
+                    .unwrap();
+                ["S", "I"].map(|ob| Observation {
+                    time: solution.time[i],
+                    ob: name(ob),
+                    value: solution.states[&name(ob)][i],
+                })
+            })
+            .collect();
+        let infect = FlowParameter::Balanced { transition: name("infect") };
+        let recover = FlowParameter::Balanced { transition: name("recover") };
+        data.set_rate(&infect, 1.0);
+        data.set_rate(&recover, 1.0);
+        let fit_data = MassActionFitData {
+            observations,
+            unknowns: vec![infect, recover],
+        };
+
+        let fit = mass_action_fit(&sys, &data, &fit_data).unwrap();
+        assert!(fit.converged && !fit.stalled);
+        assert_eq!(fit.residuals.len(), 20);
+        assert!((fit.parameters[0].value - 2.0).abs() < 2e-2);
+        assert!((fit.parameters[1].value - 0.5).abs() < 5e-3);
+        assert!(fit.residuals.iter().all(|r| r.abs() < 1e-2));
+        assert_eq!(fit.solution.time.len(), solution.time.len());
+
+        // No parameters fit an observation that is not a number.
+        let mut fit_data = fit_data;
+        fit_data.observations[0].value = f32::NAN;
+        let fit = mass_action_fit(&sys, &data, &fit_data).unwrap();
+        assert!(fit.stalled && !fit.converged);
+        assert!(fit.cost.is_nan());
+
+        // Observations must lie within the simulation, which must not have events.
+        fit_data.observations[0].time = 11.0;
+        let result = mass_action_fit(&sys, &data, &fit_data);
+        assert!(matches!(result, Err(FittingError::LateTime(_))));
+        data.events.push(EventSpec {
+            condition: Vec::new(),
+            direction: EventDirection::Rising,
+            action: EventAction::Terminate,
+        });
+        let result = mass_action_fit(&sys, &data, &fit_data);
+        assert!(matches!(result, Err(FittingError::Events)));
+    }
+
     // Test for LaTeX.
 
     #[test]
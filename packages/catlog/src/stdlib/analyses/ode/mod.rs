@@ -3,7 +3,6 @@
 use std::collections::HashMap;
 
 use derivative::Derivative;
-use derive_more::Constructor;
 use indexmap::IndexMap;
 use ode_solvers::dop_shared::IntegrationError;
 
@@ -12,7 +11,7 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "serde-wasm")]
 use tsify::Tsify;
 
-use crate::simulate::ode::{ODEProblem, ODESystem};
+use crate::simulate::ode::{EventOccurrence, ODEProblem, ODESystem};
 use crate::zero::{QualifiedName, alg::Polynomial};
 
 /// Symbolic parameter in a polynomial system.
@@ -30,34 +29,69 @@ pub struct ODESolution {
 
     /// Values of state variables for the duration of the simulation.
     pub(in crate::stdlib::analyses) states: HashMap<QualifiedName, Vec<f32>>,
+
+    /// Events that occurred during the simulation, in order of time.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(in crate::stdlib::analyses) events: Vec<EventOccurrence>,
+
+    /// Statistics of the solver that produced the solution.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(in crate::stdlib::analyses) stats: SolverStats,
 }
 
 /// Data needed to simulate and interpret an ODE analysis of a model.
-#[derive(Constructor)]
 pub struct ODEAnalysis<Sys> {
     /// ODE problem for the analysis.
     pub problem: ODEProblem<Sys>,
 
     /// Map from IDs in model (usually object IDs) to variable indices.
     pub variable_index: IndexMap<QualifiedName, usize>,
+
+    /// Settings for solving the ODE problem.
+    pub solver: SolverOptions,
 }
 
 impl<Sys> ODEAnalysis<Sys> {
-    /// Solves the ODE with reasonable default settings and collects results.
-    pub fn solve_with_defaults(self) -> Result<ODESolution, IntegrationError>
+    /// Constructs an ODE analysis with the default solver settings.
+    pub fn new(problem: ODEProblem<Sys>, variable_index: IndexMap<QualifiedName, usize>) -> Self {
+        Self {
+            problem,
+            variable_index,
+            solver: Default::default(),
+        }
+    }
+
+    /// Sets the settings for solving the ODE problem.
+    pub fn with_solver(mut self, solver: SolverOptions) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    /// Adds events to the ODE problem, replacing any existing events.
+    pub fn with_events(mut self, events: &[EventSpec]) -> Self {
+        let events = events.iter().map(|spec| spec.to_event(&self.variable_index)).collect();
+        self.problem = self.problem.events(events);
+        self
+    }
+}
+
+impl<Sys> ODEAnalysis<Sys> {
+    /// Solves the ODE with the solver settings and collects results.
+    ///
+    /// With the default settings, problems are solved using the Dormand-Prince
+    /// method unless they are detected to be stiff, either in advance or during
+    /// integration, in which case a Rosenbrock method is used instead.
+    pub fn solve(self) -> Result<ODESolution, IntegrationError>
     where
-        Sys: ODESystem,
+        Sys: ODESystem<f32> + ODESystem<f64>,
     {
         // ODE solver will fail in the degenerate case of an empty system.
         if self.variable_index.is_empty() {
             return Ok(Default::default());
         }
 
-        let duration = self.problem.end_time - self.problem.start_time;
-        let output_step_size = (duration / 100.0).min(0.01f32);
-        let result = self.problem.solve_dopri5(output_step_size)?;
-
-        let (t_out, x_out) = result.get();
+        let (output, stats) = self.solver.solve(&self.problem)?;
+        let (t_out, x_out) = output.result.get();
         Ok(ODESolution {
             time: t_out.clone(),
             states: self
@@ -65,20 +99,42 @@ impl<Sys> ODEAnalysis<Sys> {
                 .into_iter()
                 .map(|(ob, i)| (ob, x_out.iter().map(|x| x[i]).collect()))
                 .collect(),
+            events: output.events,
+            stats,
         })
     }
 }
 
+pub mod conservation;
+pub mod deficiency;
+pub mod delay;
+pub mod equilibrium;
+pub mod events;
+pub mod fitting;
 pub mod kuramoto;
 pub mod linear_ode;
 pub mod lotka_volterra;
 pub mod mass_action;
 pub mod polynomial_ode;
+pub mod sensitivity;
 pub mod signed_coefficients;
+pub mod solver;
+pub mod stability;
+pub mod sweep;
 
+pub use conservation::*;
+pub use deficiency::*;
+pub use delay::*;
+pub use equilibrium::*;
+pub use events::*;
+pub use fitting::*;
 pub use kuramoto::*;
 pub use linear_ode::*;
 pub use lotka_volterra::*;
 pub use mass_action::*;
 pub use polynomial_ode::*;
+pub use sensitivity::*;
 pub use signed_coefficients::*;
+pub use solver::*;
+pub use stability::*;
+pub use sweep::*;
@@ -19,9 +19,13 @@ use crate::{
     zero::{QualifiedName, alg::Polynomial, name, rig::Monomial},
 };
 
-use super::{ODEAnalysis, Parameter};
+use super::{
+    AssignParameters, AssignmentError, EventSpec, ODEAnalysis, ODESolutionWithSensitivities,
+    Parameter, ParameterAssignment, ParameterSweep, SensitivityError, SolverOptions, SweepRun,
+};
 
 /// Data defining an unbalanced mass-action ODE problem for a model.
+#[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde-wasm", derive(Tsify))]
 #[cfg_attr(
@@ -38,6 +42,24 @@ pub struct PolynomialODEProblemData {
 
     /// Duration of simulation.
     pub duration: f32,
+
+    /// Events during the simulation.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub events: Vec<EventSpec>,
+
+    /// Settings for solving the ODE problem.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub solver: SolverOptions,
+}
+
+impl AssignParameters for PolynomialODEProblemData {
+    fn assign(&mut self, assignment: &ParameterAssignment) -> Result<(), AssignmentError> {
+        assignment.check_no_growth_rates()?;
+        self.coefficients.extend(assignment.rates.iter().map(|(k, v)| (k.clone(), *v)));
+        self.initial_values
+            .extend(assignment.initial_values.iter().map(|(k, v)| (k.clone(), *v)));
+        Ok(())
+    }
 }
 
 /// Polynomial ODE analysis.
@@ -121,6 +143,28 @@ pub fn extend_polynomial_ode_scalars(
     sys.normalize()
 }
 
+/// Solves a symbolic system of polynomial ODEs along with its sensitivities to
+/// all coefficients.
+///
+/// The problem must not have events.
+pub fn polynomial_ode_sensitivities(
+    sys: &PolynomialSystem<QualifiedName, Parameter<QualifiedName>, i8>,
+    data: &PolynomialODEProblemData,
+) -> Result<ODESolutionWithSensitivities<QualifiedName>, SensitivityError> {
+    if !data.events.is_empty() {
+        return Err(SensitivityError::Events);
+    }
+    let coefficient = |mor: &QualifiedName| data.coefficients.get(mor).copied().unwrap_or_default();
+    let result = super::solve_with_sensitivities(
+        sys,
+        coefficient,
+        &data.initial_values,
+        data.duration,
+        &data.solver,
+    )?;
+    Ok(result)
+}
+
 /// Builds the numerical ODE analysis for a system of polynomial ODEs whose scalars have been substituted.
 pub fn polynomial_ode_analysis(
     sys: PolynomialSystem<QualifiedName, f32, i8>,
@@ -139,6 +183,19 @@ pub fn polynomial_ode_analysis(
     let problem = ODEProblem::new(num_sys, x0).end_time(data.duration);
 
     ODEAnalysis::new(problem, ob_index)
+        .with_events(&data.events)
+        .with_solver(data.solver.clone())
+}
+
+/// Runs a parameter sweep of a symbolic system of polynomial ODEs.
+pub fn polynomial_ode_sweep(
+    sys: &PolynomialSystem<QualifiedName, Parameter<QualifiedName>, i8>,
+    data: &PolynomialODEProblemData,
+    sweep: &ParameterSweep,
+) -> Vec<SweepRun> {
+    super::parameter_sweep(data, sweep, |data| {
+        polynomial_ode_analysis(extend_polynomial_ode_scalars(sys.clone(), &data), data)
+    })
 }
 
 #[cfg(test)]
@@ -0,0 +1,187 @@
+//! Local sensitivity analysis of ODE analyses.
+//!
+//! The sensitivity of a solution to a parameter is the derivative of the state
+//! variables with respect to the parameter over the duration of the simulation.
+//! It is computed by [forward sensitivity analysis](SensitivitySystem) of a
+//! symbolic polynomial system, for all of its parameters at once.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use itertools::Itertools;
+use nalgebra::DVector;
+use ode_solvers::dop_shared::IntegrationError;
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::{ODESolution, Parameter, SolverOptions};
+use crate::simulate::ode::{ODEProblem, PolynomialSystem, SensitivitySystem};
+use crate::zero::QualifiedName;
+
+/// Sensitivities of the solution to an ODE problem with respect to a parameter.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ParameterSensitivity<P> {
+    /// The parameter.
+    pub parameter: P,
+
+    /// Value of the parameter.
+    pub value: f32,
+
+    /// Map from object IDs to derivatives with respect to the parameter, at
+    /// the same times as the solution.
+    pub sensitivities: HashMap<QualifiedName, Vec<f32>>,
+
+    /// Largest magnitude of the *scaled* sensitivity `p ∂x/∂p` over all
+    /// variables and times.
+    ///
+    /// The scaled sensitivity is the change in a variable per relative change
+    /// in the parameter, so that it can be compared across parameters.
+    pub magnitude: f32,
+}
+
+/// Solution to an ODE problem together with its sensitivities to parameters.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ODESolutionWithSensitivities<P> {
+    /// Solution to the ODE problem.
+    pub solution: ODESolution,
+
+    /// Sensitivities of the solution, one for each parameter of the system.
+    pub sensitivities: Vec<ParameterSensitivity<P>>,
+}
+
+/// An error in solving an ODE problem along with its sensitivities.
+#[derive(Debug, Error)]
+pub enum SensitivityError {
+    /// The problem has events, which are not supported by sensitivity analysis.
+    #[error("Sensitivities cannot be computed for a simulation with events")]
+    Events,
+
+    /// The ODE solver failed.
+    #[error("Simulation failed: {0}")]
+    Integration(#[from] IntegrationError),
+}
+
+/// Solves a symbolic polynomial system along with its sensitivities to all of
+/// its parameters.
+///
+/// The parameters take values given by `values`. Variables missing from
+/// `initial_values` start at zero. The system is solved with the given solver
+/// settings.
+pub fn solve_with_sensitivities<P, F>(
+    sys: &PolynomialSystem<QualifiedName, Parameter<P>, i8>,
+    values: F,
+    initial_values: &HashMap<QualifiedName, f32>,
+    duration: f32,
+    solver: &SolverOptions,
+) -> Result<ODESolutionWithSensitivities<P>, IntegrationError>
+where
+    P: Clone + Hash + Ord,
+    F: FnMut(&P) -> f32,
+{
+    let params = sys.parameters();
+    let sens: SensitivitySystem<i8> = sys.to_sensitivity_system(values, &params);
+    let obs = sys.components.keys().collect_vec();
+    let n = obs.len();
+    if n == 0 {
+        return Ok(ODESolutionWithSensitivities {
+            solution: Default::default(),
+            sensitivities: Vec::new(),
+        });
+    }
+
+    let x0 = DVector::from_iterator(
+        n,
+        obs.iter().map(|ob| initial_values.get(*ob).copied().unwrap_or_default()),
+    );
+    let values = sens.sensitive_parameters().to_vec();
+    let y0 = sens.initial_values(&x0);
+    let problem = ODEProblem::new(sens, y0).end_time(duration);
+    let (output, stats) = solver.solve(&problem)?;
+    let (t_out, y_out) = output.result.get();
+
+    let solution = ODESolution {
+        time: t_out.clone(),
+        states: obs
+            .iter()
+            .enumerate()
+            .map(|(i, ob)| ((*ob).clone(), y_out.iter().map(|y| y[i]).collect()))
+            .collect(),
+        events: output.events,
+        stats,
+    };
+    let sensitivities = std::iter::zip(params, values)
+        .enumerate()
+        .map(|(k, (parameter, value))| {
+            let offset = n * (k + 1);
+            let magnitude = y_out
+                .iter()
+                .flat_map(|y| y.rows(offset, n).iter().map(|s| (value * s).abs()).collect_vec())
+                .fold(0.0, f32::max);
+            ParameterSensitivity {
+                parameter,
+                value,
+                sensitivities: obs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ob)| ((*ob).clone(), y_out.iter().map(|y| y[offset + i]).collect()))
+                    .collect(),
+                magnitude,
+            }
+        })
+        .collect();
+
+    Ok(ODESolutionWithSensitivities { solution, sensitivities })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zero::{alg::Polynomial, name};
+
+    #[test]
+    fn logistic_growth() {
+        // dx/dt = r x - c x^2, starting from x = 0.1.
+        let x = Polynomial::<_, Parameter<_>, i8>::generator(name("x"));
+        let r = Parameter::generator(name("r"));
+        let c = Parameter::generator(name("c"));
+        let sys: PolynomialSystem<_, _, _> =
+            [(name("x"), x.clone() * r + x.clone() * x * (-c))].into_iter().collect();
+        let values = |p: &QualifiedName| if *p == name("r") { 1.0 } else { 0.5 };
+        let initial_values = [(name("x"), 0.1)].into_iter().collect();
+        let result =
+            solve_with_sensitivities(&sys, values, &initial_values, 4.0, &Default::default())
+                .unwrap();
+
+        let params = result.sensitivities.iter().map(|s| s.parameter.clone()).collect_vec();
+        assert_eq!(params, vec![name("c"), name("r")]);
+
+        // Compare with finite differences at the end of the simulation.
+        let final_value = |r: f32, c: f32| {
+            let values = |p: &QualifiedName| if *p == name("r") { r } else { c };
+            let result =
+                solve_with_sensitivities(&sys, values, &initial_values, 4.0, &Default::default())
+                    .unwrap();
+            *result.solution.states[&name("x")].last().unwrap()
+        };
+        let h = 1e-2;
+        let dc = (final_value(1.0, 0.5 + h) - final_value(1.0, 0.5 - h)) / (2.0 * h);
+        let dr = (final_value(1.0 + h, 0.5) - final_value(1.0 - h, 0.5)) / (2.0 * h);
+        let last = |k: usize| *result.sensitivities[k].sensitivities[&name("x")].last().unwrap();
+        assert!((last(0) - dc).abs() < 1e-2);
+        assert!((last(1) - dr).abs() < 1e-2);
+
+        // The population is still growing towards capacity, so the growth rate
+        // has the larger effect.
+        assert!(result.sensitivities[1].magnitude > result.sensitivities[0].magnitude);
+    }
+}
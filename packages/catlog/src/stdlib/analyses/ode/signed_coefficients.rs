@@ -13,11 +13,28 @@ use crate::{
 /// Builder for signed coefficient matrices and analyses based on them.
 ///
 /// Used to construct the [linear](Self::linear_ode_analysis) and
-/// [Lotka-Volterra](Self::lotka_volterra_analysis) ODE analyses.
+/// [Lotka-Volterra](Self::lotka_volterra_analysis) ODE analyses, as well as
+/// their [delayed](Self::linear_dde_analysis) variants.
 pub struct SignedCoefficientBuilder<ObType, MorType> {
     var_ob_type: ObType,
     positive_mor_types: Vec<MorType>,
     negative_mor_types: Vec<MorType>,
+    delayed_positive_mor_types: Vec<MorType>,
+    delayed_negative_mor_types: Vec<MorType>,
+}
+
+/// A delayed interaction between objects, as found by a
+/// [`SignedCoefficientBuilder`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DelayedLink {
+    /// ID of the morphism defining the interaction.
+    pub mor: QualifiedName,
+    /// Index of the object acting, whose state is delayed.
+    pub dom: usize,
+    /// Index of the object acted upon.
+    pub cod: usize,
+    /// Sign of the interaction, either `1.0` or `-1.0`.
+    pub sign: f32,
 }
 
 impl<ObType, MorType> SignedCoefficientBuilder<ObType, MorType> {
@@ -27,6 +44,8 @@ impl<ObType, MorType> SignedCoefficientBuilder<ObType, MorType> {
             var_ob_type,
             positive_mor_types: Vec::new(),
             negative_mor_types: Vec::new(),
+            delayed_positive_mor_types: Vec::new(),
+            delayed_negative_mor_types: Vec::new(),
         }
     }
 
@@ -42,6 +61,18 @@ impl<ObType, MorType> SignedCoefficientBuilder<ObType, MorType> {
         self
     }
 
+    /// Adds a morphism type defining a delayed positive interaction between objects.
+    pub fn add_delayed_positive(mut self, mor_type: MorType) -> Self {
+        self.delayed_positive_mor_types.push(mor_type);
+        self
+    }
+
+    /// Adds a morphism type defining a delayed negative interaction between objects.
+    pub fn add_delayed_negative(mut self, mor_type: MorType) -> Self {
+        self.delayed_negative_mor_types.push(mor_type);
+        self
+    }
+
     /// Builds the matrix of symbolic coefficients for the given model.
     ///
     /// Returns the coefficient matrix along with an ordered map from object
@@ -81,4 +112,35 @@ impl<ObType, MorType> SignedCoefficientBuilder<ObType, MorType> {
 
         (mat, ob_index)
     }
+
+    /// Builds the list of delayed interactions in the given model.
+    ///
+    /// Objects are indexed as in the [coefficient matrix](Self::build_matrix).
+    pub fn build_delayed_links(
+        &self,
+        model: &impl FpDblModel<
+            ObType = ObType,
+            MorType = MorType,
+            Ob = QualifiedName,
+            ObGen = QualifiedName,
+            MorGen = QualifiedName,
+        >,
+        ob_index: &IndexMap<QualifiedName, usize>,
+    ) -> Vec<DelayedLink> {
+        let signed_types = self
+            .delayed_positive_mor_types
+            .iter()
+            .map(|mor_type| (mor_type, 1.0))
+            .chain(self.delayed_negative_mor_types.iter().map(|mor_type| (mor_type, -1.0)));
+        signed_types
+            .flat_map(|(mor_type, sign)| {
+                model.mor_generators_with_type(mor_type).map(move |mor| DelayedLink {
+                    dom: *ob_index.get(&model.mor_generator_dom(&mor)).unwrap(),
+                    cod: *ob_index.get(&model.mor_generator_cod(&mor)).unwrap(),
+                    mor,
+                    sign,
+                })
+            })
+            .collect()
+    }
 }
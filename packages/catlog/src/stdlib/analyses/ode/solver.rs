@@ -0,0 +1,366 @@
+//! Solver settings for ODE analyses.
+//!
+//! By default, ODE analyses are solved in single precision by the Dormand-Prince
+//! method, switching to a Rosenbrock method for stiff problems. These settings
+//! can be changed as part of the problem data of an analysis, though the data
+//! and solutions of the analyses remain in single precision.
+
+use nalgebra::DVector;
+use num_traits::Float;
+use ode_solvers::dop_shared::{IntegrationError, SolverResult, Stats};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use crate::simulate::ode::{ODEProblem, ODEScalar, ODESolverOutput, ODESystem, cast_scalar};
+
+/// Method used to solve an ODE problem.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum SolverMethod {
+    /// Dormand-Prince method, unless the problem is detected to be stiff,
+    /// either in advance or during integration, in which case the Rosenbrock
+    /// method is used instead.
+    #[default]
+    Auto,
+
+    /// Dormand-Prince method of order 5(4), with adaptive step size.
+    Dopri5,
+
+    /// Rosenbrock method of order 2(3), with adaptive step size.
+    Rosenbrock23,
+
+    /// Classical Runge-Kutta method, with step size equal to the output step size.
+    Rk4,
+}
+
+/// Floating point precision in which an ODE problem is solved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum Precision {
+    /// Single precision (`f32`).
+    #[default]
+    Single,
+
+    /// Double precision (`f64`), for the internal integration only.
+    ///
+    /// Only the integrator runs in double precision. The parameters and initial
+    /// values of the problem data are given in single precision, and the
+    /// solution is rounded to single precision, like every [ODE
+    /// solution](super::ODESolution). Double precision thus reduces the error
+    /// that accumulates over a long or stiff integration, but not the error in
+    /// the inputs or outputs.
+    Double,
+}
+
+/// Settings for solving an ODE problem.
+///
+/// Settings that are not given keep the defaults of the
+/// [ODE problem](ODEProblem).
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct SolverOptions {
+    /// Method used to solve the problem.
+    pub method: SolverMethod,
+
+    /// Precision in which the problem is integrated.
+    pub precision: Precision,
+
+    /// Relative tolerance of the adaptive methods.
+    pub rtol: Option<f64>,
+
+    /// Absolute tolerance of the adaptive methods.
+    pub atol: Option<f64>,
+
+    /// Maximum number of steps of the adaptive methods.
+    #[cfg_attr(feature = "serde", serde(rename = "maxSteps"))]
+    pub max_steps: Option<u32>,
+
+    /// Time between outputs of the solver.
+    ///
+    /// Defaults to a hundredth of the duration or 0.01, whichever is smaller.
+    #[cfg_attr(feature = "serde", serde(rename = "outputStepSize"))]
+    pub output_step_size: Option<f32>,
+}
+
+/// Statistics of the solver for an ODE problem.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct SolverStats {
+    /// Method that produced the solution, never [`SolverMethod::Auto`] unless
+    /// the problem was not solved.
+    pub method: SolverMethod,
+
+    /// Number of evaluations of the vector field.
+    pub evaluations: u32,
+
+    /// Number of accepted steps.
+    #[cfg_attr(feature = "serde", serde(rename = "acceptedSteps"))]
+    pub accepted_steps: u32,
+
+    /// Number of rejected steps.
+    #[cfg_attr(feature = "serde", serde(rename = "rejectedSteps"))]
+    pub rejected_steps: u32,
+}
+
+impl SolverStats {
+    fn new(method: SolverMethod, stats: Stats) -> Self {
+        Self {
+            method,
+            evaluations: stats.num_eval,
+            accepted_steps: stats.accepted_steps,
+            rejected_steps: stats.rejected_steps,
+        }
+    }
+}
+
+impl SolverOptions {
+    /// Solves an ODE problem with these settings.
+    ///
+    /// In double precision, the problem is cast to `f64` before any setting is
+    /// applied, so the initial values, which are given in single precision, are
+    /// kept exactly and the tolerances are not rounded to single precision. The
+    /// numerical system is then evaluated in `f64` throughout. The output is
+    /// rounded to single precision regardless of the precision in which the
+    /// problem is solved.
+    pub fn solve<Sys>(
+        &self,
+        problem: &ODEProblem<Sys>,
+    ) -> Result<(ODESolverOutput, SolverStats), IntegrationError>
+    where
+        Sys: ODESystem<f32> + ODESystem<f64>,
+    {
+        match self.precision {
+            Precision::Single => self.solve_in(problem.cast::<f32>()),
+            Precision::Double => {
+                let (output, stats) = self.solve_in(problem.cast::<f64>())?;
+                let (t_out, x_out) = output.result.get();
+                let result = SolverResult::new(
+                    t_out.iter().copied().map(cast_scalar).collect(),
+                    x_out.iter().map(|x| x.map(cast_scalar)).collect(),
+                );
+                let output = ODESolverOutput {
+                    result,
+                    events: output.events,
+                    stats: output.stats,
+                };
+                Ok((output, stats))
+            }
+        }
+    }
+
+    /// Solves an ODE problem with these settings, in the problem's precision.
+    fn solve_in<Sys, T>(
+        &self,
+        mut problem: ODEProblem<Sys, T>,
+    ) -> Result<(ODESolverOutput<T>, SolverStats), IntegrationError>
+    where
+        Sys: ODESystem<T>,
+        T: ODEScalar,
+        f64: From<T>,
+    {
+        problem = self.configure(problem);
+        let dt = self.output_step_size_for(problem.end_time - problem.start_time);
+
+        let solve = |method| {
+            let output = match method {
+                SolverMethod::Dopri5 => problem.solve_dopri5_with_events(dt),
+                SolverMethod::Rosenbrock23 => problem.solve_rosenbrock23_with_events(dt),
+                SolverMethod::Rk4 => problem.solve_rk4_with_events(dt),
+                SolverMethod::Auto => unreachable!("Solver method should be resolved"),
+            }?;
+            let stats = SolverStats::new(method, output.stats);
+            Ok((output, stats))
+        };
+        match self.method {
+            SolverMethod::Auto if problem.is_stiff() => solve(SolverMethod::Rosenbrock23),
+            SolverMethod::Auto => match solve(SolverMethod::Dopri5) {
+                Err(
+                    IntegrationError::StiffnessDetected { .. }
+                    | IntegrationError::MaxNumStepReached { .. },
+                ) => solve(SolverMethod::Rosenbrock23),
+                result => result,
+            },
+            method => solve(method),
+        }
+    }
+
+    /// Solves an ODE problem with these settings, returning the states at the
+    /// given times.
+    ///
+    /// The problem is integrated between consecutive times as in
+    /// [`ODEProblem::solve_at_times`], but by the chosen method and in the
+    /// chosen precision. The classical Runge-Kutta method takes steps no longer
+    /// than the output step size. Events of the problem are not taken into
+    /// account.
+    pub fn solve_at_times<Sys>(
+        &self,
+        problem: &ODEProblem<Sys>,
+        times: &[f32],
+    ) -> Result<Vec<DVector<f32>>, IntegrationError>
+    where
+        Sys: ODESystem<f32> + ODESystem<f64>,
+    {
+        match self.precision {
+            Precision::Single => self.solve_at_times_in(problem.cast::<f32>(), times),
+            Precision::Double => {
+                let times: Vec<f64> = times.iter().copied().map(cast_scalar).collect();
+                let states = self.solve_at_times_in(problem.cast::<f64>(), &times)?;
+                Ok(states.into_iter().map(|x| x.map(cast_scalar)).collect())
+            }
+        }
+    }
+
+    /// Solves an ODE problem at the given times, in the problem's precision.
+    fn solve_at_times_in<Sys, T>(
+        &self,
+        problem: ODEProblem<Sys, T>,
+        times: &[T],
+    ) -> Result<Vec<DVector<T>>, IntegrationError>
+    where
+        Sys: ODESystem<T>,
+        T: ODEScalar,
+        f64: From<T>,
+    {
+        let problem = self.configure(problem);
+        let step_size = self.output_step_size_for(problem.end_time - problem.start_time);
+        problem.solve_at_times_with(times, |problem, dt| match self.method {
+            SolverMethod::Auto => match problem.solve_dopri5(dt) {
+                Err(
+                    IntegrationError::StiffnessDetected { .. }
+                    | IntegrationError::MaxNumStepReached { .. },
+                ) => problem.solve_rosenbrock23(dt),
+                result => result,
+            },
+            SolverMethod::Dopri5 => problem.solve_dopri5(dt),
+            SolverMethod::Rosenbrock23 => problem.solve_rosenbrock23(dt),
+            SolverMethod::Rk4 => {
+                // Take a whole number of steps, enlarged slightly so that
+                // rounding cannot add a step.
+                let span = problem.end_time - problem.start_time;
+                let num_steps = Float::max(Float::ceil(span / step_size), T::one());
+                let four: T = nalgebra::convert(4.0);
+                problem.solve_rk4(span / num_steps * (T::one() + four * <T as Float>::epsilon()))
+            }
+        })
+    }
+
+    /// Applies the tolerances and maximum number of steps to an ODE problem.
+    fn configure<Sys, T>(&self, mut problem: ODEProblem<Sys, T>) -> ODEProblem<Sys, T>
+    where
+        T: ODEScalar,
+    {
+        if let Some(rtol) = self.rtol {
+            problem = problem.rtol(cast_scalar(rtol));
+        }
+        if let Some(atol) = self.atol {
+            problem = problem.atol(cast_scalar(atol));
+        }
+        if let Some(n_max) = self.max_steps {
+            problem = problem.max_steps(n_max);
+        }
+        problem
+    }
+
+    /// Output step size for a problem of the given duration.
+    fn output_step_size_for<T: ODEScalar>(&self, duration: T) -> T {
+        match self.output_step_size.filter(|dt| *dt > 0.0) {
+            Some(dt) => cast_scalar(dt),
+            None => {
+                let dt: T = nalgebra::convert(0.01);
+                if duration / nalgebra::convert(100.0) < dt {
+                    duration / nalgebra::convert(100.0)
+                } else {
+                    dt
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::DVector;
+
+    use super::*;
+    use crate::simulate::ode::NumericalPolynomialSystem;
+    use crate::zero::alg::Polynomial;
+
+    fn exponential_decay() -> ODEProblem<NumericalPolynomialSystem<i8>> {
+        let x = Polynomial::<usize, f32, i8>::generator(0);
+        let system = NumericalPolynomialSystem { components: vec![x * (-1.0)] };
+        ODEProblem::new(system, DVector::from_element(1, 1.0)).end_time(1.0)
+    }
+
+    #[test]
+    fn default_options() {
+        let (output, stats) = SolverOptions::default().solve(&exponential_decay()).unwrap();
+        let (t_out, x_out) = output.result.get();
+        assert_eq!(t_out.len(), 101);
+        assert!((x_out.last().unwrap()[0] - (-1f32).exp()).abs() < 1e-4);
+        assert_eq!(stats.method, SolverMethod::Dopri5);
+        assert!(stats.accepted_steps > 0 && stats.evaluations > stats.accepted_steps);
+    }
+
+    #[test]
+    fn double_precision() {
+        let options = SolverOptions {
+            precision: Precision::Double,
+            rtol: Some(1e-10),
+            atol: Some(1e-12),
+            ..Default::default()
+        };
+        let (output, _) = options.solve(&exponential_decay()).unwrap();
+        let x = output.result.get().1.last().unwrap()[0];
+        assert!((f64::from(x) - (-1f64).exp()).abs() < 1e-7);
+    }
+
+    #[test]
+    fn solve_at_times() {
+        let methods = [
+            SolverMethod::Auto,
+            SolverMethod::Dopri5,
+            SolverMethod::Rosenbrock23,
+            SolverMethod::Rk4,
+        ];
+        for method in methods {
+            let options = SolverOptions { method, ..Default::default() };
+            let states = options.solve_at_times(&exponential_decay(), &[0.5, 1.0]).unwrap();
+            assert_eq!(states.len(), 2);
+            assert!((states[1][0] - (-1f32).exp()).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn fixed_step_method() {
+        let options = SolverOptions {
+            method: SolverMethod::Rk4,
+            output_step_size: Some(0.1),
+            ..Default::default()
+        };
+        let (output, stats) = options.solve(&exponential_decay()).unwrap();
+        assert_eq!(output.result.get().0.len(), 11);
+        assert_eq!(stats.method, SolverMethod::Rk4);
+        assert_eq!(stats.rejected_steps, 0);
+
+        // Too few steps for the adaptive method.
+        let options = SolverOptions {
+            method: SolverMethod::Dopri5,
+            max_steps: Some(1),
+            rtol: Some(1e-6),
+            ..Default::default()
+        };
+        assert!(options.solve(&exponential_decay()).is_err());
+    }
+}
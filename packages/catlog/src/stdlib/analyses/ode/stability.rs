@@ -0,0 +1,201 @@
+//! Linear stability analysis of ODE systems.
+//!
+//! The stability of a system near a point is determined, to first order, by the
+//! eigenvalues of the Jacobian matrix of the vector field at that point. This
+//! module computes the Jacobian and its eigenvalues and uses them to classify
+//! the point, which is meaningful when the point is an equilibrium.
+
+use std::collections::HashMap;
+
+use nalgebra::{Complex, DVector};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::ODEAnalysis;
+use crate::simulate::ode::ODESystem;
+use crate::zero::QualifiedName;
+
+/// Data defining a linear stability analysis.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct LinearStabilityData {
+    /// Map from object IDs to values at the point to analyze.
+    ///
+    /// Variables not in the map take their initial values.
+    pub point: HashMap<QualifiedName, f32>,
+}
+
+/// A complex eigenvalue of a real matrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct Eigenvalue {
+    /// Real part of the eigenvalue.
+    pub re: f32,
+    /// Imaginary part of the eigenvalue.
+    pub im: f32,
+}
+
+/// Classification of a point by the eigenvalues of the Jacobian there.
+///
+/// The names are those of the planar case but apply in any dimension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum StabilityType {
+    /// All eigenvalues are real and negative.
+    StableNode,
+    /// All eigenvalues are real and positive.
+    UnstableNode,
+    /// All eigenvalues have negative real part, and some are not real.
+    StableFocus,
+    /// All eigenvalues have positive real part, and some are not real.
+    UnstableFocus,
+    /// Some eigenvalues have positive and others negative real part.
+    Saddle,
+    /// All eigenvalues are nonzero and purely imaginary.
+    Centre,
+    /// Some eigenvalues have zero real part, so linearization is inconclusive.
+    NonHyperbolic,
+}
+
+/// Result of a linear stability analysis.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct LinearStability {
+    /// Jacobian matrix at the point, as a map from object IDs to rows.
+    ///
+    /// The entry `jacobian[x][y]` is the partial derivative of the rate of
+    /// change of `x` with respect to `y`.
+    pub jacobian: HashMap<QualifiedName, HashMap<QualifiedName, f32>>,
+
+    /// Eigenvalues of the Jacobian matrix, sorted by real part.
+    pub eigenvalues: Vec<Eigenvalue>,
+
+    /// Classification of the point.
+    pub classification: StabilityType,
+
+    /// Euclidean norm of the vector field at the point.
+    ///
+    /// The classification describes an equilibrium only when this is zero.
+    pub residual: f32,
+}
+
+/// Relative tolerance below which the real part of an eigenvalue is zero.
+const ZERO_TOLERANCE: f32 = 1e-4;
+
+/// Classifies a point by the eigenvalues of the Jacobian there.
+pub fn classify_eigenvalues(eigenvalues: &[Complex<f32>]) -> StabilityType {
+    let scale = eigenvalues.iter().map(|lambda| lambda.norm()).fold(1.0f32, f32::max);
+    let tol = ZERO_TOLERANCE * scale;
+    let is_zero = |x: f32| x.abs() <= tol;
+
+    let any_complex = eigenvalues.iter().any(|lambda| !is_zero(lambda.im));
+    if eigenvalues.iter().all(|lambda| is_zero(lambda.re) && !is_zero(lambda.im)) {
+        StabilityType::Centre
+    } else if eigenvalues.iter().any(|lambda| is_zero(lambda.re)) {
+        StabilityType::NonHyperbolic
+    } else if eigenvalues.iter().all(|lambda| lambda.re < 0.0) {
+        if any_complex {
+            StabilityType::StableFocus
+        } else {
+            StabilityType::StableNode
+        }
+    } else if eigenvalues.iter().all(|lambda| lambda.re > 0.0) {
+        if any_complex {
+            StabilityType::UnstableFocus
+        } else {
+            StabilityType::UnstableNode
+        }
+    } else {
+        StabilityType::Saddle
+    }
+}
+
+impl<Sys: ODESystem> ODEAnalysis<Sys> {
+    /// Analyzes the linear stability of the system at a point.
+    ///
+    /// The Jacobian is evaluated at the start time of the problem, which only
+    /// matters for non-autonomous systems.
+    pub fn linear_stability(&self, data: &LinearStabilityData) -> LinearStability {
+        let mut x = self.problem.initial_values.clone();
+        for (ob, i) in self.variable_index.iter() {
+            if let Some(value) = data.point.get(ob) {
+                x[*i] = *value;
+            }
+        }
+        self.linear_stability_at(&x)
+    }
+
+    /// Analyzes the linear stability of the system at a state vector.
+    pub(super) fn linear_stability_at(&self, x: &DVector<f32>) -> LinearStability {
+        let t = self.problem.start_time;
+
+        let mut dx = DVector::zeros(x.len());
+        self.problem.system.vector_field(&mut dx, x, t);
+        let jac = self.problem.system.jacobian(x, t);
+
+        let mut eigenvalues: Vec<_> = if jac.is_empty() {
+            Vec::new()
+        } else {
+            jac.complex_eigenvalues().iter().copied().collect()
+        };
+        eigenvalues.sort_by(|a, b| a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im)));
+
+        let jacobian = self
+            .variable_index
+            .iter()
+            .map(|(ob, i)| {
+                let row =
+                    self.variable_index.iter().map(|(other, j)| (other.clone(), jac[(*i, *j)]));
+                (ob.clone(), row.collect())
+            })
+            .collect();
+
+        LinearStability {
+            jacobian,
+            classification: classify_eigenvalues(&eigenvalues),
+            eigenvalues: eigenvalues
+                .into_iter()
+                .map(|lambda| Eigenvalue { re: lambda.re, im: lambda.im })
+                .collect(),
+            residual: dx.norm(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classification() {
+        let c = |re: f32, im: f32| Complex::new(re, im);
+        assert_eq!(classify_eigenvalues(&[c(-1.0, 0.0), c(-2.0, 0.0)]), StabilityType::StableNode);
+        assert_eq!(
+            classify_eigenvalues(&[c(1.0, 2.0), c(1.0, -2.0)]),
+            StabilityType::UnstableFocus
+        );
+        assert_eq!(classify_eigenvalues(&[c(-1.0, 0.0), c(2.0, 0.0)]), StabilityType::Saddle);
+        assert_eq!(classify_eigenvalues(&[c(0.0, 1.0), c(0.0, -1.0)]), StabilityType::Centre);
+        assert_eq!(
+            classify_eigenvalues(&[c(0.0, 0.0), c(-1.0, 0.0)]),
+            StabilityType::NonHyperbolic
+        );
+    }
+}
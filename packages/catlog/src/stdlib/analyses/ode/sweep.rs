@@ -0,0 +1,325 @@
+//! Parameter sweeps of ODE analyses.
+//!
+//! A parameter sweep solves the same ODE analysis under many assignments of
+//! rate parameters and initial values, given either as an explicit list or as a
+//! grid. The symbolic system for a model is built only once, after which each
+//! run substitutes its own parameters into the system. Besides the solution,
+//! each run is summarized by the peak and final value of every variable.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::{ODEAnalysis, ODESolution};
+use crate::simulate::ode::ODESystem;
+use crate::zero::QualifiedName;
+
+/// An assignment of values to some of the parameters of an ODE problem.
+///
+/// Parameters not assigned keep the values in the base problem data.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct ParameterAssignment {
+    /// Map from morphism IDs to rate coefficients.
+    pub rates: HashMap<QualifiedName, f32>,
+
+    /// Map from object IDs to growth rates, in analyses that have them.
+    #[cfg_attr(feature = "serde", serde(rename = "growthRates", default))]
+    pub growth_rates: HashMap<QualifiedName, f32>,
+
+    /// Map from object IDs to initial values.
+    #[cfg_attr(feature = "serde", serde(rename = "initialValues"))]
+    pub initial_values: HashMap<QualifiedName, f32>,
+}
+
+/// A grid of parameter values, every combination of which is run.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct ParameterGrid {
+    /// Map from morphism IDs to lists of rate coefficients.
+    pub rates: HashMap<QualifiedName, Vec<f32>>,
+
+    /// Map from object IDs to lists of growth rates, in analyses that have them.
+    #[cfg_attr(feature = "serde", serde(rename = "growthRates", default))]
+    pub growth_rates: HashMap<QualifiedName, Vec<f32>>,
+
+    /// Map from object IDs to lists of initial values.
+    #[cfg_attr(feature = "serde", serde(rename = "initialValues"))]
+    pub initial_values: HashMap<QualifiedName, Vec<f32>>,
+}
+
+/// Specification of the runs in a parameter sweep.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "tag", content = "content"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum ParameterSweep {
+    /// An explicit list of assignments.
+    List(Vec<ParameterAssignment>),
+
+    /// A grid of values.
+    Grid(ParameterGrid),
+}
+
+impl ParameterSweep {
+    /// Enumerates the assignments in the sweep.
+    ///
+    /// The assignments in a grid are enumerated in lexicographic order, with
+    /// the rates before the growth rates before the initial values and each
+    /// sorted by ID.
+    pub fn assignments(&self) -> Vec<ParameterAssignment> {
+        match self {
+            ParameterSweep::List(assignments) => assignments.clone(),
+            ParameterSweep::Grid(grid) => {
+                let axes = [
+                    (SweepTarget::Rate, &grid.rates),
+                    (SweepTarget::GrowthRate, &grid.growth_rates),
+                    (SweepTarget::InitialValue, &grid.initial_values),
+                ]
+                .into_iter()
+                .flat_map(|(target, map)| {
+                    map.iter()
+                        .sorted_by_key(|(id, _)| *id)
+                        .map(move |(id, values)| (target, id, values))
+                })
+                .collect_vec();
+                if axes.is_empty() {
+                    return vec![ParameterAssignment::default()];
+                }
+                axes.iter()
+                    .map(|(_, _, values)| values.iter().copied())
+                    .multi_cartesian_product()
+                    .map(|point| {
+                        let mut assignment = ParameterAssignment::default();
+                        for ((target, id, _), value) in axes.iter().zip(point) {
+                            assignment.target_mut(*target).insert((*id).clone(), value);
+                        }
+                        assignment
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Kind of parameter targeted by an axis of a parameter grid.
+#[derive(Clone, Copy)]
+enum SweepTarget {
+    Rate,
+    GrowthRate,
+    InitialValue,
+}
+
+impl ParameterAssignment {
+    /// Gets the map of values for the given kind of parameter.
+    fn target_mut(&mut self, target: SweepTarget) -> &mut HashMap<QualifiedName, f32> {
+        match target {
+            SweepTarget::Rate => &mut self.rates,
+            SweepTarget::GrowthRate => &mut self.growth_rates,
+            SweepTarget::InitialValue => &mut self.initial_values,
+        }
+    }
+
+    /// Checks that the assignment has no growth rates, for analyses without them.
+    pub fn check_no_growth_rates(&self) -> Result<(), AssignmentError> {
+        match self.growth_rates.keys().min() {
+            Some(ob) => Err(AssignmentError::GrowthRate(ob.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An error in assigning parameters to problem data.
+#[derive(Debug, Error)]
+pub enum AssignmentError {
+    /// A rate is keyed by a morphism that has several rate parameters.
+    #[error("Rate of `{0}` is ambiguous, as it has several rate parameters")]
+    AmbiguousRate(QualifiedName),
+
+    /// A growth rate is assigned in an analysis without growth rates.
+    #[error("Growth rate of `{0}` is not a parameter of the analysis")]
+    GrowthRate(QualifiedName),
+}
+
+/// Problem data that can be modified by a parameter assignment.
+pub trait AssignParameters {
+    /// Overrides the parameters in the data with those in the assignment.
+    fn assign(&mut self, assignment: &ParameterAssignment) -> Result<(), AssignmentError>;
+}
+
+/// Summary statistics of a variable over a single run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct VariableSummary {
+    /// Largest value attained.
+    pub peak: f32,
+
+    /// Earliest time at which the largest value is attained.
+    #[cfg_attr(feature = "serde", serde(rename = "peakTime"))]
+    pub peak_time: f32,
+
+    /// Value at the end of the simulation.
+    #[cfg_attr(feature = "serde", serde(rename = "finalValue"))]
+    pub final_value: f32,
+}
+
+/// A single run in a parameter sweep.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct SweepRun {
+    /// Parameter assignment for the run.
+    pub assignment: ParameterAssignment,
+
+    /// Solution of the ODE problem, if the solver succeeded.
+    pub solution: Option<ODESolution>,
+
+    /// Error message, if the parameters could not be assigned or the solver
+    /// failed.
+    pub error: Option<String>,
+
+    /// Map from object IDs to summary statistics of the solution.
+    pub summary: HashMap<QualifiedName, VariableSummary>,
+}
+
+impl ODESolution {
+    /// Computes summary statistics for each variable in the solution.
+    pub fn summarize(&self) -> HashMap<QualifiedName, VariableSummary> {
+        self.states
+            .iter()
+            .filter_map(|(ob, values)| {
+                let (i_peak, peak) = values
+                    .iter()
+                    .copied()
+                    .enumerate()
+                    .reduce(|best, next| if next.1 > best.1 { next } else { best })?;
+                let summary = VariableSummary {
+                    peak,
+                    peak_time: self.time[i_peak],
+                    final_value: *values.last()?,
+                };
+                Some((ob.clone(), summary))
+            })
+            .collect()
+    }
+}
+
+/// Runs a parameter sweep of an ODE analysis.
+///
+/// For each assignment in the sweep, the base problem data is modified by the
+/// assignment and passed to `analysis`, which should build the numerical ODE
+/// analysis from a symbolic system constructed once beforehand.
+pub fn parameter_sweep<Data, Sys, F>(
+    data: &Data,
+    sweep: &ParameterSweep,
+    mut analysis: F,
+) -> Vec<SweepRun>
+where
+    Data: Clone + AssignParameters,
+    Sys: ODESystem<f32> + ODESystem<f64>,
+    F: FnMut(Data) -> ODEAnalysis<Sys>,
+{
+    sweep
+        .assignments()
+        .into_iter()
+        .map(|assignment| {
+            let mut data = data.clone();
+            if let Err(err) = data.assign(&assignment) {
+                return SweepRun {
+                    assignment,
+                    solution: None,
+                    error: Some(err.to_string()),
+                    summary: HashMap::new(),
+                };
+            }
+            match analysis(data).solve() {
+                Ok(solution) => SweepRun {
+                    assignment,
+                    summary: solution.summarize(),
+                    solution: Some(solution),
+                    error: None,
+                },
+                Err(err) => SweepRun {
+                    assignment,
+                    solution: None,
+                    error: Some(format!("{err:?}")),
+                    summary: HashMap::new(),
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zero::name;
+
+    #[test]
+    fn grid_assignments() {
+        let grid = ParameterGrid {
+            rates: [(name("b"), vec![1.0, 2.0]), (name("a"), vec![0.5])].into_iter().collect(),
+            growth_rates: Default::default(),
+            initial_values: [(name("x"), vec![3.0, 4.0])].into_iter().collect(),
+        };
+        let grid_with_growth = ParameterGrid {
+            growth_rates: [(name("x"), vec![5.0, 6.0])].into_iter().collect(),
+            ..grid.clone()
+        };
+        let assignments = ParameterSweep::Grid(grid).assignments();
+        assert_eq!(assignments.len(), 4);
+        assert_eq!(assignments[1].rates[&name("a")], 0.5);
+        assert_eq!(assignments[1].rates[&name("b")], 1.0);
+        assert_eq!(assignments[1].initial_values[&name("x")], 4.0);
+        assert_eq!(assignments[2].rates[&name("b")], 2.0);
+        assert_eq!(assignments[2].initial_values[&name("x")], 3.0);
+
+        // Growth rates vary between the rates and the initial values.
+        let assignments = ParameterSweep::Grid(grid_with_growth).assignments();
+        assert_eq!(assignments.len(), 8);
+        assert_eq!(assignments[1].growth_rates[&name("x")], 5.0);
+        assert_eq!(assignments[1].initial_values[&name("x")], 4.0);
+        assert_eq!(assignments[2].growth_rates[&name("x")], 6.0);
+        assert!(assignments[0].check_no_growth_rates().is_err());
+    }
+
+    #[test]
+    fn summary_statistics() {
+        let solution = ODESolution {
+            time: vec![0.0, 1.0, 2.0, 3.0],
+            states: [(name("x"), vec![1.0, 3.0, 3.0, 2.0])].into_iter().collect(),
+            events: Vec::new(),
+            stats: Default::default(),
+        };
+        let summary = solution.summarize();
+        let expected = VariableSummary {
+            peak: 3.0,
+            peak_time: 1.0,
+            final_value: 2.0,
+        };
+        assert_eq!(summary[&name("x")], expected);
+    }
+}
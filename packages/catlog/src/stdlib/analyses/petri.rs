@@ -1,7 +1,12 @@
 //! Helpers for analyses on Petri nets.
 
+use std::collections::{BTreeSet, HashMap};
+
+use itertools::Itertools;
+
 use crate::dbl::model::{ModalDblModel, ModalOb, MutDblModel};
 use crate::dbl::theory::Unital;
+use crate::one::category::FgCategory;
 use crate::zero::QualifiedName;
 
 /// Gets the inputs and outputs of a transition in a Petri net.
@@ -19,3 +24,167 @@ pub fn transition_interface(
         .unwrap_or_default();
     (inputs, outputs)
 }
+
+/// A Petri net with places and transitions in a canonical order.
+pub(crate) struct PetriNet {
+    /// IDs of the places, sorted.
+    pub places: Vec<QualifiedName>,
+
+    /// IDs of the transitions, sorted.
+    pub transitions: Vec<QualifiedName>,
+
+    /// Number of tokens consumed by each transition at each place.
+    pub pre: Vec<Vec<u32>>,
+
+    /// Number of tokens produced by each transition at each place.
+    pub post: Vec<Vec<u32>>,
+}
+
+impl PetriNet {
+    /// Extracts the Petri net presented by a model.
+    pub fn new(m: &ModalDblModel<Unital>) -> Self {
+        Self::from_generators(m, m.ob_generators(), m.mor_generators())
+    }
+
+    /// Extracts the Petri net with the given places and transitions.
+    pub fn from_generators(
+        m: &ModalDblModel<Unital>,
+        places: impl Iterator<Item = QualifiedName>,
+        transitions: impl Iterator<Item = QualifiedName>,
+    ) -> Self {
+        let places: Vec<_> = places.sorted().collect();
+        let place_index: HashMap<_, _> =
+            places.iter().enumerate().map(|(i, place)| (place.clone(), i)).collect();
+        let transitions: Vec<_> = transitions.sorted().collect();
+
+        let count = |obs: Vec<ModalOb>| {
+            let mut counts = vec![0; places.len()];
+            for ob in obs {
+                if let ModalOb::Generator(place) = ob
+                    && let Some(i) = place_index.get(&place)
+                {
+                    counts[*i] += 1;
+                }
+            }
+            counts
+        };
+        let (pre, post) = transitions
+            .iter()
+            .map(|t| {
+                let (inputs, outputs) = transition_interface(m, t);
+                (count(inputs), count(outputs))
+            })
+            .unzip();
+        Self { places, transitions, pre, post }
+    }
+
+    /// Computes the incidence matrix of the net, with a row for each place
+    /// and a column for each transition.
+    pub fn incidence(&self) -> Vec<Vec<i32>> {
+        (0..self.places.len())
+            .map(|p| {
+                std::iter::zip(&self.pre, &self.post)
+                    .map(|(pre, post)| post[p] as i32 - pre[p] as i32)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Computes the minimal semiflows of a matrix with integer entries.
+///
+/// A *semiflow* is a nonzero vector `y` with nonnegative integer entries such
+/// that `yᵀ A = 0`, where the rows of `A` are given by `rows`. The semiflows
+/// with minimal support, normalized so that their entries are coprime, generate
+/// all semiflows as a cone. They are computed by the Farkas algorithm, as
+/// described by [Colom & Silva](https://doi.org/10.1007/3-540-53863-1_33),
+/// eliminating the columns of the matrix one at a time.
+///
+/// Applied to the stoichiometric matrix of a Petri net, the semiflows are the
+/// P-semiflows of the net; applied to its transpose, the T-semiflows.
+pub fn semiflows(rows: &[Vec<i32>]) -> Vec<Vec<u32>> {
+    let n = rows.len();
+    let m = rows.first().map_or(0, |row| row.len());
+
+    // Each row is a pair of the remaining entries of the matrix and a
+    // combination of the original rows.
+    let mut table: Vec<(Vec<i64>, Vec<i64>)> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let entries = row.iter().map(|a| i64::from(*a)).collect();
+            let combination = (0..n).map(|k| if k == i { 1 } else { 0 }).collect();
+            (entries, combination)
+        })
+        .collect();
+
+    for j in 0..m {
+        let (mut next, nonzero): (Vec<_>, Vec<_>) =
+            table.into_iter().partition(|(entries, _)| entries[j] == 0);
+        let (positive, negative): (Vec<_>, Vec<_>) =
+            nonzero.into_iter().partition(|(entries, _)| entries[j] > 0);
+        for (p_entries, p_comb) in positive.iter() {
+            for (q_entries, q_comb) in negative.iter() {
+                let (a, b) = (-q_entries[j], p_entries[j]);
+                let combine = |u: &[i64], v: &[i64]| -> Vec<i64> {
+                    std::iter::zip(u, v).map(|(x, y)| a * x + b * y).collect()
+                };
+                let mut row = (combine(p_entries, q_entries), combine(p_comb, q_comb));
+                let divisor = row.0.iter().chain(row.1.iter()).fold(0, |d, x| gcd(d, *x));
+                if divisor > 1 {
+                    row.0.iter_mut().chain(row.1.iter_mut()).for_each(|x| *x /= divisor);
+                }
+                next.push(row);
+            }
+        }
+
+        // Discard rows whose support strictly contains that of another row.
+        let supports = next.iter().map(|(_, comb)| support(comb)).collect_vec();
+        table = std::iter::zip(next, supports.iter())
+            .filter(|(_, s)| !supports.iter().any(|t| t.len() < s.len() && t.is_subset(s)))
+            .map(|(row, _)| row)
+            .collect();
+    }
+
+    // Of the rows with the same support, which are proportional, keep one.
+    table
+        .into_iter()
+        .map(|(_, comb)| comb)
+        .unique_by(|comb| support(comb).into_iter().collect_vec())
+        .map(|comb| comb.into_iter().map(|x| x as u32).collect_vec())
+        .sorted_by_key(|y| y.iter().map(|x| *x == 0).collect_vec())
+        .collect()
+}
+
+fn support(y: &[i64]) -> BTreeSet<usize> {
+    y.iter().enumerate().filter(|(_, x)| **x != 0).map(|(i, _)| i).collect()
+}
+
+/// Greatest common divisor of two integers, which is nonnegative.
+pub(crate) fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semiflows_of_matrix() {
+        // Stoichiometry of the SIR model, with transitions for infection
+        // (S + I -> 2I) and recovery (I -> R).
+        let sir = vec![vec![-1, 0], vec![1, -1], vec![0, 1]];
+        assert_eq!(semiflows(&sir), vec![vec![1, 1, 1]]);
+
+        // Two independent pools exchanging mass: A <-> B and C -> 2D.
+        let rows = vec![vec![-1, 1, 0], vec![1, -1, 0], vec![0, 0, -1], vec![0, 0, 2]];
+        assert_eq!(semiflows(&rows), vec![vec![1, 1, 0, 0], vec![0, 0, 2, 1]]);
+
+        // A pure source has no semiflows.
+        assert!(semiflows(&[vec![1]]).is_empty());
+    }
+}
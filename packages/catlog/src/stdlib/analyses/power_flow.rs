@@ -0,0 +1,514 @@
+//! DC power flow and contingency analysis of power systems.
+//!
+//! The DC power flow is the standard linearization of the power-flow equations
+//! of an AC network: voltage magnitudes are fixed, resistances are neglected,
+//! and phase differences across branches are small, so that the active power
+//! flowing through a branch from bus `i` to bus `j` is `b (θ_i - θ_j)`, where
+//! `b` is the susceptance of the branch and `θ` are the voltage angles. The
+//! angles then solve a linear system given by the susceptance-weighted
+//! Laplacian of the network and the power injected at each bus.
+//!
+//! Each connected sub-network, or *island*, is solved separately, with one bus,
+//! the *slack bus*, fixed at angle zero and absorbing any imbalance between
+//! generation and load, as in PyPSA.
+
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use crate::dbl::model::{DiscreteDblModel, FpDblModel};
+use crate::one::{FgCategory, QualifiedPath};
+use crate::zero::QualifiedName;
+
+use super::temporal::strongly_connected_components;
+
+/// Data defining a DC power-flow problem for a model.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct PowerFlowData {
+    /// Map from bus IDs to active power injected, positive for generation and
+    /// negative for load.
+    pub injections: HashMap<QualifiedName, f32>,
+
+    /// Map from branch IDs to susceptances (nonnegative reals).
+    ///
+    /// Branches not in the map carry no power and do not connect buses.
+    pub susceptances: HashMap<QualifiedName, f32>,
+
+    /// Map from branch IDs to thermal ratings, the largest power that the
+    /// branch can carry in either direction.
+    ///
+    /// Branches not in the map are never overloaded.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ratings: HashMap<QualifiedName, f32>,
+
+    /// ID of the preferred slack bus.
+    ///
+    /// In islands not containing this bus, the first bus is the slack bus.
+    #[cfg_attr(feature = "serde", serde(rename = "slackBus", default))]
+    pub slack_bus: Option<QualifiedName>,
+}
+
+/// A connected sub-network of a power system.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct Island {
+    /// IDs of the buses in the island.
+    pub buses: Vec<QualifiedName>,
+
+    /// ID of the slack bus of the island.
+    #[cfg_attr(feature = "serde", serde(rename = "slackBus"))]
+    pub slack_bus: QualifiedName,
+
+    /// Power injected at the slack bus, in addition to its given injection, to
+    /// balance generation and load in the island.
+    pub imbalance: f32,
+}
+
+/// Result of a DC power-flow analysis.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct PowerFlow {
+    /// Map from bus IDs to voltage angles, in radians.
+    pub angles: HashMap<QualifiedName, f32>,
+
+    /// Map from branch IDs to power flowing from the domain to the codomain.
+    pub flows: HashMap<QualifiedName, f32>,
+
+    /// Map from rated branch IDs to their loading, the ratio of the magnitude
+    /// of the flow to the rating.
+    pub loading: HashMap<QualifiedName, f32>,
+
+    /// IDs of branches whose flow exceeds their rating.
+    pub overloaded: Vec<QualifiedName>,
+
+    /// Islands of the network.
+    pub islands: Vec<Island>,
+}
+
+/// Result of removing a single branch from a power system.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct Contingency {
+    /// ID of the branch removed.
+    pub outage: QualifiedName,
+
+    /// IDs of the remaining branches whose flow exceeds their rating.
+    pub overloaded: Vec<QualifiedName>,
+
+    /// Sub-networks cut off from the slack bus of their island by the outage,
+    /// as lists of bus IDs.
+    pub islanded: Vec<Vec<QualifiedName>>,
+
+    /// Power flow in the network without the branch.
+    #[cfg_attr(feature = "serde", serde(rename = "powerFlow"))]
+    pub power_flow: PowerFlow,
+}
+
+/// Result of an N-1 contingency analysis.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ContingencyAnalysis {
+    /// Power flow in the intact network.
+    pub base: PowerFlow,
+
+    /// Results of removing each branch in turn.
+    pub contingencies: Vec<Contingency>,
+
+    /// Whether the network is N-1 secure, meaning that no single outage
+    /// overloads a branch or islands part of the network.
+    pub secure: bool,
+}
+
+/// An error in the data for a power-flow analysis.
+#[derive(Debug, Error)]
+pub enum PowerFlowError {
+    /// A branch has a negative or undefined susceptance.
+    #[error("Susceptance of branch `{0}` is not a nonnegative number")]
+    Susceptance(QualifiedName),
+}
+
+/// DC power-flow analysis of a model.
+pub struct PowerFlowAnalysis {
+    bus_ob_type: QualifiedName,
+    branch_mor_types: Vec<QualifiedPath>,
+}
+
+impl PowerFlowAnalysis {
+    /// Constructs a power-flow analysis with buses the objects of given type.
+    pub fn new(ob_type: QualifiedName) -> Self {
+        Self {
+            bus_ob_type: ob_type,
+            branch_mor_types: Default::default(),
+        }
+    }
+
+    /// Adds a type of morphism to be treated as branches between buses.
+    pub fn add_branch_type(mut self, mor_type: QualifiedPath) -> Self {
+        self.branch_mor_types.push(mor_type);
+        self
+    }
+
+    /// Solves the DC power flow of a model.
+    pub fn power_flow(
+        &self,
+        model: &DiscreteDblModel,
+        data: &PowerFlowData,
+    ) -> Result<PowerFlow, PowerFlowError> {
+        Ok(self.network(model, data)?.solve(None))
+    }
+
+    /// Solves the DC power flow of a model with each branch removed in turn.
+    pub fn contingencies(
+        &self,
+        model: &DiscreteDblModel,
+        data: &PowerFlowData,
+    ) -> Result<ContingencyAnalysis, PowerFlowError> {
+        let network = self.network(model, data)?;
+        let base = network.solve(None);
+        let slack_buses: HashSet<_> = base.islands.iter().map(|i| &i.slack_bus).collect();
+
+        let contingencies: Vec<_> = (0..network.branches.len())
+            .map(|k| {
+                let power_flow = network.solve(Some(k));
+                let islanded = power_flow
+                    .islands
+                    .iter()
+                    .filter(|island| !island.buses.iter().any(|bus| slack_buses.contains(bus)))
+                    .map(|island| island.buses.clone())
+                    .collect();
+                Contingency {
+                    outage: network.branches[k].id.clone(),
+                    overloaded: power_flow.overloaded.clone(),
+                    islanded,
+                    power_flow,
+                }
+            })
+            .collect();
+        let secure = contingencies.iter().all(|c| c.overloaded.is_empty() && c.islanded.is_empty());
+        Ok(ContingencyAnalysis { base, contingencies, secure })
+    }
+
+    fn network(
+        &self,
+        model: &DiscreteDblModel,
+        data: &PowerFlowData,
+    ) -> Result<Network, PowerFlowError> {
+        let bus_index: IndexMap<_, _> = model
+            .ob_generators_with_type(&self.bus_ob_type)
+            .enumerate()
+            .map(|(i, x)| (x, i))
+            .collect();
+        let mut branches = Vec::new();
+        for mor_type in self.branch_mor_types.iter() {
+            for mor in model.mor_generators_with_type(mor_type) {
+                let (dom, cod) = (model.mor_generator_dom(&mor), model.mor_generator_cod(&mor));
+                let susceptance = data.susceptances.get(&mor).copied().unwrap_or_default();
+                if susceptance < 0.0 || susceptance.is_nan() {
+                    return Err(PowerFlowError::Susceptance(mor));
+                }
+                branches.push(Branch {
+                    source: *bus_index.get(&dom).unwrap(),
+                    target: *bus_index.get(&cod).unwrap(),
+                    susceptance: susceptance.into(),
+                    rating: data.ratings.get(&mor).copied(),
+                    id: mor,
+                });
+            }
+        }
+        let injections = bus_index
+            .keys()
+            .map(|id| data.injections.get(id).copied().unwrap_or_default().into())
+            .collect();
+        let slack_bus = data.slack_bus.as_ref().and_then(|id| bus_index.get_index_of(id));
+        Ok(Network {
+            buses: bus_index.into_keys().collect(),
+            branches,
+            injections,
+            slack_bus,
+        })
+    }
+}
+
+/// A branch between two buses.
+struct Branch {
+    id: QualifiedName,
+    source: usize,
+    target: usize,
+    susceptance: f64,
+    rating: Option<f32>,
+}
+
+/// A power network, with buses and branches indexed by position.
+struct Network {
+    buses: Vec<QualifiedName>,
+    branches: Vec<Branch>,
+    injections: Vec<f64>,
+    slack_bus: Option<usize>,
+}
+
+impl Network {
+    /// Solves the DC power flow, optionally without one of the branches.
+    fn solve(&self, outage: Option<usize>) -> PowerFlow {
+        let n = self.buses.len();
+        let branches = || {
+            self.branches
+                .iter()
+                .enumerate()
+                .filter(move |(k, b)| Some(*k) != outage && b.susceptance != 0.0)
+                .map(|(_, b)| b)
+        };
+        let mut laplacian = vec![vec![0.0; n]; n];
+        for b in branches() {
+            let (i, j) = (b.source, b.target);
+            laplacian[i][j] -= b.susceptance;
+            laplacian[j][i] -= b.susceptance;
+            laplacian[i][i] += b.susceptance;
+            laplacian[j][j] += b.susceptance;
+        }
+
+        let mut angles = vec![0.0; n];
+        let mut islands = Vec::new();
+        for component in self.components(branches()) {
+            let slack = self.slack_bus.filter(|s| component.contains(s)).unwrap_or(component[0]);
+            let others: Vec<_> = component.iter().copied().filter(|i| *i != slack).collect();
+            let matrix = others
+                .iter()
+                .map(|i| others.iter().map(|j| laplacian[*i][*j]).collect())
+                .collect();
+            let rhs = others.iter().map(|i| self.injections[*i]).collect();
+            for (i, theta) in std::iter::zip(&others, solve_linear(matrix, rhs)) {
+                angles[*i] = theta;
+            }
+            let imbalance = -component.iter().map(|i| self.injections[*i]).sum::<f64>();
+            islands.push(Island {
+                buses: component.iter().map(|i| self.buses[*i].clone()).collect(),
+                slack_bus: self.buses[slack].clone(),
+                imbalance: imbalance as f32,
+            });
+        }
+
+        let mut flows = HashMap::new();
+        let mut loading = HashMap::new();
+        let mut overloaded = Vec::new();
+        let remaining = self.branches.iter().enumerate().filter(|(k, _)| Some(*k) != outage);
+        for (_, b) in remaining {
+            let flow = (b.susceptance * (angles[b.source] - angles[b.target])) as f32;
+            flows.insert(b.id.clone(), flow);
+            if let Some(rating) = b.rating {
+                let load = flow.abs() / rating;
+                loading.insert(b.id.clone(), load);
+                if flow.abs() > rating {
+                    overloaded.push(b.id.clone());
+                }
+            }
+        }
+        PowerFlow {
+            angles: std::iter::zip(&self.buses, angles)
+                .map(|(id, x)| (id.clone(), x as f32))
+                .collect(),
+            flows,
+            loading,
+            overloaded,
+            islands,
+        }
+    }
+
+    /// Computes the connected components of the buses, in order of their
+    /// first buses.
+    ///
+    /// These are the strongly connected components of the graph with branches
+    /// in both directions.
+    fn components<'a>(&self, branches: impl Iterator<Item = &'a Branch>) -> Vec<Vec<usize>> {
+        let mut neighbors = vec![Vec::new(); self.buses.len()];
+        for b in branches {
+            neighbors[b.source].push(b.target);
+            neighbors[b.target].push(b.source);
+        }
+        let mut components = strongly_connected_components(&neighbors);
+        components.iter_mut().for_each(|component| component.sort());
+        components.sort();
+        components
+    }
+}
+
+/// Relative tolerance below which a pivot is treated as zero.
+const PIVOT_TOLERANCE: f64 = 1e-12;
+
+/// Solves a square linear system by Gaussian elimination with partial pivoting.
+///
+/// Variables whose pivot vanishes relative to the largest entry of the matrix,
+/// as happens for singular systems, are set to zero.
+fn solve_linear(mut matrix: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Vec<f64> {
+    let n = rhs.len();
+    let scale = matrix.iter().flatten().fold(0.0, |m: f64, x| m.max(x.abs()));
+    let tolerance = PIVOT_TOLERANCE * scale;
+    for col in 0..n {
+        let pivot = (col..n).max_by(|i, j| matrix[*i][col].abs().total_cmp(&matrix[*j][col].abs()));
+        let Some(pivot) = pivot.filter(|p| matrix[*p][col].abs() > tolerance) else {
+            continue;
+        };
+        matrix.swap(col, pivot);
+        rhs.swap(col, pivot);
+        for row in (col + 1)..n {
+            let factor = matrix[row][col] / matrix[col][col];
+            if factor == 0.0 {
+                continue;
+            }
+            let (upper, lower) = matrix.split_at_mut(row);
+            for (x, y) in std::iter::zip(&mut lower[0][col..], &upper[col][col..]) {
+                *x -= factor * y;
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        if matrix[row][row].abs() <= tolerance {
+            continue;
+        }
+        let sum: f64 = ((row + 1)..n).map(|k| matrix[row][k] * x[k]).sum();
+        x[row] = (rhs[row] - sum) / matrix[row][row];
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::model::MutDblModel;
+    use crate::one::Path;
+    use crate::stdlib::theories::th_power_system;
+    use crate::zero::name;
+
+    #[test]
+    fn triangle_with_spur() {
+        // A generator `a` supplying a load `c` through a triangle of lines, with
+        // a spur from the load to an idle bus `d`.
+        let mut model = DiscreteDblModel::new(Rc::new(th_power_system()));
+        for bus in ["a", "b", "c", "d"] {
+            model.add_ob(name(bus), name("Bus"));
+        }
+        for (line, dom, cod) in
+            [("ab", "a", "b"), ("bc", "b", "c"), ("ca", "c", "a"), ("cd", "c", "d")]
+        {
+            model.add_mor(name(line), name(dom), name(cod), Path::Id(name("Bus")));
+        }
+        let data = PowerFlowData {
+            injections: [(name("a"), 1.0), (name("c"), -1.0)].into(),
+            susceptances: ["ab", "bc", "ca", "cd"].map(|line| (name(line), 1.0)).into(),
+            ratings: ["ab", "bc", "ca"].map(|line| (name(line), 0.9)).into(),
+            slack_bus: None,
+        };
+        let analysis = PowerFlowAnalysis::new(name("Bus")).add_branch_type(Path::Id(name("Bus")));
+
+        // Two thirds of the power flows directly, one third through `b`.
+        let flow = analysis.power_flow(&model, &data).unwrap();
+        let close = |x: f32, y: f32| (x - y).abs() < 1e-6;
+        assert!(close(flow.flows[&name("ab")], 1.0 / 3.0));
+        assert!(close(flow.flows[&name("bc")], 1.0 / 3.0));
+        assert!(close(flow.flows[&name("ca")], -2.0 / 3.0));
+        assert!(close(flow.flows[&name("cd")], 0.0));
+        assert!(close(flow.angles[&name("c")], -2.0 / 3.0));
+        assert!(flow.overloaded.is_empty());
+        assert_eq!(flow.islands.len(), 1);
+        assert_eq!(flow.islands[0].slack_bus, name("a"));
+        assert!(close(flow.islands[0].imbalance, 0.0));
+
+        let result = analysis.contingencies(&model, &data).unwrap();
+        assert_eq!(result.base, flow);
+        assert!(!result.secure);
+        let outages: Vec<_> = result.contingencies.iter().map(|c| c.outage.clone()).collect();
+        assert_eq!(outages, ["ab", "bc", "ca", "cd"].map(name));
+
+        // Losing either path overloads the other.
+        let [ab, _, ca, cd] = &result.contingencies[..] else {
+            panic!("Should have one contingency per line");
+        };
+        assert_eq!(ca.overloaded, vec![name("ab"), name("bc")]);
+        assert!(ca.islanded.is_empty());
+        assert_eq!(ab.overloaded, vec![name("ca")]);
+        assert!(close(ab.power_flow.flows[&name("ca")], -1.0));
+
+        // Losing the spur islands the idle bus.
+        assert!(cd.overloaded.is_empty());
+        assert_eq!(cd.islanded, vec![vec![name("d")]]);
+        assert_eq!(cd.power_flow.islands.len(), 2);
+        assert!(!cd.power_flow.flows.contains_key(&name("cd")));
+    }
+
+    #[test]
+    fn unbalanced_islands() {
+        // Two disconnected pairs of buses, with more load than generation.
+        let mut model = DiscreteDblModel::new(Rc::new(th_power_system()));
+        for bus in ["a", "b", "c", "d"] {
+            model.add_ob(name(bus), name("Bus"));
+        }
+        model.add_mor(name("ab"), name("a"), name("b"), Path::Id(name("Bus")));
+        model.add_mor(name("cd"), name("c"), name("d"), Path::single(name("Passive")));
+        let data = PowerFlowData {
+            injections: [(name("a"), 1.0), (name("b"), -2.0), (name("d"), -1.0)].into(),
+            susceptances: [(name("ab"), 2.0), (name("cd"), 4.0)].into(),
+            slack_bus: Some(name("b")),
+            ..Default::default()
+        };
+        let analysis = PowerFlowAnalysis::new(name("Bus"))
+            .add_branch_type(Path::Id(name("Bus")))
+            .add_branch_type(Path::single(name("Passive")));
+        let flow = analysis.power_flow(&model, &data).unwrap();
+
+        let slack_buses: Vec<_> = flow.islands.iter().map(|i| i.slack_bus.clone()).collect();
+        assert_eq!(slack_buses, vec![name("b"), name("c")]);
+        let imbalances: Vec<_> = flow.islands.iter().map(|i| i.imbalance).collect();
+        assert_eq!(imbalances, vec![1.0, 1.0]);
+        assert_eq!(flow.flows[&name("ab")], 1.0);
+        assert_eq!(flow.flows[&name("cd")], 1.0);
+        assert_eq!(flow.angles[&name("d")], -0.25);
+
+        // Tiny susceptances give large angles but the same flows.
+        let scaled = PowerFlowData {
+            susceptances: [(name("ab"), 2e-20), (name("cd"), 4e-20)].into(),
+            ..data.clone()
+        };
+        let flow = analysis.power_flow(&model, &scaled).unwrap();
+        assert!((flow.flows[&name("ab")] - 1.0).abs() < 1e-6);
+        assert!((flow.angles[&name("d")] + 0.25e20).abs() < 1e14);
+
+        // Negative susceptances are rejected.
+        let negative = PowerFlowData {
+            susceptances: [(name("ab"), 2.0), (name("cd"), -4.0)].into(),
+            ..data
+        };
+        assert!(matches!(
+            analysis.power_flow(&model, &negative),
+            Err(PowerFlowError::Susceptance(id)) if id == name("cd")
+        ));
+    }
+}
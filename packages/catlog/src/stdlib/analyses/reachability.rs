@@ -1,12 +1,11 @@
 //! Reachability analyses of models.
 
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
-use crate::dbl::modal::model::{ModalDblModel, ModalOb};
+use crate::dbl::modal::model::ModalDblModel;
 use crate::dbl::theory::Unital;
-use crate::one::category::FgCategory;
-use crate::stdlib::analyses::petri::transition_interface;
+use crate::stdlib::analyses::petri::PetriNet;
 use crate::zero::QualifiedName;
 
 #[cfg(feature = "serde")]
@@ -30,74 +29,96 @@ pub struct ReachabilityProblemData {
     pub forbidden: HashMap<QualifiedName, i32>,
 }
 
+/// A firing sequence in a Petri net, with the markings that it passes through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct FiringSequence {
+    /// IDs of the transitions fired, in order.
+    pub transitions: Vec<QualifiedName>,
+
+    /// Markings before and after each firing, as maps from place IDs to
+    /// numbers of tokens. There is one more marking than transitions.
+    pub markings: Vec<HashMap<QualifiedName, i32>>,
+}
+
 /// The "Region Algebra for Petri Nets" algorithm from Ch 31 of
 /// ([Clarke et al 2018](crate::refs::HandbookModelChecking)):
 /// "Symbolic Model Checking in Non Boolean Domains".
+///
+/// Returns whether the forbidden marking is *not* coverable from the initial
+/// marking. See [`subreachability_witness`] for how it is covered otherwise.
 pub fn subreachability(m: &ModalDblModel<Unital>, data: ReachabilityProblemData) -> bool {
+    subreachability_witness(m, data).is_none()
+}
+
+/// Finds a shortest firing sequence from the initial marking to a marking
+/// covering the forbidden marking, if there is one.
+///
+/// The region algebra algorithm of [`subreachability`] computes the regions
+/// that can access the forbidden region by increasingly many firings. Each
+/// region is generated by markings that remember the transition and marking
+/// from which they were computed, so that the chain can be replayed forward
+/// from the first region containing the initial marking.
+pub fn subreachability_witness(
+    m: &ModalDblModel<Unital>,
+    data: ReachabilityProblemData,
+) -> Option<FiringSequence> {
     // Convert model into a pair of matrices
     //--------------------------------------
-
-    // Get a canonical ordering of the objects
-    let ob_vec: Vec<_> = m.ob_generators().sorted().collect();
-    let ob_inv: HashMap<_, _> = ob_vec.iter().enumerate().map(|(x, y)| (y.clone(), x)).collect();
-    let n_p = ob_vec.len();
+    let net = PetriNet::new(m);
+    let (n_p, n_t) = (net.places.len(), net.transitions.len());
     if n_p == 0 {
-        return true;
-    }
-
-    // Get a canonical ordering of the homs
-    let hom_vec: Vec<_> = m.mor_generators().sorted().collect();
-
-    let hom_inv: HashMap<_, _> = hom_vec.iter().enumerate().map(|(x, y)| (y.clone(), x)).collect();
-    let n_t = hom_vec.len();
-
-    // Populate the I/O matrices from the hom src/tgt data
-    let mut i_mat = vec![vec![0; n_t]; n_p];
-    let mut o_mat = vec![vec![0; n_t]; n_p];
-
-    for e in m.mor_generators() {
-        let e_idx = *hom_inv.get(&e).unwrap();
-        let (inputs, outputs) = transition_interface(m, &e);
-        for ob in inputs {
-            if let ModalOb::Generator(u) = ob {
-                i_mat[*ob_inv.get(&u).unwrap()][e_idx] += 1;
-            }
-        }
-        for ob in outputs {
-            if let ModalOb::Generator(u) = ob {
-                o_mat[*ob_inv.get(&u).unwrap()][e_idx] += 1;
-            }
-        }
+        return None;
     }
+    let i_mat: Vec<Vec<_>> =
+        (0..n_p).map(|p| (0..n_t).map(|t| net.pre[t][p] as i32).collect()).collect();
+    let o_mat: Vec<Vec<_>> =
+        (0..n_p).map(|p| (0..n_t).map(|t| net.post[t][p] as i32).collect()).collect();
     let (i_mat_, o_mat_) = (&i_mat, &o_mat);
 
     // Parse input data
     //-----------------
-    let mut f: Vec<Vec<_>> =
-        vec![ob_vec.iter().map(|u| *data.forbidden.get(u).unwrap_or(&0)).collect()];
-    let init: Vec<_> = ob_vec.iter().map(|u| *data.tokens.get(u).unwrap_or(&0)).collect();
+    // Each generator of a region is stored once, along with the transition
+    // and generator of the previous region that it was computed from.
+    let forbidden: Vec<_> =
+        net.places.iter().map(|u| *data.forbidden.get(u).unwrap_or(&0)).collect();
+    let init: Vec<_> = net.places.iter().map(|u| *data.tokens.get(u).unwrap_or(&0)).collect();
+    let mut generators = vec![(forbidden, None)];
+    let mut f: Vec<usize> = vec![0];
 
-    // Apply recursive algorithm until fix point
-    //------------------------------------------
-    loop {
+    // Apply recursive algorithm until fix point, or until the region contains
+    // the initial marking
+    //------------------------------------------------------------------------
+    let covering = |f: &[usize], generators: &[(Vec<i32>, _)]| {
+        f.iter().copied().find(|k| (0..n_p).all(|p| generators[*k].0[p] <= init[p]))
+    };
+    let mut found = covering(&f, &generators);
+    while found.is_none() {
         // For each transition + region (in `f`) pair `(t,v)`, compute the
         // region that accesses `v` via firing `t`.
-        let pre: Vec<Vec<_>> = (0..n_t)
+        let generators_ = &generators;
+        let pre: Vec<(Vec<_>, _)> = (0..n_t)
             .flat_map(|t| {
-                f.iter().map(move |v| {
-                    (0..n_p).map(move |p| {
+                f.iter().map(move |k| {
+                    let v = &generators_[*k].0;
+                    let z = (0..n_p).map(move |p| {
                         std::cmp::max(i_mat_[p][t], v[p] - (o_mat_[p][t] - i_mat_[p][t]))
-                    })
+                    });
+                    (z.collect(), (t, *k))
                 })
             })
-            .map(|z| z.collect())
             .collect();
 
         // Filter `pre` for regions which are not already in `f`.
-        let newstuff: Vec<Vec<_>> = pre
+        let newstuff: Vec<_> = pre
             .into_iter()
-            .filter(|v| f.iter().all(|old| (0..n_p).any(|p| v[p] < old[p])))
-            .unique()
+            .filter(|(v, _)| f.iter().all(|old| (0..n_p).any(|p| v[p] < generators[*old].0[p])))
+            .unique_by(|(v, _)| v.clone())
             .collect();
 
         // We have terminated when there is nothing new generated by `pre`
@@ -106,21 +127,406 @@ pub fn subreachability(m: &ModalDblModel<Unital>, data: ReachabilityProblemData)
         }
 
         // Update f with new stuff and remove extraneous old stuff
-        f.retain(|v| newstuff.iter().all(|n| (0..n_p).any(|p| v[p] < n[p])));
-        f.extend(newstuff);
+        f.retain(|k| {
+            let v = &generators[*k].0;
+            newstuff.iter().all(|(n, _)| (0..n_p).any(|p| v[p] < n[p]))
+        });
+        for (v, parent) in newstuff {
+            f.push(generators.len());
+            generators.push((v, Some(parent)));
+        }
+        found = covering(&f, &generators);
+    }
+
+    // Replay the chain of generators forward from the initial marking, which
+    // covers each generator in turn.
+    //----------------------------------------------------------------------
+    let as_map = |marking: &[i32]| -> HashMap<_, _> {
+        std::iter::zip(&net.places, marking).map(|(u, x)| (u.clone(), *x)).collect()
+    };
+    let mut marking = init.clone();
+    let mut sequence = FiringSequence {
+        transitions: Vec::new(),
+        markings: vec![as_map(&marking)],
+    };
+    let mut k = found?;
+    while let Some((t, next)) = generators[k].1 {
+        for p in 0..n_p {
+            marking[p] += o_mat[p][t] - i_mat[p][t];
+        }
+        sequence.transitions.push(net.transitions[t].clone());
+        sequence.markings.push(as_map(&marking));
+        k = next;
+    }
+    Some(sequence)
+}
+
+/// Data defining a forward exploration of the state space of a Petri net.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct StateSpaceData {
+    /// Map from place IDs to number of initial tokens of that type.
+    pub tokens: HashMap<QualifiedName, u32>,
+
+    /// Map from place IDs to number of tokens in a target marking, if any.
+    ///
+    /// When given, a shortest firing sequence reaching the target is sought.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target: Option<HashMap<QualifiedName, u32>>,
+
+    /// Maximum number of states in the reachability graph and of nodes in the
+    /// coverability tree.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "maxStates", default = "default_max_states")
+    )]
+    pub max_states: usize,
+}
+
+#[cfg(feature = "serde")]
+fn default_max_states() -> usize {
+    10_000
+}
+
+/// A firing of a transition between two states of a reachability graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct FiringEdge {
+    /// Index of the marking before firing.
+    pub source: usize,
+
+    /// ID of the transition fired.
+    pub transition: QualifiedName,
+
+    /// Index of the marking after firing.
+    pub target: usize,
+}
+
+/// Reachability graph of a Petri net, explored breadth-first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ReachabilityGraph {
+    /// Reachable markings, as token counts in the order of the places. The
+    /// initial marking comes first.
+    pub markings: Vec<Vec<u32>>,
+
+    /// Firings between the markings.
+    pub edges: Vec<FiringEdge>,
+
+    /// Whether every reachable marking was explored, which is the case unless
+    /// the state bound was hit.
+    pub complete: bool,
+}
+
+/// A node in the coverability tree of a Petri net.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct CoverabilityNode {
+    /// Generalized marking in the order of the places, where an absent count
+    /// stands for ω, an arbitrarily large number of tokens.
+    pub marking: Vec<Option<u32>>,
+
+    /// Index of the parent node, absent for the root.
+    pub parent: Option<usize>,
+
+    /// ID of the transition fired from the parent, absent for the root.
+    pub transition: Option<QualifiedName>,
+}
+
+/// Karp–Miller coverability tree of a Petri net.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct CoverabilityTree {
+    /// Nodes of the tree. The root comes first.
+    pub nodes: Vec<CoverabilityNode>,
+
+    /// Whether the tree was fully constructed within the bound on nodes.
+    pub complete: bool,
+}
+
+/// State space of a Petri net, explored forward from an initial marking.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct StateSpace {
+    /// IDs of the places, in the order used by markings.
+    pub places: Vec<QualifiedName>,
+
+    /// IDs of the transitions.
+    pub transitions: Vec<QualifiedName>,
+
+    /// Reachability graph, possibly truncated.
+    pub reachability: ReachabilityGraph,
+
+    /// Coverability tree, which is finite even when the net is unbounded.
+    pub coverability: CoverabilityTree,
+
+    /// Map from place IDs to the largest number of tokens reachable at the
+    /// place, absent when the place is unbounded.
+    ///
+    /// Absent altogether when the coverability tree is incomplete, since the
+    /// bounds are then unknown.
+    pub bounds: Option<HashMap<QualifiedName, Option<u32>>>,
+
+    /// Indices of the explored markings at which no transition is enabled.
+    pub deadlocks: Vec<usize>,
+
+    /// IDs of the transitions that can never fire, absent when the
+    /// coverability tree is incomplete.
+    #[cfg_attr(feature = "serde", serde(rename = "deadTransitions"))]
+    pub dead_transitions: Option<Vec<QualifiedName>>,
+
+    /// Shortest firing sequence from the initial marking to the target
+    /// marking, if one was requested and found among the explored markings.
+    pub witness: Option<Vec<QualifiedName>>,
+}
+
+/// Explores the state space of a Petri net forward from an initial marking.
+///
+/// The reachability graph is explored breadth-first up to the state bound. The
+/// Karp–Miller coverability tree is also constructed, from which boundedness
+/// and dead transitions are decided. They are left undecided when the tree is
+/// truncated by the state bound.
+pub fn state_space(m: &ModalDblModel<Unital>, data: StateSpaceData) -> StateSpace {
+    let net = PetriNet::new(m);
+    let initial = net.marking(&data.tokens);
+    let (reachability, deadlocks) = net.reachability_graph(initial.clone(), data.max_states);
+    let coverability = net.coverability_tree(initial, data.max_states);
+
+    let complete = coverability.complete;
+    let bounds = net
+        .places
+        .iter()
+        .enumerate()
+        .map(|(i, place)| {
+            let bound = coverability
+                .nodes
+                .iter()
+                .map(|node| node.marking[i])
+                .try_fold(0, |a, b| b.map(|b| a.max(b)));
+            (place.clone(), bound)
+        })
+        .collect();
+    let bounds = complete.then_some(bounds);
+    let dead_transitions = (0..net.transitions.len())
+        .filter(|t| {
+            coverability
+                .nodes
+                .iter()
+                .all(|node| net.fire_omega(*t, &node.marking).is_none())
+        })
+        .map(|t| net.transitions[t].clone())
+        .collect();
+    let dead_transitions = complete.then_some(dead_transitions);
+    let witness = data
+        .target
+        .map(|target| net.marking(&target))
+        .and_then(|target| reachability.markings.iter().position(|m| *m == target));
+    let witness = witness.and_then(|k| reachability.firing_sequence(k));
+
+    StateSpace {
+        places: net.places,
+        transitions: net.transitions,
+        reachability,
+        coverability,
+        bounds,
+        deadlocks,
+        dead_transitions,
+        witness,
+    }
+}
+
+impl ReachabilityGraph {
+    /// Finds a shortest firing sequence from the initial marking to the
+    /// marking with the given index.
+    pub fn firing_sequence(&self, target: usize) -> Option<Vec<QualifiedName>> {
+        let mut parent: Vec<Option<&FiringEdge>> = vec![None; self.markings.len()];
+        let mut visited = vec![false; self.markings.len()];
+        let mut queue = VecDeque::from([0]);
+        *visited.first_mut()? = true;
+        while let Some(k) = queue.pop_front() {
+            if k == target {
+                break;
+            }
+            for edge in self.edges.iter().filter(|edge| edge.source == k) {
+                if !visited[edge.target] {
+                    visited[edge.target] = true;
+                    parent[edge.target] = Some(edge);
+                    queue.push_back(edge.target);
+                }
+            }
+        }
+        if !*visited.get(target)? {
+            return None;
+        }
+        let path = std::iter::successors(parent[target], |edge| parent[edge.source]);
+        Some(
+            path.map(|edge| edge.transition.clone())
+                .collect_vec()
+                .into_iter()
+                .rev()
+                .collect(),
+        )
+    }
+}
+
+impl PetriNet {
+    /// Marking with the given token counts, zero for places not in the map.
+    pub(crate) fn marking(&self, tokens: &HashMap<QualifiedName, u32>) -> Vec<u32> {
+        self.places
+            .iter()
+            .map(|place| tokens.get(place).copied().unwrap_or_default())
+            .collect()
+    }
+
+    /// Fires a transition at a marking, if it is enabled.
+    fn fire(&self, t: usize, marking: &[u32]) -> Option<Vec<u32>> {
+        itertools::izip!(marking, &self.pre[t], &self.post[t])
+            .map(|(x, pre, post)| x.checked_sub(*pre).map(|x| x + post))
+            .collect()
+    }
+
+    /// Fires a transition at a generalized marking, if it is enabled.
+    fn fire_omega(&self, t: usize, marking: &[Option<u32>]) -> Option<Vec<Option<u32>>> {
+        itertools::izip!(marking, &self.pre[t], &self.post[t])
+            .map(|(x, pre, post)| match x {
+                Some(x) => Some(Some(x.checked_sub(*pre)? + post)),
+                None => Some(None),
+            })
+            .collect()
+    }
+
+    /// Explores the reachability graph breadth-first, returning it along with
+    /// the indices of the deadlocked markings.
+    pub(crate) fn reachability_graph(
+        &self,
+        initial: Vec<u32>,
+        max_states: usize,
+    ) -> (ReachabilityGraph, Vec<usize>) {
+        let mut index = HashMap::from([(initial.clone(), 0)]);
+        let mut graph = ReachabilityGraph {
+            markings: vec![initial],
+            edges: Vec::new(),
+            complete: true,
+        };
+        let mut deadlocks = Vec::new();
+        let mut k = 0;
+        while k < graph.markings.len() {
+            let mut enabled = false;
+            for (t, transition) in self.transitions.iter().enumerate() {
+                let Some(next) = self.fire(t, &graph.markings[k]) else {
+                    continue;
+                };
+                enabled = true;
+                let target = match index.get(&next) {
+                    Some(j) => *j,
+                    None if graph.markings.len() >= max_states => {
+                        graph.complete = false;
+                        continue;
+                    }
+                    None => {
+                        index.insert(next.clone(), graph.markings.len());
+                        graph.markings.push(next);
+                        graph.markings.len() - 1
+                    }
+                };
+                graph.edges.push(FiringEdge {
+                    source: k,
+                    transition: transition.clone(),
+                    target,
+                });
+            }
+            if !enabled {
+                deadlocks.push(k);
+            }
+            k += 1;
+        }
+        (graph, deadlocks)
     }
 
-    // Check whether input tokening lies within the region which can access
-    // the forbidden state, `init`.
-    let init_in_forbbiden = f.iter().any(|v| (0..n_p).all(|p| v[p] <= init[p]));
-    !init_in_forbbiden
+    /// Constructs the Karp–Miller coverability tree.
+    ///
+    /// A node is not expanded when an ancestor has the same marking. Whenever a
+    /// new marking strictly covers that of an ancestor, the places where it is
+    /// larger are accelerated to ω.
+    fn coverability_tree(&self, initial: Vec<u32>, max_nodes: usize) -> CoverabilityTree {
+        let mut tree = CoverabilityTree {
+            nodes: vec![CoverabilityNode {
+                marking: initial.into_iter().map(Some).collect(),
+                parent: None,
+                transition: None,
+            }],
+            complete: true,
+        };
+        let mut queue = VecDeque::from([0]);
+        while let Some(k) = queue.pop_front() {
+            let nodes = &tree.nodes;
+            let ancestors =
+                std::iter::successors(nodes[k].parent, |a| nodes[*a].parent).collect_vec();
+            if ancestors.iter().any(|a| nodes[*a].marking == nodes[k].marking) {
+                continue;
+            }
+            for (t, transition) in self.transitions.iter().enumerate() {
+                let Some(mut next) = self.fire_omega(t, &tree.nodes[k].marking) else {
+                    continue;
+                };
+                for a in std::iter::once(k).chain(ancestors.iter().copied()) {
+                    let marking = &tree.nodes[a].marking;
+                    if covers(&next, marking) {
+                        for (x, y) in std::iter::zip(next.iter_mut(), marking) {
+                            if x != y {
+                                *x = None;
+                            }
+                        }
+                    }
+                }
+                if tree.nodes.len() >= max_nodes {
+                    tree.complete = false;
+                    return tree;
+                }
+                tree.nodes.push(CoverabilityNode {
+                    marking: next,
+                    parent: Some(k),
+                    transition: Some(transition.clone()),
+                });
+                queue.push_back(tree.nodes.len() - 1);
+            }
+        }
+        tree
+    }
+}
+
+/// Whether a generalized marking covers another, with ω above every count.
+fn covers(x: &[Option<u32>], y: &[Option<u32>]) -> bool {
+    std::iter::zip(x, y).all(|(x, y)| match (x, y) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(x), Some(y)) => x >= y,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::dbl::{model::*, theory::*};
-    use crate::stdlib::th_sym_monoidal_category;
+    use crate::stdlib::{sir_petri, th_sym_monoidal_category};
     use crate::zero::name;
     use std::rc::Rc;
 
@@ -205,5 +611,128 @@ mod tests {
         test_input(&model, 0, 2, 0, false);
         test_input(&model, 1, 0, 1, true);
         test_input(&model, 1, 1, 0, true);
+
+        // Witnesses are shortest firing sequences covering the forbidden state.
+        let witness = |x1: i32, x2: i32, x3: i32| {
+            let data = ReachabilityProblemData {
+                tokens: HashMap::from_iter([(name("p1"), x1), (name("p2"), x2), (name("p3"), x3)]),
+                forbidden: HashMap::from_iter([(name("p3"), 2)]),
+            };
+            subreachability_witness(&model, data)
+        };
+        let sequence = witness(0, 0, 2).unwrap();
+        assert!(sequence.transitions.is_empty());
+        assert_eq!(sequence.markings.len(), 1);
+
+        let sequence = witness(0, 1, 1).unwrap();
+        assert_eq!(sequence.transitions, vec![name("t1"), name("t2")]);
+        let last = sequence.markings.last().unwrap();
+        assert_eq!(last, &HashMap::from_iter([(name("p1"), 0), (name("p2"), 0), (name("p3"), 2)]));
+
+        let sequence = witness(0, 2, 0).unwrap();
+        assert_eq!(sequence.transitions.len(), 4);
+        assert_eq!(sequence.markings.len(), 5);
+        assert!(sequence.markings.iter().all(|m| m.values().all(|x| *x >= 0)));
+        assert_eq!(sequence.markings.last().unwrap()[&name("p3")], 2);
+
+        assert_eq!(witness(1, 0, 1), None);
+    }
+
+    #[test]
+    fn sir_state_space() {
+        let model = sir_petri(Rc::new(th_sym_monoidal_category()));
+        let data = StateSpaceData {
+            tokens: HashMap::from_iter([(name("S"), 2), (name("I"), 1)]),
+            target: Some(HashMap::from_iter([(name("R"), 3)])),
+            max_states: 100,
+        };
+        let space = state_space(&model, data);
+        assert_eq!(space.places, vec![name("I"), name("R"), name("S")]);
+        assert!(space.reachability.complete && space.coverability.complete);
+
+        // The population is conserved, so the net is bounded.
+        assert!(space.reachability.markings.iter().all(|m| m.iter().sum::<u32>() == 3));
+        let bounds =
+            HashMap::from_iter([(name("I"), Some(3)), (name("R"), Some(3)), (name("S"), Some(2))]);
+        assert_eq!(space.bounds, Some(bounds));
+
+        // The epidemic ends exactly when there are no more infected.
+        let deadlocks = space.deadlocks.iter().map(|k| &space.reachability.markings[*k]);
+        let expected = vec![vec![0, 1, 2], vec![0, 2, 1], vec![0, 3, 0]];
+        assert_eq!(deadlocks.sorted().cloned().collect_vec(), expected);
+        assert_eq!(space.dead_transitions, Some(vec![]));
+
+        // Everyone is infected and then recovers.
+        let witness = space.witness.unwrap();
+        assert_eq!(witness.len(), 5);
+        assert_eq!(witness.iter().filter(|t| **t == name("infect")).count(), 2);
+
+        // Without any infected, nothing ever happens.
+        let data = StateSpaceData {
+            tokens: HashMap::from_iter([(name("S"), 2)]),
+            target: Some(HashMap::from_iter([(name("R"), 2)])),
+            max_states: 100,
+        };
+        let space = state_space(&model, data);
+        assert_eq!(space.reachability.markings, vec![vec![0, 0, 2]]);
+        assert_eq!(space.deadlocks, vec![0]);
+        assert_eq!(space.dead_transitions, Some(vec![name("infect"), name("recover")]));
+        assert_eq!(space.witness, None);
+    }
+
+    #[test]
+    fn unbounded_state_space() {
+        // A source of tokens in `p`, which move into and out of a cycle
+        // between `q` and `r`.
+        let th = Rc::new(th_sym_monoidal_category());
+        let (ob_type, op) = (ModalObType::new(name("Object")), name("tensor"));
+        let mut model = ModalDblModel::new(th);
+        let [p, q, r] = [name("p"), name("q"), name("r")];
+        for x in [&p, &q, &r] {
+            model.add_ob(x.clone(), ob_type.clone());
+        }
+        let list = |obs: Vec<QualifiedName>| {
+            let obs = obs.into_iter().map(ModalOb::from).collect();
+            ModalOb::App(ModalOb::List(List::Symmetric, obs).into(), op.clone())
+        };
+        let mor_type = ModalMorType::Zero(ob_type);
+        model.add_mor(name("t1"), list(vec![]), list(vec![p.clone()]), mor_type.clone());
+        model.add_mor(
+            name("t2"),
+            list(vec![p, q.clone()]),
+            list(vec![r.clone()]),
+            mor_type.clone(),
+        );
+        model.add_mor(name("t3"), list(vec![r]), list(vec![q]), mor_type);
+
+        let data = StateSpaceData {
+            tokens: HashMap::from_iter([(name("q"), 1)]),
+            target: Some(HashMap::from_iter([(name("p"), 3), (name("r"), 1)])),
+            max_states: 50,
+        };
+        let space = state_space(&model, data);
+        assert!(!space.reachability.complete);
+        assert!(space.coverability.complete);
+        let bounds =
+            HashMap::from_iter([(name("p"), None), (name("q"), Some(1)), (name("r"), Some(1))]);
+        assert_eq!(space.bounds, Some(bounds));
+        assert!(space.coverability.nodes.iter().any(|node| node.marking[0].is_none()));
+        assert!(space.deadlocks.is_empty());
+        assert_eq!(space.dead_transitions, Some(vec![]));
+
+        let witness = space.witness.unwrap();
+        assert_eq!(witness, [vec![name("t1"); 4], vec![name("t2")]].concat());
+
+        // With too few states, the coverability tree is truncated before `t2`
+        // and `t3` can fire, so dead transitions and bounds are unknown.
+        let data = StateSpaceData {
+            tokens: HashMap::from_iter([(name("q"), 1)]),
+            target: None,
+            max_states: 2,
+        };
+        let space = state_space(&model, data);
+        assert!(!space.coverability.complete);
+        assert_eq!(space.bounds, None);
+        assert_eq!(space.dead_transitions, None);
     }
 }
@@ -1,18 +1,26 @@
-//! Produces a valid SQL data manipulation script from a model in the theory of schemas.
+//! Produces a valid SQL data manipulation script from a model in the theory of schemas,
+//! parses such scripts back into models, and migrates between versions of a schema.
 use crate::{
-    dbl::model::*,
-    one::{Path, graph::FinGraph, graph_algorithms::toposort},
-    zero::{QualifiedLabel, QualifiedName, label, name},
+    dbl::{model::*, model_morphism::*},
+    one::{
+        Category, FgCategory, Path,
+        graph::FinGraph,
+        graph_algorithms::{simple_paths, toposort},
+    },
+    stdlib::theories::th_nullable_schema,
+    validate::Validate,
+    zero::{Mapping, QualifiedLabel, QualifiedName, label, name},
 };
 use indexmap::IndexMap;
 use itertools::Itertools;
 use sea_query::SchemaBuilder;
 use sea_query::{
-    ColumnDef, ForeignKey, ForeignKeyCreateStatement, Iden, MysqlQueryBuilder,
-    PostgresQueryBuilder, SqliteQueryBuilder, Table, TableCreateStatement, prepare::Write,
+    ColumnDef, ForeignKey, ForeignKeyCreateStatement, Iden, Index, MysqlQueryBuilder,
+    PostgresQueryBuilder, Query, SqliteQueryBuilder, Table, TableCreateStatement, prepare::Write,
 };
 use sqlformat::{Dialect, format};
 use std::fmt;
+use std::rc::Rc;
 
 impl Iden for QualifiedName {
     fn unquoted(&self, s: &mut dyn Write) {
@@ -50,28 +58,26 @@ impl SQLAnalysis {
         ob_label: impl Fn(&QualifiedName) -> QualifiedLabel,
         mor_label: impl Fn(&QualifiedName) -> QualifiedLabel,
     ) -> Result<String, String> {
-        let g = model.generating_graph();
-        let t = toposort(g).map_err(|e| format!("Topological sort failed: {}", e))?;
-        let morphisms: IndexMap<&QualifiedName, Vec<QualifiedName>> =
-            IndexMap::from_iter(t.iter().rev().filter_map(|v| {
-                (name("Entity") == model.ob_generator_type(v))
-                    .then_some((v, g.out_edges(v).collect::<Vec<QualifiedName>>()))
-            }));
-
-        let tables = self.make_tables(model, morphisms, ob_label, mor_label);
-
-        let output: String = tables
-            .iter()
-            .map(|table| match self.backend {
-                SQLBackend::MySQL => table.to_string(MysqlQueryBuilder),
-                SQLBackend::SQLite => table.to_string(SqliteQueryBuilder),
-                SQLBackend::PostgresSQL => table.to_string(PostgresQueryBuilder),
-            })
-            .join(";\n")
-            + ";";
+        let schema = LabeledSchema {
+            model,
+            ob_label: &ob_label,
+            mor_label: &mor_label,
+        };
+        let formatted_output = self.format(self.make_tables(&schema, &table_order(model)));
+
+        let result = match self.backend {
+            SQLBackend::SQLite => ["PRAGMA foreign_keys = ON", &formatted_output].join(";\n\n"),
+            _ => formatted_output,
+        };
+        Ok(result)
+    }
+
+    /// Joins and pretty-prints SQL statements.
+    fn format(&self, statements: Vec<String>) -> String {
+        let output = statements.join(";\n") + ";";
 
         // TODO SQL analysis should interface with this
-        let formatted_output = format(
+        format(
             &output,
             &sqlformat::QueryParams::None,
             &sqlformat::FormatOptions {
@@ -79,13 +85,7 @@ impl SQLAnalysis {
                 dialect: self.backend.clone().into(),
                 ..Default::default()
             },
-        );
-
-        let result = match self.backend {
-            SQLBackend::SQLite => ["PRAGMA foreign_keys = ON", &formatted_output].join(";\n\n"),
-            _ => formatted_output,
-        };
-        Ok(result)
+        )
     }
 
     fn fk(
@@ -101,55 +101,163 @@ impl SQLAnalysis {
             .to_owned()
     }
 
-    fn make_tables(
+    /// Creates the tables for the given entities, in order.
+    ///
+    /// A foreign key referring to a table that is created later is added by a
+    /// separate statement after all the tables are created, except in SQLite,
+    /// which allows such references.
+    fn make_tables(&self, schema: &LabeledSchema, obs: &[QualifiedName]) -> Vec<String> {
+        let builder = self.backend.as_type();
+        let g = schema.model.generating_graph();
+        let (mut statements, mut deferred) = (Vec::new(), Vec::new());
+        for (i, ob) in obs.iter().enumerate() {
+            let mors = g.out_edges(ob).collect_vec();
+            let defer = |mor: &QualifiedName| {
+                !matches!(self.backend, SQLBackend::SQLite)
+                    && obs[i + 1..].contains(&schema.model.mor_generator_cod(mor))
+            };
+            deferred.extend(
+                mors.iter()
+                    .filter(|mor| is_fk(schema.model, mor) && defer(mor))
+                    .map(|mor| self.schema_fk(schema, mor)),
+            );
+            let nullable = |mor: &QualifiedName| is_nullable(schema.model, mor);
+            let mut table = self.make_table(schema, ob, &mors, nullable, defer);
+            statements.push(table.if_not_exists().build_any(&*builder));
+        }
+        statements.extend(deferred.iter().map(|fk| fk.build_any(&*builder)));
+        statements
+    }
+
+    /// Creates the table for an entity with the given mappings and attributes.
+    ///
+    /// The table of a [junction](is_junction) entity is keyed by the pair of
+    /// its foreign keys. Every other table has an integer `id` as primary key.
+    /// Columns of [split monic](is_split_monic) mappings are unique. Foreign
+    /// key constraints are omitted for deferred mappings.
+    fn make_table(
         &self,
-        model: &DiscreteDblModel,
-        morphisms: IndexMap<&QualifiedName, Vec<QualifiedName>>,
-        ob_label: impl Fn(&QualifiedName) -> QualifiedLabel,
-        mor_label: impl Fn(&QualifiedName) -> QualifiedLabel,
-    ) -> Vec<TableCreateStatement> {
-        morphisms
-            .into_iter()
-            .map(|(ob, mors)| {
-                let mut tbl = Table::create();
-
-                // the targets for arrows
-                let table_column_defs = mors.iter().fold(
-                    tbl.table(ob_label(ob)).if_not_exists().col(
-                        ColumnDef::new("id").integer().not_null().auto_increment().primary_key(),
-                    ),
-                    |acc, mor| {
-                        let mor_name = mor_label(mor);
-                        // if the Id of the name is an entity, it is assumed to be a column
-                        // which references the primary key of another table.
-                        if model.mor_generator_type(mor) == Path::Id(name("Entity")) {
-                            acc.col(ColumnDef::new(mor_name.clone()).integer().not_null())
-                        } else {
-                            let tgt =
-                                model.get_cod(mor).map(&ob_label).unwrap_or_else(|| label(""));
-                            let mut col = ColumnDef::new(mor_name);
-                            col.not_null();
-                            add_column_type(&mut col, &tgt);
-                            acc.col(col)
-                        }
-                    },
-                );
+        schema: &LabeledSchema,
+        ob: &QualifiedName,
+        mors: &[QualifiedName],
+        nullable: impl Fn(&QualifiedName) -> bool,
+        defer: impl Fn(&QualifiedName) -> bool,
+    ) -> TableCreateStatement {
+        let model = schema.model;
+        let mut tbl = Table::create();
+        tbl.table((schema.ob_label)(ob));
 
-                mors.iter()
-                    .filter(|mor| model.mor_generator_type(mor) == Path::Id(name("Entity")))
-                    .fold(
-                        // TABLE AND COLUMN DEFS
-                        table_column_defs,
-                        |acc, mor| {
-                            let tgt =
-                                model.get_cod(mor).map(&ob_label).unwrap_or_else(|| label(""));
-                            acc.foreign_key(&mut self.fk(ob_label(ob), tgt, mor_label(mor)))
-                        },
-                    )
-                    .to_owned()
-            })
-            .collect()
+        let junction = is_junction(model, ob);
+        if !junction {
+            tbl.col(ColumnDef::new("id").integer().not_null().auto_increment().primary_key());
+        }
+        for mor in mors {
+            let mut col = column_def(model, mor, schema.ob_label, schema.mor_label, nullable(mor));
+            if is_split_monic(model, mor) {
+                col.unique_key();
+            }
+            tbl.col(&mut col);
+        }
+        if junction {
+            let mut key = Index::create();
+            for mor in mors {
+                key.col((schema.mor_label)(mor));
+            }
+            tbl.primary_key(&mut key);
+        }
+
+        // the targets for arrows
+        for mor in mors.iter().filter(|mor| is_fk(model, mor) && !defer(mor)) {
+            tbl.foreign_key(&mut self.schema_fk(schema, mor));
+        }
+        tbl
     }
+
+    /// Foreign key constraint for a mapping of a schema.
+    fn schema_fk(&self, schema: &LabeledSchema, mor: &QualifiedName) -> ForeignKeyCreateStatement {
+        let (dom, cod) = (schema.model.mor_generator_dom(mor), schema.model.mor_generator_cod(mor));
+        self.fk((schema.ob_label)(&dom), (schema.ob_label)(&cod), (schema.mor_label)(mor))
+    }
+}
+
+/// Entities of a schema in the order that their tables are created.
+///
+/// When the foreign keys have no cycles, each table comes after the tables
+/// that it refers to.
+fn table_order(model: &DiscreteDblModel) -> Vec<QualifiedName> {
+    let obs = match toposort(model.generating_graph()) {
+        Ok(t) => t.into_iter().rev().collect_vec(),
+        Err(_) => model.ob_generators().collect_vec(),
+    };
+    obs.into_iter()
+        .filter(|x| model.ob_generator_type(x) == name("Entity"))
+        .collect()
+}
+
+/// Column definition for a mapping or attribute of a schema.
+fn column_def(
+    model: &DiscreteDblModel,
+    mor: &QualifiedName,
+    ob_label: impl Fn(&QualifiedName) -> QualifiedLabel,
+    mor_label: impl Fn(&QualifiedName) -> QualifiedLabel,
+    nullable: bool,
+) -> ColumnDef {
+    let mut col = ColumnDef::new(mor_label(mor));
+    // if the target of the morphism is an entity, it is assumed to be a column
+    // which references the primary key of another table.
+    if is_fk(model, mor) {
+        col.integer();
+    } else {
+        let tgt = model.get_cod(mor).map(&ob_label).unwrap_or_else(|| label(""));
+        add_column_type(&mut col, &tgt);
+    }
+    if !nullable {
+        col.not_null();
+    }
+    col
+}
+
+/// Is the morphism a mapping between entities, hence a foreign key?
+fn is_fk(model: &DiscreteDblModel, mor: &QualifiedName) -> bool {
+    model.get_cod(mor).is_some_and(|x| model.ob_generator_type(x) == name("Entity"))
+}
+
+/// Is the mapping or attribute nullable?
+///
+/// Only models of the [theory of nullable schemas](crate::stdlib::th_nullable_schema) have
+/// nullable morphisms.
+fn is_nullable(model: &DiscreteDblModel, mor: &QualifiedName) -> bool {
+    let mor_type = model.mor_generator_type(mor);
+    mor_type == name("Nullable").into() || mor_type == name("NullableAttr").into()
+}
+
+/// Is the mapping split monic, i.e., does it have a retraction in the schema?
+///
+/// A mapping with a retraction is injective in every instance of the schema,
+/// so its column can be declared unique. Retractions are sought among the
+/// simple paths back to the domain, using the path equations of the schema.
+fn is_split_monic(model: &DiscreteDblModel, mor: &QualifiedName) -> bool {
+    if model.is_free() || !is_fk(model, mor) {
+        return false;
+    }
+    let (dom, cod) = (model.mor_generator_dom(mor), model.mor_generator_cod(mor));
+    simple_paths(model.generating_graph(), &cod, &dom).any(|path| {
+        let composite = model.compose2(Path::single(mor.clone()), path);
+        model.category.morphisms_are_equal(composite, Path::Id(dom.clone()))
+    })
+}
+
+/// Is the entity a junction, a span of two mappings and nothing else?
+///
+/// The table of a junction entity represents a many-to-many relationship
+/// between the tables of its two mappings. Such a table needs no `id` column
+/// when nothing refers to it, as its rows are determined by their foreign keys.
+fn is_junction(model: &DiscreteDblModel, ob: &QualifiedName) -> bool {
+    let g = model.generating_graph();
+    let mors = g.out_edges(ob).collect_vec();
+    mors.len() == 2
+        && mors.iter().all(|mor| is_fk(model, mor) && !is_nullable(model, mor))
+        && g.in_edges(ob).next().is_none()
 }
 
 /// Variants of SQL backends. Each correspond to types which implement the
@@ -210,17 +318,828 @@ impl fmt::Display for SQLBackend {
     }
 }
 
+/// Sets the column type named by an attribute type.
+///
+/// Common type names are recognized case-insensitively, including those with
+/// a length or precision such as `Varchar(255)` or `Decimal(10, 2)`. Any other
+/// name is passed through as a custom type.
 fn add_column_type(col: &mut ColumnDef, name: &QualifiedLabel) {
-    match format!("{}", name).as_str() {
-        "Int" => col.integer(),
-        "TinyInt" => col.tiny_integer(),
-        "Bool" => col.boolean(),
-        "Float" => col.float(),
-        "Time" => col.timestamp(),
-        "Date" => col.date(),
-        "DateTime" => col.date_time(),
-        _ => col.custom(name.clone()),
+    let name = format!("{}", name);
+    let (base, args) = match name.split_once('(') {
+        Some((base, args)) => {
+            let args = args.strip_suffix(')').map(|args| {
+                args.split(',')
+                    .map(|arg| arg.trim().parse::<u32>().ok())
+                    .collect::<Option<Vec<_>>>()
+            });
+            (base.trim(), args.flatten())
+        }
+        None => (name.as_str(), Some(vec![])),
+    };
+    let Some(args) = args else {
+        col.custom(label(name.as_str()));
+        return;
     };
+    match (base.to_lowercase().as_str(), args.as_slice()) {
+        ("int" | "integer", []) => col.integer(),
+        ("tinyint", []) => col.tiny_integer(),
+        ("smallint", []) => col.small_integer(),
+        ("bigint", []) => col.big_integer(),
+        ("bool" | "boolean", []) => col.boolean(),
+        ("float" | "real", []) => col.float(),
+        ("double" | "double precision", []) => col.double(),
+        ("decimal" | "numeric", []) => col.decimal(),
+        ("decimal" | "numeric", [precision, scale]) => col.decimal_len(*precision, *scale),
+        ("string" | "varchar", []) => col.string(),
+        ("string" | "varchar", [len]) => col.string_len(*len),
+        ("char", []) => col.char(),
+        ("char", [len]) => col.char_len(*len),
+        ("text", []) => col.text(),
+        ("time" | "timestamp", []) => col.timestamp(),
+        ("timestamptz", []) => col.timestamp_with_time_zone(),
+        ("date", []) => col.date(),
+        ("datetime", []) => col.date_time(),
+        ("uuid", []) => col.uuid(),
+        ("json", []) => col.json(),
+        ("jsonb", []) => col.json_binary(),
+        ("blob" | "bytes", []) => col.blob(),
+        _ => col.custom(label(name.as_str())),
+    };
+}
+
+impl SQLAnalysis {
+    /// Parses SQL DDL into a model in the theory of nullable schemas.
+    ///
+    /// Each `CREATE TABLE` statement becomes an entity, each foreign key
+    /// becomes a mapping into the referenced entity, and every other column
+    /// becomes an attribute into an attribute type named by the column's SQL
+    /// type. A primary key consisting of a single column is taken to be the
+    /// identity of the rows and so does not become an attribute. Mappings and
+    /// attributes are named by the table and column names, and are nullable
+    /// unless their column is `NOT NULL` or part of the primary key. Besides
+    /// `CREATE TABLE`, only `ALTER TABLE` statements adding foreign keys are
+    /// read; other statements are ignored.
+    ///
+    /// A foreign key must refer to the single-column primary key of its table,
+    /// the identity of the rows, and unquoted column names that also begin
+    /// constraints, like `key` and `index`, are rejected as ambiguous.
+    ///
+    /// `UNIQUE` constraints are not read back: a unique column rendered from a
+    /// split monic mapping owes its uniqueness to a path equation, which the
+    /// DDL does not record.
+    ///
+    /// Identifiers can be quoted in any of the styles accepted by the
+    /// supported backends. For the Postgres backend, unquoted identifiers are
+    /// folded to lower case, as Postgres does.
+    pub fn parse(&self, ddl: &str) -> Result<DiscreteDblModel, String> {
+        let tokens = tokenize(ddl)?;
+        let mut parser = DDLParser {
+            tokens,
+            pos: 0,
+            fold_case: matches!(self.backend, SQLBackend::PostgresSQL),
+        };
+        let tables = parser.statements()?;
+
+        let mut model = DiscreteDblModel::new(Rc::new(th_nullable_schema()));
+        for table in &tables {
+            let ob = name(table.name.as_str());
+            if model.has_ob(&ob) {
+                return Err(format!("Table `{}` is defined more than once", table.name));
+            }
+            model.add_ob(ob, name("Entity"));
+        }
+        for table in &tables {
+            let ob = name(table.name.as_str());
+            for ForeignKeySpec { columns: cols, table: target, column } in &table.foreign_keys {
+                if cols.len() != 1 {
+                    return Err(format!(
+                        "Composite foreign key ({}) in table `{}` is not supported",
+                        cols.join(", "),
+                        table.name
+                    ));
+                }
+                if !table.columns.iter().any(|col| col.name == cols[0]) {
+                    return Err(format!(
+                        "Foreign key refers to nonexistent column `{}` in table `{}`",
+                        cols[0], table.name
+                    ));
+                }
+                let Some(target_table) = tables.iter().find(|other| &other.name == target) else {
+                    return Err(format!(
+                        "Foreign key `{}` in table `{}` refers to undefined table `{target}`",
+                        cols[0], table.name
+                    ));
+                };
+                if let Some(column) = column
+                    && target_table.primary_key != [column.as_str()]
+                {
+                    return Err(format!(
+                        "Foreign key `{}` in table `{}` refers to column `{column}` of table \
+                         `{target}`, which is not its primary key",
+                        cols[0], table.name
+                    ));
+                }
+            }
+            for (i, col) in table.columns.iter().enumerate() {
+                if table.columns[..i].iter().any(|other| other.name == col.name) {
+                    return Err(format!(
+                        "Column `{}` is defined more than once in table `{}`",
+                        col.name, table.name
+                    ));
+                }
+                let mor: QualifiedName = [table.name.as_str(), col.name.as_str()].into();
+                let target = table
+                    .foreign_keys
+                    .iter()
+                    .find_map(|fk| (fk.columns[0] == col.name).then_some(&fk.table));
+                let nullable = col.nullable && !table.primary_key.contains(&col.name);
+                if let Some(target) = target {
+                    let mor_type = if nullable {
+                        name("Nullable").into()
+                    } else {
+                        Path::Id(name("Entity"))
+                    };
+                    model.add_mor(mor, ob.clone(), name(target.as_str()), mor_type);
+                } else if table.primary_key != [col.name.as_str()] {
+                    let Some(ty) = &col.ty else {
+                        return Err(format!(
+                            "Column `{}` in table `{}` has no type",
+                            col.name, table.name
+                        ));
+                    };
+                    let attr_type = name(ty.as_str());
+                    if !model.has_ob(&attr_type) {
+                        model.add_ob(attr_type.clone(), name("AttrType"));
+                    } else if model.ob_generator_type(&attr_type) != name("AttrType") {
+                        return Err(format!("Type `{ty}` has the same name as a table"));
+                    }
+                    let mor_type = if nullable { "NullableAttr" } else { "Attr" };
+                    model.add_mor(mor, ob.clone(), attr_type, name(mor_type).into());
+                }
+            }
+        }
+        Ok(model)
+    }
+}
+
+/// A schema together with the labels that name its tables, types, and columns.
+pub struct LabeledSchema<'a> {
+    /// The schema.
+    pub model: &'a DiscreteDblModel,
+
+    /// Labels of entities and attribute types.
+    pub ob_label: &'a dyn Fn(&QualifiedName) -> QualifiedLabel,
+
+    /// Labels of mappings and attributes.
+    pub mor_label: &'a dyn Fn(&QualifiedName) -> QualifiedLabel,
+}
+
+impl LabeledSchema<'_> {
+    /// Name of the foreign key constraint for a mapping, as in [`SQLAnalysis::fk`].
+    fn fk_name(&self, mor: &QualifiedName) -> String {
+        let (dom, cod) = (self.model.mor_generator_dom(mor), self.model.mor_generator_cod(mor));
+        format!(
+            "FK_{}_{}_{}",
+            (self.mor_label)(mor),
+            (self.ob_label)(&dom),
+            (self.ob_label)(&cod)
+        )
+    }
+
+    /// Describes a column for use in error messages.
+    fn describe_column(&self, mor: &QualifiedName) -> String {
+        let dom = self.model.mor_generator_dom(mor);
+        format!("`{}` of table `{}`", (self.mor_label)(mor), (self.ob_label)(&dom))
+    }
+}
+
+impl SQLAnalysis {
+    /// Produces a SQL script migrating a database from one schema to another.
+    ///
+    /// The mapping must be a morphism from the old schema to the new one. It
+    /// says which tables and columns of the new schema come from which tables
+    /// and columns of the old schema. Tables and columns whose labels change
+    /// are renamed, attributes whose types change are converted, and foreign
+    /// key constraints whose names change, say because the referenced table is
+    /// renamed, are replaced. Tables and columns of the new schema outside the
+    /// image of the mapping are added. Since existing rows have no values for
+    /// them, added columns are nullable. Columns whose nullability changes are
+    /// altered accordingly, but unique constraints are only declared for new
+    /// tables.
+    ///
+    /// SQLite can neither change the type of a column nor add a foreign key to
+    /// an existing table, so with that backend such a table is instead rebuilt
+    /// and its data copied over.
+    ///
+    /// Changes that would lose data are reported as errors. These are merging
+    /// tables or columns, by mapping two of them to the same place, and
+    /// dropping columns, by mapping them to identities. Mapping a column to a
+    /// composite path is also not supported, nor is changing the primary key
+    /// of a table by turning it into a junction table or back.
+    pub fn migrate(
+        &self,
+        old: &LabeledSchema,
+        new: &LabeledSchema,
+        mapping: &DiscreteDblModelMapping,
+    ) -> Result<String, String> {
+        if let Err(errs) = DblModelMorphism(mapping, old.model, new.model).validate() {
+            return Err(format!(
+                "Mapping is not a morphism of schemas: {}",
+                errs.iter().join("; ")
+            ));
+        }
+        let entity = name("Entity");
+        let is_column_of = |model: &DiscreteDblModel, mor: &QualifiedName| {
+            model.ob_generator_type(&model.mor_generator_dom(mor)) == entity
+        };
+
+        // Find the table and column of the old schema that each table and
+        // column of the new schema comes from, if any.
+        let mut table_sources: IndexMap<QualifiedName, QualifiedName> = IndexMap::new();
+        for x in old.model.ob_generators_with_type(&entity).sorted() {
+            let y = mapping.0.ob_generator_map.apply_to_ref(&x).expect("Mapping should be valid");
+            if let Some(other) = table_sources.insert(y.clone(), x.clone()) {
+                return Err(format!(
+                    "Tables `{}` and `{}` are both mapped to `{}`, but merging tables is not supported",
+                    (old.ob_label)(&other),
+                    (old.ob_label)(&x),
+                    (new.ob_label)(&y)
+                ));
+            }
+        }
+        let mut column_sources: IndexMap<QualifiedName, QualifiedName> = IndexMap::new();
+        for f in old.model.mor_generators().sorted().filter(|f| is_column_of(old.model, f)) {
+            let path =
+                mapping.0.mor_generator_map.apply_to_ref(&f).expect("Mapping should be valid");
+            let g = match path {
+                Path::Id(_) => {
+                    return Err(format!(
+                        "Column {} is mapped to an identity, but dropping columns is not supported",
+                        old.describe_column(&f)
+                    ));
+                }
+                path => path.only().ok_or_else(|| {
+                    format!(
+                        "Column {} is mapped to a composite, which is not supported",
+                        old.describe_column(&f)
+                    )
+                })?,
+            };
+            if let Some(other) = column_sources.insert(g.clone(), f.clone()) {
+                return Err(format!(
+                    "Columns {} and {} are both mapped to {}, but merging columns is not supported",
+                    old.describe_column(&other),
+                    old.describe_column(&f),
+                    new.describe_column(&g)
+                ));
+            }
+        }
+
+        let builder = self.backend.as_type();
+        let mut statements = Vec::new();
+
+        // Rename tables and columns.
+        for (y, x) in &table_sources {
+            let (from, to) = ((old.ob_label)(x), (new.ob_label)(y));
+            if from != to {
+                statements.push(Table::rename().table(from, to).build_any(&*builder));
+            }
+        }
+        for (g, f) in &column_sources {
+            let (from, to) = ((old.mor_label)(f), (new.mor_label)(g));
+            if from != to {
+                let table = (new.ob_label)(&new.model.mor_generator_dom(g));
+                let mut alter = Table::alter();
+                alter.table(table).rename_column(from, to);
+                statements.push(alter.build_any(&*builder));
+            }
+        }
+
+        // Create tables that are new.
+        let new_tables =
+            table_order(new.model).into_iter().filter(|y| !table_sources.contains_key(y));
+        statements.extend(self.make_tables(new, &new_tables.collect_vec()));
+
+        // Alter tables that already exist.
+        let g = new.model.generating_graph();
+        let mut rebuilt = false;
+        for (y, x) in &table_sources {
+            let table = (new.ob_label)(y);
+            if is_junction(old.model, x) != is_junction(new.model, y) {
+                return Err(format!(
+                    "Changing the primary key of table `{table}` is not supported"
+                ));
+            }
+            let is_added = |g: &QualifiedName| !column_sources.contains_key(g);
+            let nullable = |g: &QualifiedName| is_nullable(new.model, g) || is_added(g);
+            let new_column = |g: &QualifiedName| {
+                column_def(new.model, g, new.ob_label, new.mor_label, nullable(g))
+            };
+            let mors = g.out_edges(y).sorted().collect_vec();
+            let (mut retyped, mut added) = (Vec::new(), Vec::new());
+            let mut retargeted = Vec::new();
+            for g in &mors {
+                let Some(f) = column_sources.get(g) else {
+                    added.push(g);
+                    continue;
+                };
+                let old_type = (old.ob_label)(&old.model.mor_generator_cod(f));
+                if is_nullable(old.model, f) != is_nullable(new.model, g)
+                    || !is_fk(new.model, g)
+                        && old_type != (new.ob_label)(&new.model.mor_generator_cod(g))
+                {
+                    retyped.push(g);
+                }
+                if is_fk(new.model, g) && old.fk_name(f) != new.fk_name(g) {
+                    retargeted.push((f, g));
+                }
+            }
+
+            if let SQLBackend::SQLite = self.backend {
+                if retyped.is_empty() && added.iter().all(|g| !is_fk(new.model, g)) {
+                    for g in added {
+                        let mut alter = Table::alter();
+                        alter.table(table.clone()).add_column(new_column(g));
+                        statements.push(alter.build_any(&*builder));
+                    }
+                    continue;
+                }
+
+                // Rebuild the table under a temporary name and copy the data.
+                rebuilt = true;
+                let tmp = label(format!("new_{table}").as_str());
+                let mut create = self.make_table(new, y, &mors, nullable, |_| false);
+                create.table(tmp.clone());
+                statements.push(create.build_any(&*builder));
+                let id = (!is_junction(new.model, y)).then(|| label("id"));
+                let copied = id
+                    .into_iter()
+                    .chain(mors.iter().filter(|g| !is_added(g)).map(|g| (new.mor_label)(g)))
+                    .collect_vec();
+                let select = Query::select().columns(copied.clone()).from(table.clone()).to_owned();
+                let insert = Query::insert()
+                    .into_table(tmp.clone())
+                    .columns(copied)
+                    .select_from(select)
+                    .map_err(|e| e.to_string())?
+                    .to_string(SqliteQueryBuilder);
+                statements.push(insert);
+                statements.push(Table::drop().table(table.clone()).build_any(&*builder));
+                statements.push(Table::rename().table(tmp, table.clone()).build_any(&*builder));
+                continue;
+            }
+
+            for g in retyped {
+                let mut col = new_column(g);
+                if nullable(g) {
+                    col.null();
+                }
+                let mut alter = Table::alter();
+                alter.table(table.clone()).modify_column(col);
+                statements.push(alter.build_any(&*builder));
+            }
+            for (f, g) in retargeted {
+                let drop = ForeignKey::drop().name(old.fk_name(f)).table(table.clone()).to_owned();
+                statements.push(drop.build_any(&*builder));
+                statements.push(self.schema_fk(new, g).build_any(&*builder));
+            }
+            for g in added {
+                let mut alter = Table::alter();
+                alter.table(table.clone()).add_column(new_column(g));
+                statements.push(alter.build_any(&*builder));
+                if is_fk(new.model, g) {
+                    statements.push(self.schema_fk(new, g).build_any(&*builder));
+                }
+            }
+        }
+
+        if statements.is_empty() {
+            return Ok(String::new());
+        }
+        let formatted_output = self.format(statements);
+        let result = if rebuilt {
+            format!("PRAGMA foreign_keys = OFF;\n\n{formatted_output}\n\nPRAGMA foreign_keys = ON;")
+        } else {
+            formatted_output
+        };
+        Ok(result)
+    }
+}
+
+/// Token of SQL DDL.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// A keyword or identifier, possibly quoted.
+    Word { text: String, quoted: bool },
+    /// A string or numeric literal.
+    Literal(String),
+    /// A punctuation character.
+    Punct(char),
+}
+
+fn tokenize(ddl: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = ddl.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            _ if c.is_whitespace() => {}
+            '-' if chars.peek() == Some(&'-') => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                chars.by_ref().find(|c| std::mem::replace(&mut prev, *c) == '*' && *c == '/');
+            }
+            '"' | '`' | '[' | '\'' => {
+                let close = if c == '[' { ']' } else { c };
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(d) if d == close && chars.peek() == Some(&close) && c != '[' => {
+                            chars.next();
+                            text.push(d);
+                        }
+                        Some(d) if d == close => break,
+                        Some(d) => text.push(d),
+                        None => return Err(format!("Unterminated quotation `{c}{text}`")),
+                    }
+                }
+                tokens.push(if c == '\'' {
+                    Token::Literal(text)
+                } else {
+                    Token::Word { text, quoted: true }
+                });
+            }
+            _ if c.is_alphanumeric() || c == '_' || c == '$' => {
+                let mut text = String::from(c);
+                while let Some(d) = chars.next_if(|d| d.is_alphanumeric() || *d == '_' || *d == '$')
+                {
+                    text.push(d);
+                }
+                tokens.push(if c.is_ascii_digit() {
+                    Token::Literal(text)
+                } else {
+                    Token::Word { text, quoted: false }
+                });
+            }
+            _ => tokens.push(Token::Punct(c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Table parsed from a `CREATE TABLE` statement.
+struct TableDef {
+    name: String,
+    columns: Vec<ColumnSpec>,
+    primary_key: Vec<String>,
+    foreign_keys: Vec<ForeignKeySpec>,
+}
+
+/// Foreign key parsed from a `CREATE TABLE` or `ALTER TABLE` statement.
+struct ForeignKeySpec {
+    columns: Vec<String>,
+    table: String,
+    column: Option<String>,
+}
+
+/// Column parsed from a `CREATE TABLE` statement.
+struct ColumnSpec {
+    name: String,
+    ty: Option<String>,
+    nullable: bool,
+}
+
+/// Keywords that begin a table constraint that is ignored.
+const TABLE_CONSTRAINTS: [&str; 2] = ["CHECK", "UNIQUE"];
+
+/// Keywords that begin an index or exclusion constraint that is ignored.
+///
+/// Unlike those in [`TABLE_CONSTRAINTS`], these keywords are not reserved in
+/// every dialect and so can also be the names of columns.
+const INDEX_CONSTRAINTS: [&str; 4] = ["EXCLUDE", "FULLTEXT", "INDEX", "KEY"];
+
+/// Keywords that end the type of a column definition.
+const COLUMN_CONSTRAINTS: [&str; 16] = [
+    "AUTOINCREMENT",
+    "AUTO_INCREMENT",
+    "CHECK",
+    "COLLATE",
+    "COMMENT",
+    "CONSTRAINT",
+    "DEFAULT",
+    "GENERATED",
+    "IDENTITY",
+    "KEY",
+    "NOT",
+    "NULL",
+    "PRIMARY",
+    "REFERENCES",
+    "UNIQUE",
+    "UNSIGNED",
+];
+
+/// Recursive descent parser for the `CREATE TABLE` statements of SQL DDL.
+struct DDLParser {
+    tokens: Vec<Token>,
+    pos: usize,
+    fold_case: bool,
+}
+
+impl DDLParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word { text, quoted: false })
+                 if text.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let is_keyword = self.is_keyword(keyword);
+        if is_keyword {
+            self.pos += 1;
+        }
+        is_keyword
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(format!("Expected `{keyword}` but found {}", self.describe()))
+        }
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let is_punct = self.is_punct(c);
+        if is_punct {
+            self.pos += 1;
+        }
+        is_punct
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<(), String> {
+        if self.eat_punct(c) {
+            Ok(())
+        } else {
+            Err(format!("Expected `{c}` but found {}", self.describe()))
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.peek() {
+            Some(Token::Word { text, .. } | Token::Literal(text)) => format!("`{text}`"),
+            Some(Token::Punct(c)) => format!("`{c}`"),
+            None => "end of input".into(),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Word { text, quoted }) => {
+                let ident = if self.fold_case && !quoted {
+                    text.to_lowercase()
+                } else {
+                    text.clone()
+                };
+                self.pos += 1;
+                Ok(ident)
+            }
+            _ => Err(format!("Expected identifier but found {}", self.describe())),
+        }
+    }
+
+    /// Parses a possibly schema-qualified name, discarding the schema.
+    fn qualified_ident(&mut self) -> Result<String, String> {
+        let mut ident = self.ident()?;
+        while self.eat_punct('.') {
+            ident = self.ident()?;
+        }
+        Ok(ident)
+    }
+
+    /// Parses a parenthesized list of column names.
+    fn ident_list(&mut self) -> Result<Vec<String>, String> {
+        self.expect_punct('(')?;
+        let mut idents = vec![self.ident()?];
+        while self.eat_punct(',') {
+            idents.push(self.ident()?);
+        }
+        self.expect_punct(')')?;
+        Ok(idents)
+    }
+
+    /// Skips a token, or a whole parenthesized group, returning its text.
+    fn skip(&mut self) -> Result<String, String> {
+        let Some(token) = self.peek().cloned() else {
+            return Err("Unexpected end of input".into());
+        };
+        self.pos += 1;
+        Ok(match token {
+            Token::Word { text, .. } | Token::Literal(text) => text,
+            Token::Punct('(') => {
+                let mut parts = Vec::new();
+                while !self.eat_punct(')') {
+                    if !self.eat_punct(',') {
+                        parts.push(self.skip()?);
+                    }
+                }
+                format!("({})", parts.join(","))
+            }
+            Token::Punct(c) => c.to_string(),
+        })
+    }
+
+    /// Skips to the end of the current element of a table definition.
+    fn skip_element(&mut self) -> Result<(), String> {
+        while !self.is_punct(',') && !self.is_punct(')') {
+            self.skip()?;
+        }
+        Ok(())
+    }
+
+    fn statements(&mut self) -> Result<Vec<TableDef>, String> {
+        let mut tables = Vec::new();
+        while self.peek().is_some() {
+            if self.eat_keyword("CREATE") {
+                let _ = self.eat_keyword("TEMP")
+                    || self.eat_keyword("TEMPORARY")
+                    || self.eat_keyword("UNLOGGED");
+                if self.eat_keyword("TABLE") {
+                    tables.push(self.create_table()?);
+                }
+            } else if self.eat_keyword("ALTER") && self.eat_keyword("TABLE") {
+                self.alter_table(&mut tables)?;
+            }
+            while self.peek().is_some() && !self.eat_punct(';') {
+                self.skip()?;
+            }
+        }
+        Ok(tables)
+    }
+
+    fn create_table(&mut self) -> Result<TableDef, String> {
+        if self.eat_keyword("IF") {
+            self.expect_keyword("NOT")?;
+            self.expect_keyword("EXISTS")?;
+        }
+        let mut table = TableDef {
+            name: self.qualified_ident()?,
+            columns: Vec::new(),
+            primary_key: Vec::new(),
+            foreign_keys: Vec::new(),
+        };
+        if !self.is_punct('(') {
+            return Err(format!(
+                "Table `{}` must be defined by a list of columns, not {}",
+                table.name,
+                self.describe()
+            ));
+        }
+        self.expect_punct('(')?;
+        loop {
+            self.table_element(&mut table)?;
+            if !self.eat_punct(',') {
+                self.expect_punct(')')?;
+                return Ok(table);
+            }
+        }
+    }
+
+    /// Parses an `ALTER TABLE` statement, keeping only added foreign keys.
+    fn alter_table(&mut self, tables: &mut [TableDef]) -> Result<(), String> {
+        let name = self.qualified_ident()?;
+        if !self.eat_keyword("ADD") {
+            return Ok(());
+        }
+        if self.eat_keyword("CONSTRAINT") {
+            self.ident()?;
+        }
+        if !self.eat_keyword("FOREIGN") {
+            return Ok(());
+        }
+        self.expect_keyword("KEY")?;
+        let cols = self.ident_list()?;
+        let fk = self.references(cols)?;
+        let Some(table) = tables.iter_mut().find(|table| table.name == name) else {
+            return Err(format!("Foreign key added to undefined table `{name}`"));
+        };
+        table.foreign_keys.push(fk);
+        Ok(())
+    }
+
+    fn table_element(&mut self, table: &mut TableDef) -> Result<(), String> {
+        if self.eat_keyword("CONSTRAINT") {
+            self.ident()?;
+        }
+        if self.eat_keyword("PRIMARY") {
+            self.expect_keyword("KEY")?;
+            table.primary_key = self.ident_list()?;
+        } else if self.eat_keyword("FOREIGN") {
+            self.expect_keyword("KEY")?;
+            let cols = self.ident_list()?;
+            table.foreign_keys.push(self.references(cols)?);
+        } else if INDEX_CONSTRAINTS.iter().any(|keyword| self.is_keyword(keyword)) {
+            if !self.is_index_constraint() {
+                return Err(format!(
+                    "Column {} in table `{}` must be quoted to distinguish it from a constraint",
+                    self.describe(),
+                    table.name
+                ));
+            }
+        } else if !TABLE_CONSTRAINTS.iter().any(|keyword| self.is_keyword(keyword)) {
+            let col = self.ident()?;
+            let mut ty = Vec::new();
+            while !self.is_punct(',')
+                && !self.is_punct(')')
+                && !COLUMN_CONSTRAINTS.iter().any(|keyword| self.is_keyword(keyword))
+            {
+                ty.push(self.skip()?);
+            }
+            let mut nullable = true;
+            loop {
+                if self.eat_keyword("PRIMARY") {
+                    self.expect_keyword("KEY")?;
+                    table.primary_key = vec![col.clone()];
+                } else if self.eat_keyword("NOT") {
+                    nullable &= !self.eat_keyword("NULL");
+                } else if self.is_keyword("REFERENCES") {
+                    let fk = self.references(vec![col.clone()])?;
+                    table.foreign_keys.push(fk);
+                } else if self.is_punct(',') || self.is_punct(')') {
+                    break;
+                } else {
+                    self.skip()?;
+                }
+            }
+            let ty = (!ty.is_empty()).then(|| normalize_type(&ty.iter().join(" ")));
+            table.columns.push(ColumnSpec { name: col, ty, nullable });
+        }
+        self.skip_element()
+    }
+
+    /// Does an index or exclusion constraint begin at the current keyword?
+    ///
+    /// Such a constraint is the keyword followed by an optional `INDEX` or
+    /// `KEY`, an optional name, an optional `USING` method, and then a
+    /// parenthesized list starting with a column. A column definition like
+    /// `key varchar(10)` does not have this form.
+    fn is_index_constraint(&self) -> bool {
+        let mut rest = self.tokens[self.pos + 1..].iter().peekable();
+        let is_word = |token: &Token, keyword: &str| matches!(token, Token::Word { text, quoted: false } if text.eq_ignore_ascii_case(keyword));
+        rest.next_if(|token| is_word(token, "INDEX") || is_word(token, "KEY"));
+        let mut words = Vec::new();
+        while let Some(token) = rest.next_if(|token| matches!(token, Token::Word { .. })) {
+            words.push(token);
+        }
+        let words_ok = match words.as_slice() {
+            [] | [_] => true,
+            [using, _] | [_, using, _] => is_word(using, "USING"),
+            _ => false,
+        };
+        words_ok
+            && rest.next() == Some(&Token::Punct('('))
+            && matches!(rest.next(), Some(Token::Word { .. }))
+    }
+
+    /// Parses a `REFERENCES` clause for a foreign key on the given columns.
+    fn references(&mut self, columns: Vec<String>) -> Result<ForeignKeySpec, String> {
+        self.expect_keyword("REFERENCES")?;
+        let table = self.qualified_ident()?;
+        let mut column = None;
+        if self.is_punct('(') {
+            let mut cols = self.ident_list()?;
+            if cols.len() != 1 {
+                return Err(format!("Composite reference to table `{table}` is not supported"));
+            }
+            column = cols.pop();
+        }
+        Ok(ForeignKeySpec { columns, table, column })
+    }
+}
+
+/// Normalizes a SQL type to the name used for it by [`add_column_type`], when
+/// there is one, so that rendering a parsed schema gives back the same types.
+fn normalize_type(ty: &str) -> String {
+    let ty = ty.replace(" (", "(");
+    match ty.to_lowercase().as_str() {
+        "int" | "integer" | "int4" | "serial" => "Int",
+        "tinyint" => "TinyInt",
+        "bool" | "boolean" => "Bool",
+        "float" | "real" => "Float",
+        "timestamp" => "Time",
+        "date" => "Date",
+        "datetime" => "DateTime",
+        _ => return ty,
+    }
+    .into()
 }
 
 #[cfg(test)]
@@ -229,7 +1148,9 @@ mod tests {
     use std::rc::Rc;
 
     use super::*;
-    use crate::{stdlib::th_schema, tt};
+    use crate::one::PathEq;
+    use crate::stdlib::th_schema;
+    use crate::{tt, validate::Validate};
 
     #[test]
     fn sql_schema() {
@@ -265,4 +1186,626 @@ CREATE TABLE IF NOT EXISTS `Person` (
             .expect("SQL should render");
         expected.assert_eq(&ddl);
     }
+
+    /// Students enrolled in courses, with advisors who have offices.
+    fn university() -> DiscreteDblModel {
+        let mut model = DiscreteDblModel::new(Rc::new(th_nullable_schema()));
+        for x in ["Student", "Course", "Enrollment", "Professor", "Office"] {
+            model.add_ob(name(x), name("Entity"));
+        }
+        for x in ["Varchar(100)", "Decimal(10, 2)", "Text", "BigInt", "Uuid", "Bool"] {
+            model.add_ob(name(x), name("AttrType"));
+        }
+        let (mapping, nullable) = (Path::Id(name("Entity")), Path::single(name("Nullable")));
+        let (attr, nullable_attr) = (name("Attr").into(), name("NullableAttr").into());
+        for (f, dom, cod, mor_type) in [
+            ("student", "Enrollment", "Student", &mapping),
+            ("course", "Enrollment", "Course", &mapping),
+            ("advisor", "Student", "Professor", &nullable),
+            ("name", "Student", "Varchar(100)", &attr),
+            ("nickname", "Student", "Text", &nullable_attr),
+            ("gpa", "Student", "Decimal(10, 2)", &attr),
+            ("credits", "Course", "BigInt", &attr),
+            ("online", "Course", "Bool", &nullable_attr),
+            ("badge", "Professor", "Uuid", &attr),
+            ("office", "Professor", "Office", &mapping),
+            ("occupant", "Office", "Professor", &mapping),
+        ] {
+            model.add_mor(name(f), name(dom), name(cod), mor_type.clone());
+        }
+        model.add_equation(PathEq::new(
+            Path::pair(name("office"), name("occupant")),
+            Path::Id(name("Professor")),
+        ));
+        model
+    }
+
+    #[test]
+    fn sql_rich_schema() {
+        let model = university();
+        assert!(model.validate().is_ok());
+        let render = |backend| {
+            SQLAnalysis::new(backend)
+                .render(
+                    &model,
+                    |id| format!("{id}").as_str().into(),
+                    |id| format!("{id}").as_str().into(),
+                )
+                .unwrap()
+        };
+
+        let expected = expect![[r#"
+            CREATE TABLE IF NOT EXISTS "Student" (
+              "id" serial NOT NULL PRIMARY KEY,
+              "advisor" integer,
+              "name" varchar(100) NOT NULL,
+              "nickname" text,
+              "gpa" decimal(10, 2) NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS "Course" (
+              "id" serial NOT NULL PRIMARY KEY,
+              "credits" bigint NOT NULL,
+              "online" bool
+            );
+
+            CREATE TABLE IF NOT EXISTS "Enrollment" (
+              "student" integer NOT NULL,
+              "course" integer NOT NULL,
+              PRIMARY KEY ("student", "course"),
+              CONSTRAINT "FK_student_Enrollment_Student" FOREIGN KEY ("student") REFERENCES "Student" ("id"),
+              CONSTRAINT "FK_course_Enrollment_Course" FOREIGN KEY ("course") REFERENCES "Course" ("id")
+            );
+
+            CREATE TABLE IF NOT EXISTS "Professor" (
+              "id" serial NOT NULL PRIMARY KEY,
+              "badge" uuid NOT NULL,
+              "office" integer NOT NULL UNIQUE
+            );
+
+            CREATE TABLE IF NOT EXISTS "Office" (
+              "id" serial NOT NULL PRIMARY KEY,
+              "occupant" integer NOT NULL,
+              CONSTRAINT "FK_occupant_Office_Professor" FOREIGN KEY ("occupant") REFERENCES "Professor" ("id")
+            );
+
+            ALTER TABLE
+              "Student"
+            ADD
+              CONSTRAINT "FK_advisor_Student_Professor" FOREIGN KEY ("advisor") REFERENCES "Professor" ("id");
+
+            ALTER TABLE
+              "Professor"
+            ADD
+              CONSTRAINT "FK_office_Professor_Office" FOREIGN KEY ("office") REFERENCES "Office" ("id");"#]];
+        expected.assert_eq(&render(SQLBackend::PostgresSQL));
+
+        let expected = expect![[r#"
+            CREATE TABLE IF NOT EXISTS `Student` (
+              `id` int NOT NULL AUTO_INCREMENT PRIMARY KEY,
+              `advisor` int,
+              `name` varchar(100) NOT NULL,
+              `nickname` text,
+              `gpa` decimal(10, 2) NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS `Course` (
+              `id` int NOT NULL AUTO_INCREMENT PRIMARY KEY,
+              `credits` bigint NOT NULL,
+              `online` bool
+            );
+
+            CREATE TABLE IF NOT EXISTS `Enrollment` (
+              `student` int NOT NULL,
+              `course` int NOT NULL,
+              PRIMARY KEY (`student`, `course`),
+              CONSTRAINT `FK_student_Enrollment_Student` FOREIGN KEY (`student`) REFERENCES `Student` (`id`),
+              CONSTRAINT `FK_course_Enrollment_Course` FOREIGN KEY (`course`) REFERENCES `Course` (`id`)
+            );
+
+            CREATE TABLE IF NOT EXISTS `Professor` (
+              `id` int NOT NULL AUTO_INCREMENT PRIMARY KEY,
+              `badge` binary(16) NOT NULL,
+              `office` int NOT NULL UNIQUE
+            );
+
+            CREATE TABLE IF NOT EXISTS `Office` (
+              `id` int NOT NULL AUTO_INCREMENT PRIMARY KEY,
+              `occupant` int NOT NULL,
+              CONSTRAINT `FK_occupant_Office_Professor` FOREIGN KEY (`occupant`) REFERENCES `Professor` (`id`)
+            );
+
+            ALTER TABLE
+              `Student`
+            ADD
+              CONSTRAINT `FK_advisor_Student_Professor` FOREIGN KEY (`advisor`) REFERENCES `Professor` (`id`);
+
+            ALTER TABLE
+              `Professor`
+            ADD
+              CONSTRAINT `FK_office_Professor_Office` FOREIGN KEY (`office`) REFERENCES `Office` (`id`);"#]];
+        expected.assert_eq(&render(SQLBackend::MySQL));
+
+        let expected = expect![[r#"
+            PRAGMA foreign_keys = ON;
+
+            CREATE TABLE IF NOT EXISTS "Student" (
+              "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+              "advisor" integer,
+              "name" varchar(100) NOT NULL,
+              "nickname" text,
+              "gpa" real(10, 2) NOT NULL,
+              FOREIGN KEY ("advisor") REFERENCES "Professor" ("id")
+            );
+
+            CREATE TABLE IF NOT EXISTS "Course" (
+              "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+              "credits" bigint NOT NULL,
+              "online" boolean
+            );
+
+            CREATE TABLE IF NOT EXISTS "Enrollment" (
+              "student" integer NOT NULL,
+              "course" integer NOT NULL,
+              PRIMARY KEY ("student", "course"),
+              FOREIGN KEY ("student") REFERENCES "Student" ("id"),
+              FOREIGN KEY ("course") REFERENCES "Course" ("id")
+            );
+
+            CREATE TABLE IF NOT EXISTS "Professor" (
+              "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+              "badge" uuid_text NOT NULL,
+              "office" integer NOT NULL UNIQUE,
+              FOREIGN KEY ("office") REFERENCES "Office" ("id")
+            );
+
+            CREATE TABLE IF NOT EXISTS "Office" (
+              "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+              "occupant" integer NOT NULL,
+              FOREIGN KEY ("occupant") REFERENCES "Professor" ("id")
+            );"#]];
+        expected.assert_eq(&render(SQLBackend::SQLite));
+
+        // Nullability survives parsing the rendered schema.
+        for backend in [SQLBackend::MySQL, SQLBackend::SQLite, SQLBackend::PostgresSQL] {
+            let parsed = SQLAnalysis::new(backend.clone()).parse(&render(backend)).unwrap();
+            let mor_type = |table, col| parsed.mor_generator_type(&[table, col].into());
+            assert_eq!(mor_type("Student", "advisor"), name("Nullable").into());
+            assert_eq!(mor_type("Student", "nickname"), name("NullableAttr").into());
+            assert_eq!(mor_type("Student", "name"), name("Attr").into());
+            assert_eq!(mor_type("Enrollment", "student"), Path::Id(name("Entity")));
+        }
+    }
+
+    #[test]
+    fn sql_parse_postgres() {
+        let ddl = r#"
+            -- People and their dogs.
+            CREATE TABLE public.Person (
+                id SERIAL PRIMARY KEY,
+                "Name" VARCHAR (255) NOT NULL,
+                age integer DEFAULT 0
+            );
+            CREATE INDEX person_name ON Person ("Name");
+            CREATE TABLE IF NOT EXISTS dog (
+                id integer NOT NULL,
+                owner integer REFERENCES person (id) ON DELETE CASCADE,
+                walker integer,
+                weight double precision,
+                PRIMARY KEY (id),
+                CONSTRAINT fk_walker FOREIGN KEY (walker) REFERENCES person(id),
+                UNIQUE (owner, walker)
+            );
+        "#;
+        let model = SQLAnalysis::new(SQLBackend::PostgresSQL).parse(ddl).unwrap();
+        assert!(model.validate().is_ok());
+
+        let entities = model.ob_generators_with_type(&name("Entity")).sorted().collect_vec();
+        assert_eq!(entities, vec![name("dog"), name("person")]);
+        let attr_types = model.ob_generators_with_type(&name("AttrType")).sorted().collect_vec();
+        assert_eq!(attr_types, vec![name("Int"), name("VARCHAR(255)"), name("double precision")]);
+
+        let owner: QualifiedName = ["dog", "owner"].into();
+        assert_eq!(model.mor_generator_type(&owner), name("Nullable").into());
+        assert_eq!(model.mor_generator_cod(&owner), name("person"));
+        let walker: QualifiedName = ["dog", "walker"].into();
+        assert_eq!(model.mor_generator_cod(&walker), name("person"));
+        let person_name: QualifiedName = ["person", "Name"].into();
+        assert_eq!(model.mor_generator_type(&person_name), name("Attr").into());
+        assert_eq!(model.mor_generator_cod(&person_name), name("VARCHAR(255)"));
+        let weight: QualifiedName = ["dog", "weight"].into();
+        assert_eq!(model.mor_generator_type(&weight), name("NullableAttr").into());
+        assert!(!model.has_mor(&Path::single(["person", "id"].into())));
+        assert_eq!(model.mor_generators().count(), 5);
+    }
+
+    #[test]
+    fn sql_parse_mysql_indexes() {
+        let ddl = "
+            CREATE TABLE `Person` (
+              `id` int NOT NULL AUTO_INCREMENT PRIMARY KEY,
+              `key` varchar(10) NOT NULL,
+              `name` text NOT NULL,
+              KEY `person_key` (`key`),
+              INDEX USING BTREE (`name`),
+              FULLTEXT KEY `person_name` (`name`)
+            );
+        ";
+        let model = SQLAnalysis::new(SQLBackend::MySQL).parse(ddl).unwrap();
+        let key: QualifiedName = ["Person", "key"].into();
+        assert_eq!(model.mor_generator_cod(&key), name("varchar(10)"));
+        assert_eq!(model.mor_generators().count(), 2);
+    }
+
+    #[test]
+    fn sql_round_trip() {
+        let th = Rc::new(th_schema());
+        let model = tt::modelgen::Model::from_text(
+            &th.into(),
+            "[
+                Person : Entity,
+                Dog : Entity,
+                walks : (Hom Entity)[Person, Dog],
+                Hair : AttrType,
+                has : Attr[Person, Hair],
+            ]",
+        );
+        let model = model.unwrap().as_discrete().unwrap();
+        for backend in [SQLBackend::MySQL, SQLBackend::SQLite, SQLBackend::PostgresSQL] {
+            let ddl = SQLAnalysis::new(backend.clone())
+                .render(
+                    &model,
+                    |id| format!("{id}").as_str().into(),
+                    |id| format!("{id}").as_str().into(),
+                )
+                .unwrap();
+            let parsed = SQLAnalysis::new(backend).parse(&ddl).unwrap();
+            let walks: QualifiedName = ["Person", "walks"].into();
+            assert_eq!(parsed.mor_generator_cod(&walks), name("Dog"));
+            let has: QualifiedName = ["Person", "has"].into();
+            assert_eq!(parsed.mor_generator_cod(&has), name("Hair"));
+            assert_eq!(parsed.mor_generators().count(), 2);
+        }
+    }
+
+    #[test]
+    fn sql_parse_errors() {
+        let parse = |ddl| SQLAnalysis::new(SQLBackend::SQLite).parse(ddl);
+        assert!(parse("CREATE TABLE a (b INTEGER REFERENCES c)").is_err());
+        assert!(parse("CREATE TABLE a (id INTEGER, b INTEGER, b TEXT)").is_err());
+        assert!(parse("CREATE TABLE a (x INT, y INT, FOREIGN KEY (x, y) REFERENCES a)").is_err());
+        assert!(parse("CREATE TABLE a AS SELECT * FROM b").is_err());
+        assert!(parse("CREATE TABLE a (b TEXT").is_err());
+        assert!(parse("CREATE TABLE a (id INTEGER PRIMARY KEY, key TEXT)").is_err());
+        assert!(parse("CREATE TABLE a (id INTEGER, index VARCHAR(10))").is_err());
+        let ddl = "CREATE TABLE a (id INTEGER PRIMARY KEY, x INTEGER);
+                   CREATE TABLE b (y INTEGER REFERENCES a (x))";
+        assert!(parse(ddl).is_err());
+    }
+
+    fn schema(text: &str) -> DiscreteDblModel {
+        let th = Rc::new(th_schema());
+        let model = tt::modelgen::Model::from_text(&th.into(), text);
+        model.unwrap().as_discrete().unwrap()
+    }
+
+    fn migration() -> (DiscreteDblModel, DiscreteDblModel, DiscreteDblModelMapping) {
+        let old = schema(
+            "[
+                Person : Entity,
+                Dog : Entity,
+                walks : (Hom Entity)[Person, Dog],
+                Hair : AttrType,
+                has : Attr[Person, Hair],
+            ]",
+        );
+        let new = schema(
+            "[
+                Human : Entity,
+                Dog : Entity,
+                Leash : Entity,
+                walks : (Hom Entity)[Human, Dog],
+                leash : (Hom Entity)[Dog, Leash],
+                Color : AttrType,
+                Int : AttrType,
+                hair : Attr[Human, Color],
+                age : Attr[Human, Int],
+            ]",
+        );
+        let mapping = DiscreteDblModelMapping::new(
+            [
+                (name("Person"), name("Human")),
+                (name("Dog"), name("Dog")),
+                (name("Hair"), name("Color")),
+            ],
+            [
+                (name("walks"), Path::single(name("walks"))),
+                (name("has"), Path::single(name("hair"))),
+            ],
+        );
+        (old, new, mapping)
+    }
+
+    fn labeled(model: &DiscreteDblModel) -> LabeledSchema<'_> {
+        LabeledSchema {
+            model,
+            ob_label: &|id| format!("{id}").as_str().into(),
+            mor_label: &|id| format!("{id}").as_str().into(),
+        }
+    }
+
+    #[test]
+    fn sql_migration() {
+        let (old, new, mapping) = migration();
+        let (old, new) = (labeled(&old), labeled(&new));
+
+        let expected = expect![[r#"
+            ALTER TABLE
+              "Person" RENAME TO "Human";
+
+            ALTER TABLE
+              "Human" RENAME COLUMN "has" TO "hair";
+
+            CREATE TABLE IF NOT EXISTS "Leash" ("id" serial NOT NULL PRIMARY KEY);
+
+            ALTER TABLE
+              "Dog"
+            ADD
+              COLUMN "leash" integer;
+
+            ALTER TABLE
+              "Dog"
+            ADD
+              CONSTRAINT "FK_leash_Dog_Leash" FOREIGN KEY ("leash") REFERENCES "Leash" ("id");
+
+            ALTER TABLE
+              "Human"
+            ALTER COLUMN
+              "hair" TYPE Color,
+            ALTER COLUMN
+              "hair"
+            SET
+              NOT NULL;
+
+            ALTER TABLE
+              "Human"
+              DROP CONSTRAINT "FK_walks_Person_Dog";
+
+            ALTER TABLE
+              "Human"
+            ADD
+              CONSTRAINT "FK_walks_Human_Dog" FOREIGN KEY ("walks") REFERENCES "Dog" ("id");
+
+            ALTER TABLE
+              "Human"
+            ADD
+              COLUMN "age" integer;"#]];
+        let sql = SQLAnalysis::new(SQLBackend::PostgresSQL).migrate(&old, &new, &mapping);
+        expected.assert_eq(&sql.unwrap());
+
+        let expected = expect![[r#"
+            RENAME TABLE `Person` TO `Human`;
+
+            ALTER TABLE
+              `Human` RENAME COLUMN `has` TO `hair`;
+
+            CREATE TABLE IF NOT EXISTS `Leash` (`id` int NOT NULL AUTO_INCREMENT PRIMARY KEY);
+
+            ALTER TABLE
+              `Dog`
+            ADD
+              COLUMN `leash` int;
+
+            ALTER TABLE
+              `Dog`
+            ADD
+              CONSTRAINT `FK_leash_Dog_Leash` FOREIGN KEY (`leash`) REFERENCES `Leash` (`id`);
+
+            ALTER TABLE
+              `Human`
+            MODIFY
+              COLUMN `hair` Color NOT NULL;
+
+            ALTER TABLE
+              `Human`
+              DROP FOREIGN KEY `FK_walks_Person_Dog`;
+
+            ALTER TABLE
+              `Human`
+            ADD
+              CONSTRAINT `FK_walks_Human_Dog` FOREIGN KEY (`walks`) REFERENCES `Dog` (`id`);
+
+            ALTER TABLE
+              `Human`
+            ADD
+              COLUMN `age` int;"#]];
+        let sql = SQLAnalysis::new(SQLBackend::MySQL).migrate(&old, &new, &mapping);
+        expected.assert_eq(&sql.unwrap());
+
+        let expected = expect![[r#"
+            PRAGMA foreign_keys = OFF;
+
+            ALTER TABLE
+              "Person" RENAME TO "Human";
+
+            ALTER TABLE
+              "Human" RENAME COLUMN "has" TO "hair";
+
+            CREATE TABLE IF NOT EXISTS "Leash" (
+              "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT
+            );
+
+            CREATE TABLE "new_Dog" (
+              "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+              "leash" integer,
+              FOREIGN KEY ("leash") REFERENCES "Leash" ("id")
+            );
+
+            INSERT INTO
+              "new_Dog" ("id")
+            SELECT
+              "id"
+            FROM
+              "Dog";
+
+            DROP TABLE "Dog";
+
+            ALTER TABLE
+              "new_Dog" RENAME TO "Dog";
+
+            CREATE TABLE "new_Human" (
+              "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+              "age" integer,
+              "hair" Color NOT NULL,
+              "walks" integer NOT NULL,
+              FOREIGN KEY ("walks") REFERENCES "Dog" ("id")
+            );
+
+            INSERT INTO
+              "new_Human" ("id", "hair", "walks")
+            SELECT
+              "id",
+              "hair",
+              "walks"
+            FROM
+              "Human";
+
+            DROP TABLE "Human";
+
+            ALTER TABLE
+              "new_Human" RENAME TO "Human";
+
+            PRAGMA foreign_keys = ON;"#]];
+        let sql = SQLAnalysis::new(SQLBackend::SQLite).migrate(&old, &new, &mapping);
+        expected.assert_eq(&sql.unwrap());
+
+        // The identity migration does nothing.
+        let identity = DiscreteDblModelMapping::new(
+            ["Person", "Dog", "Hair"].map(|x| (name(x), name(x))),
+            ["walks", "has"].map(|f| (name(f), Path::single(name(f)))),
+        );
+        let sql = SQLAnalysis::new(SQLBackend::SQLite).migrate(&old, &old, &identity);
+        assert_eq!(sql, Ok("".into()));
+    }
+
+    #[test]
+    fn sql_migration_junction() {
+        // Renaming a table retargets the foreign keys of a junction table.
+        let old = schema(
+            "[
+                Student : Entity,
+                Course : Entity,
+                Enrollment : Entity,
+                student : (Hom Entity)[Enrollment, Student],
+                course : (Hom Entity)[Enrollment, Course],
+            ]",
+        );
+        let new = schema(
+            "[
+                Student : Entity,
+                Class : Entity,
+                Enrollment : Entity,
+                student : (Hom Entity)[Enrollment, Student],
+                course : (Hom Entity)[Enrollment, Class],
+            ]",
+        );
+        let mapping = DiscreteDblModelMapping::new(
+            [
+                (name("Student"), name("Student")),
+                (name("Course"), name("Class")),
+                (name("Enrollment"), name("Enrollment")),
+            ],
+            ["student", "course"].map(|f| (name(f), Path::single(name(f)))),
+        );
+        let (old, new) = (labeled(&old), labeled(&new));
+
+        let expected = expect![[r#"
+            ALTER TABLE
+              "Course" RENAME TO "Class";
+
+            ALTER TABLE
+              "Enrollment"
+              DROP CONSTRAINT "FK_course_Enrollment_Course";
+
+            ALTER TABLE
+              "Enrollment"
+            ADD
+              CONSTRAINT "FK_course_Enrollment_Class" FOREIGN KEY ("course") REFERENCES "Class" ("id");"#]];
+        let sql = SQLAnalysis::new(SQLBackend::PostgresSQL).migrate(&old, &new, &mapping);
+        expected.assert_eq(&sql.unwrap());
+
+        // SQLite updates references to a renamed table by itself.
+        let expected = expect![[r#"
+            ALTER TABLE
+              "Course" RENAME TO "Class";"#]];
+        let sql = SQLAnalysis::new(SQLBackend::SQLite).migrate(&old, &new, &mapping);
+        expected.assert_eq(&sql.unwrap());
+    }
+
+    #[test]
+    fn sql_migration_errors() {
+        let analysis = SQLAnalysis::new(SQLBackend::PostgresSQL);
+        let migrate = |old: &str, new: &str, mapping| {
+            let (old, new) = (schema(old), schema(new));
+            analysis.migrate(&labeled(&old), &labeled(&new), &mapping).unwrap_err()
+        };
+        let people = "[Person : Entity, Dog : Entity, walks : (Hom Entity)[Person, Dog]]";
+
+        let err = migrate(people, people, DiscreteDblModelMapping::default());
+        assert!(err.starts_with("Mapping is not a morphism"));
+
+        let mapping = DiscreteDblModelMapping::new(
+            [(name("Person"), name("Thing")), (name("Dog"), name("Thing"))],
+            [(name("walks"), Path::single(name("walks")))],
+        );
+        let err = migrate(people, "[Thing : Entity, walks : (Hom Entity)[Thing, Thing]]", mapping);
+        assert!(err.contains("merging tables is not supported"));
+
+        let mapping = DiscreteDblModelMapping::new(
+            [(name("Person"), name("Person")), (name("Dog"), name("Dog"))],
+            [(name("walks"), Path::pair(name("holds"), name("attached")))],
+        );
+        let err = migrate(
+            people,
+            "[
+                Person : Entity,
+                Dog : Entity,
+                Leash : Entity,
+                holds : (Hom Entity)[Person, Leash],
+                attached : (Hom Entity)[Leash, Dog],
+            ]",
+            mapping,
+        );
+        assert!(err.contains("mapped to a composite"));
+
+        let mapping = DiscreteDblModelMapping::new(
+            [(name("Person"), name("Person"))],
+            [(name("manager"), Path::Id(name("Person")))],
+        );
+        let err = migrate(
+            "[Person : Entity, manager : (Hom Entity)[Person, Person]]",
+            "[Person : Entity]",
+            mapping,
+        );
+        assert!(err.contains("dropping columns is not supported"));
+
+        let mapping = DiscreteDblModelMapping::new(
+            [(name("Person"), name("Person")), (name("Name"), name("Name"))],
+            [
+                (name("first"), Path::single(name("name"))),
+                (name("last"), Path::single(name("name"))),
+            ],
+        );
+        let err = migrate(
+            "[
+                Person : Entity,
+                Name : AttrType,
+                first : Attr[Person, Name],
+                last : Attr[Person, Name],
+            ]",
+            "[Person : Entity, Name : AttrType, name : Attr[Person, Name]]",
+            mapping,
+        );
+        assert!(err.contains("merging columns is not supported"));
+    }
 }
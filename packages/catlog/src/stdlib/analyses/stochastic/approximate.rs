@@ -0,0 +1,445 @@
+//! Approximate stochastic simulation of reaction networks.
+//!
+//! Exact stochastic simulation fires one reaction at a time, which is too slow
+//! for networks with large copy numbers. *Tau-leaping* instead fires many
+//! reactions at once, over a time step chosen so that the propensities change
+//! little, as proposed by [Cao, Gillespie & Petzold
+//! 2006](https://doi.org/10.1063/1.2159468). *Hybrid* simulation treats species
+//! with large copy numbers as continuous, evolving by the mass-action ODEs of the
+//! reactions that change only such species, while the remaining reactions fire
+//! stochastically.
+
+use itertools::Itertools;
+use nalgebra::DVector;
+use num_traits::Zero;
+use ode_solvers::dop_shared::IntegrationError;
+use rand::{Rng, rngs::SmallRng};
+use rand_distr::{Distribution, Exp1, Poisson};
+
+use crate::simulate::ode::{
+    EventAction, EventDirection, NumericalPolynomialSystem, ODEEvent, ODEProblem,
+};
+use crate::zero::{alg::Polynomial, rig::Monomial};
+
+/// A reaction in a stochastic reaction network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reaction {
+    /// Number of copies of each species consumed by the reaction.
+    pub reactants: Vec<u32>,
+
+    /// Exponent of each species that modulates the reaction without being
+    /// consumed by it, such as the source of a link in a stock-flow model.
+    ///
+    /// A negative exponent divides the propensity, which vanishes when the
+    /// species is absent.
+    pub modifiers: Vec<i8>,
+
+    /// Net change in each species when the reaction fires.
+    pub changes: Vec<isize>,
+
+    /// Rate coefficient of the reaction.
+    pub rate: f64,
+}
+
+impl Reaction {
+    /// Propensity of the reaction at a state, by the law of mass action.
+    ///
+    /// The number of ways to choose the reactants is a falling factorial in the
+    /// copy number of each species, as in exact stochastic simulation, while
+    /// the modifiers enter as ordinary powers.
+    pub fn propensity(&self, x: &[f64]) -> f64 {
+        let a = std::iter::zip(x, &self.reactants).fold(self.rate, |acc, (x, k)| {
+            (0..*k).fold(acc, |acc, m| acc * (x - m as f64).max(0.0))
+        });
+        std::iter::zip(x, &self.modifiers).fold(a, |acc, (x, e)| match *e {
+            0 => acc,
+            e if e < 0 && *x <= 0.0 => 0.0,
+            e => acc * x.powi(e.into()),
+        })
+    }
+
+    /// Propensity of the reaction as a polynomial in the state variables.
+    fn propensity_polynomial(&self) -> Polynomial<usize, f32, i8> {
+        let mut poly = Polynomial::zero() + (self.rate as f32);
+        for (i, k) in self.reactants.iter().enumerate() {
+            for m in 0..*k {
+                poly = poly * (Polynomial::generator(i) + (-(m as f32)));
+            }
+        }
+        let modifiers: Monomial<_, _> = self
+            .modifiers
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| **e != 0)
+            .map(|(i, e)| (i, *e))
+            .collect();
+        poly = poly * [(1.0, modifiers)].into_iter().collect::<Polynomial<_, _, _>>();
+        poly.normalize()
+    }
+
+    /// Largest number of times that the reaction can fire before exhausting one
+    /// of its reactants, if it consumes any.
+    fn max_firings(&self, x: &[f64]) -> Option<f64> {
+        std::iter::zip(x, &self.changes)
+            .filter(|(_, v)| **v < 0)
+            .map(|(x, v)| (x / (-*v) as f64).floor())
+            .min_by(f64::total_cmp)
+    }
+
+    fn fire(&self, x: &mut [f64], times: f64) {
+        for (x, v) in std::iter::zip(x.iter_mut(), &self.changes) {
+            *x += times * (*v as f64);
+        }
+    }
+}
+
+/// Reactions that can fire this few times are fired one at a time in
+/// tau-leaping.
+const CRITICAL_FIRINGS: f64 = 10.0;
+
+/// Number of exact steps taken when tau-leaping would not save any work.
+const EXACT_STEPS: usize = 100;
+
+/// Advances a reaction network by exact stochastic simulation.
+///
+/// Takes at most `max_steps` steps, stopping at time `t_end`. Returns the time
+/// reached.
+pub(super) fn exact_steps(
+    reactions: &[Reaction],
+    x: &mut [f64],
+    mut t: f64,
+    t_end: f64,
+    max_steps: usize,
+    rng: &mut SmallRng,
+) -> f64 {
+    for _ in 0..max_steps {
+        let a = reactions.iter().map(|r| r.propensity(x)).collect_vec();
+        let a0: f64 = a.iter().sum();
+        if a0 <= 0.0 {
+            return t_end;
+        }
+        let tau: f64 = rng.sample::<f64, _>(Exp1) / a0;
+        if t + tau > t_end {
+            return t_end;
+        }
+        t += tau;
+        reactions[choose(&a, a0, rng)].fire(x, 1.0);
+    }
+    t
+}
+
+/// Advances a reaction network by tau-leaping from time `t` to `t_end`.
+///
+/// The step size is selected by the method of Cao, Gillespie & Petzold, with
+/// relative error control parameter `epsilon`. Reactions close to exhausting
+/// their reactants are treated as critical and fire at most once per step, and
+/// steps are halved whenever a species would become negative.
+pub(super) fn tau_leap(
+    reactions: &[Reaction],
+    x: &mut [f64],
+    mut t: f64,
+    t_end: f64,
+    epsilon: f64,
+    rng: &mut SmallRng,
+) {
+    while t < t_end {
+        let a = reactions.iter().map(|r| r.propensity(x)).collect_vec();
+        let a0: f64 = a.iter().sum();
+        if a0 <= 0.0 {
+            return;
+        }
+        let critical = std::iter::zip(reactions, &a)
+            .map(|(r, a)| *a > 0.0 && r.max_firings(x).is_some_and(|l| l < CRITICAL_FIRINGS))
+            .collect_vec();
+
+        let mut tau1 = leap_size(reactions, x, &a, &critical, epsilon);
+        if tau1 < 10.0 / a0 {
+            t = exact_steps(reactions, x, t, t_end, EXACT_STEPS, rng);
+            continue;
+        }
+
+        let a0_critical: f64 =
+            std::iter::zip(&a, &critical).filter(|(_, c)| **c).map(|(a, _)| a).sum();
+        loop {
+            let tau2 = if a0_critical > 0.0 {
+                rng.sample::<f64, _>(Exp1) / a0_critical
+            } else {
+                f64::INFINITY
+            };
+            let (mut tau, mut fire_critical) = if tau1 < tau2 {
+                (tau1, false)
+            } else {
+                (tau2, true)
+            };
+            if t + tau > t_end {
+                (tau, fire_critical) = (t_end - t, false);
+            }
+
+            let mut y = x.to_vec();
+            for (j, r) in reactions.iter().enumerate() {
+                if !critical[j] && a[j] > 0.0 {
+                    r.fire(&mut y, poisson(a[j] * tau, rng));
+                }
+            }
+            if fire_critical {
+                let a_critical = std::iter::zip(&a, &critical)
+                    .map(|(a, c)| if *c { *a } else { 0.0 })
+                    .collect_vec();
+                reactions[choose(&a_critical, a0_critical, rng)].fire(&mut y, 1.0);
+            }
+
+            if y.iter().all(|y| *y >= 0.0) {
+                x.copy_from_slice(&y);
+                t += tau;
+                break;
+            }
+            tau1 = tau / 2.0;
+        }
+    }
+}
+
+/// Largest leap over which the relative change in each propensity is expected
+/// to be bounded by `epsilon`, ignoring critical reactions.
+fn leap_size(reactions: &[Reaction], x: &[f64], a: &[f64], critical: &[bool], epsilon: f64) -> f64 {
+    let mut tau = f64::INFINITY;
+    for (i, x_i) in x.iter().enumerate() {
+        // Highest order of any reaction consuming the species, corrected for
+        // reactions consuming several copies of it, plus the order of the
+        // species as a modifier.
+        let g = reactions
+            .iter()
+            .filter(|r| r.reactants[i] > 0 || r.modifiers[i] != 0)
+            .map(|r| {
+                let modifier = r.modifiers[i].unsigned_abs() as f64;
+                if r.reactants[i] == 0 {
+                    return modifier;
+                }
+                let order = r.reactants.iter().sum::<u32>() as f64;
+                let k = r.reactants[i] as f64;
+                let correction: f64 = (1..r.reactants[i])
+                    .map(|m| {
+                        if *x_i > m as f64 {
+                            m as f64 / (x_i - m as f64)
+                        } else {
+                            0.0
+                        }
+                    })
+                    .sum();
+                order / k * (k + correction) + modifier
+            })
+            .fold(0.0, f64::max);
+        if g == 0.0 {
+            continue;
+        }
+
+        let (mut mu, mut sigma2) = (0.0, 0.0);
+        for (j, r) in reactions.iter().enumerate() {
+            if !critical[j] {
+                let v = r.changes[i] as f64;
+                mu += v * a[j];
+                sigma2 += v * v * a[j];
+            }
+        }
+        let bound = (epsilon * x_i / g).max(1.0);
+        if mu != 0.0 {
+            tau = tau.min(bound / mu.abs());
+        }
+        if sigma2 > 0.0 {
+            tau = tau.min(bound * bound / sigma2);
+        }
+    }
+    tau
+}
+
+/// A partition of a reaction network for hybrid simulation.
+pub(super) struct HybridPartition {
+    /// Whether each species is treated as continuous.
+    pub continuous: Vec<bool>,
+
+    /// Whether each reaction is treated deterministically, which is the case
+    /// when it changes only continuous species.
+    pub fast: Vec<bool>,
+}
+
+impl HybridPartition {
+    /// Partitions a network, treating species with at least `threshold` copies
+    /// as continuous.
+    pub fn new(reactions: &[Reaction], x: &[f64], threshold: f64) -> Self {
+        let continuous = x.iter().map(|x| *x >= threshold).collect_vec();
+        let fast = reactions
+            .iter()
+            .map(|r| std::iter::zip(&r.changes, &continuous).all(|(v, c)| *v == 0 || *c))
+            .collect();
+        Self { continuous, fast }
+    }
+}
+
+/// Advances a reaction network by hybrid simulation from time `t` to `t_end`.
+///
+/// The continuous species evolve according to the vector field `fast_system`,
+/// the mass-action ODEs of the fast reactions, while the slow reactions fire
+/// stochastically. The time of the next slow reaction is found by integrating
+/// the total propensity of the slow reactions alongside the ODEs, until it
+/// reaches a standard exponential variable. Fails if the ODEs cannot be
+/// integrated, leaving the state unchanged.
+pub(super) fn hybrid_step(
+    reactions: &[Reaction],
+    partition: &HybridPartition,
+    fast_system: Vec<Polynomial<usize, f32, i8>>,
+    x: &mut [f64],
+    mut t: f64,
+    t_end: f64,
+    rng: &mut SmallRng,
+) -> Result<(), IntegrationError> {
+    let n = x.len();
+    let hazard: Polynomial<_, _, _> = std::iter::zip(reactions, &partition.fast)
+        .filter(|(_, fast)| !**fast)
+        .map(|(r, _)| r.propensity_polynomial())
+        .sum();
+    let mut components = fast_system;
+    components.push(hazard.normalize());
+    let system = NumericalPolynomialSystem { components };
+
+    let mut threshold: f64 = rng.sample(Exp1);
+    let mut y = DVector::from_iterator(n + 1, x.iter().copied().chain([0.0]));
+    while t < t_end {
+        let event = ODEEvent {
+            condition: Polynomial::generator(n) + (-threshold as f32),
+            direction: EventDirection::Rising,
+            action: EventAction::Terminate,
+        };
+        let problem = ODEProblem::new(&system, y.clone()).time_span((t, t_end)).events(vec![event]);
+        let dt = (t_end - t) / 20.0;
+        let output = problem.solve_dopri5_with_events(dt)?;
+        let (t_out, y_out) = output.result.get();
+        let (Some(t_last), Some(y_last)) = (t_out.last(), y_out.last()) else {
+            break;
+        };
+        (t, y) = (*t_last, y_last.clone());
+        if output.events.is_empty() {
+            break;
+        }
+
+        // Fire a slow reaction, chosen in proportion to its propensity.
+        let state = y.as_slice()[..n].to_vec();
+        let a = std::iter::zip(reactions, &partition.fast)
+            .map(|(r, fast)| if *fast { 0.0 } else { r.propensity(&state) })
+            .collect_vec();
+        let a0: f64 = a.iter().sum();
+        if a0 > 0.0 {
+            reactions[choose(&a, a0, rng)].fire(&mut y.as_mut_slice()[..n], 1.0);
+        }
+        y[n] = 0.0;
+        threshold = rng.sample(Exp1);
+    }
+    x.copy_from_slice(&y.as_slice()[..n]);
+
+    // Keep the discrete species integral despite numerical drift.
+    for (x, c) in std::iter::zip(x.iter_mut(), &partition.continuous) {
+        if !*c {
+            *x = x.round().max(0.0);
+        }
+    }
+    Ok(())
+}
+
+/// Chooses an index with probability proportional to its weight.
+fn choose(weights: &[f64], total: f64, rng: &mut SmallRng) -> usize {
+    let target = total * rng.random::<f64>();
+    let mut sum = 0.0;
+    for (j, w) in weights.iter().enumerate() {
+        sum += w;
+        if target < sum {
+            return j;
+        }
+    }
+    weights.iter().rposition(|w| *w > 0.0).unwrap_or_default()
+}
+
+/// Samples from a Poisson distribution.
+fn poisson(mean: f64, rng: &mut SmallRng) -> f64 {
+    match Poisson::new(mean) {
+        Ok(dist) => dist.sample(rng),
+        Err(_) => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn propensities() {
+        let r = Reaction {
+            reactants: vec![2, 1],
+            modifiers: vec![0, 0],
+            changes: vec![-2, 1],
+            rate: 0.5,
+        };
+        assert_eq!(r.propensity(&[4.0, 3.0]), 0.5 * 4.0 * 3.0 * 3.0);
+        assert_eq!(r.propensity(&[1.0, 3.0]), 0.0);
+        assert_eq!(r.propensity_polynomial().eval(|i| [4.0, 3.0][*i]), 18.0);
+        assert_eq!(r.max_firings(&[5.0, 0.0]), Some(2.0));
+
+        // Flow from the first species modulated by the second, as by a negative link.
+        let r = Reaction {
+            reactants: vec![1, 0],
+            modifiers: vec![0, -1],
+            changes: vec![-1, 1],
+            rate: 2.0,
+        };
+        assert_eq!(r.propensity(&[3.0, 4.0]), 1.5);
+        assert_eq!(r.propensity(&[3.0, 0.0]), 0.0);
+        assert_eq!(r.propensity_polynomial().eval(|i| [3.0, 4.0][*i]), 1.5);
+    }
+
+    #[test]
+    fn tau_leaping_decay() {
+        // Pure decay X -> 0, so that the mean is x0 exp(-k t).
+        let r = Reaction {
+            reactants: vec![1],
+            modifiers: vec![0],
+            changes: vec![-1],
+            rate: 1.0,
+        };
+        let mut rng = SmallRng::seed_from_u64(0);
+        let runs = 20;
+        let mut mean = 0.0;
+        for _ in 0..runs {
+            let mut x = vec![10000.0];
+            tau_leap(std::slice::from_ref(&r), &mut x, 0.0, 1.0, 0.03, &mut rng);
+            assert!(x[0] >= 0.0 && x[0].fract() == 0.0);
+            mean += x[0] / runs as f64;
+        }
+        let expected = 10000.0 * (-1f64).exp();
+        assert!((mean - expected).abs() < 0.02 * expected);
+
+        // Critical reactions are fired one at a time, so extinction is exact.
+        let mut x = vec![5.0];
+        tau_leap(std::slice::from_ref(&r), &mut x, 0.0, 100.0, 0.03, &mut rng);
+        assert_eq!(x, vec![0.0]);
+    }
+
+    #[test]
+    fn hybrid_blow_up() {
+        // Autocatalysis 2X -> 3X, whose mass-action ODE blows up in finite time.
+        let r = Reaction {
+            reactants: vec![2],
+            modifiers: vec![0],
+            changes: vec![1],
+            rate: 1.0,
+        };
+        let reactions = std::slice::from_ref(&r);
+        let partition = HybridPartition::new(reactions, &[1000.0], 100.0);
+        assert_eq!(partition.fast, vec![true]);
+        let x = Polynomial::<usize, f32, i8>::generator(0);
+        let mut state = vec![1000.0];
+        let mut rng = SmallRng::seed_from_u64(0);
+        let result =
+            hybrid_step(reactions, &partition, vec![x.clone() * x], &mut state, 0.0, 1.0, &mut rng);
+        assert!(result.is_err());
+        assert_eq!(state, vec![1000.0]);
+    }
+}
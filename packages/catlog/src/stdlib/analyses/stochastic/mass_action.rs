@@ -1,15 +1,32 @@
 //! Stochastic mass action anaylsis of ODEs.
 //!
-//! These stochastic mass-action use statistical methods to apply transitions.
+//! These stochastic mass-action use statistical methods to apply transitions,
+//! for Petri nets and for stock-flow models.
 
 use indexmap::IndexMap;
+use itertools::Itertools;
+use num_traits::Zero;
+use ode_solvers::dop_shared::IntegrationError;
+use rand::{SeedableRng, rngs::SmallRng};
 use rebop::gillespie;
 use std::collections::HashMap;
+use thiserror::Error;
 
+use super::{HybridPartition, Reaction, exact_steps, hybrid_step, tau_leap};
+use crate::one::FgCategory;
+use crate::simulate::ode::PolynomialSystem;
+use crate::stdlib::analyses::ode::{
+    FlowParameter, MassConservationType, ODESolution, Parameter, PetriNetMassActionAnalysis,
+    StockFlowMassActionAnalysis,
+};
 use crate::{
-    dbl::{modal::*, model::FpDblModel, theory::Unital},
-    stdlib::analyses::{ode::ODESolution, petri::transition_interface},
-    zero::{QualifiedName, name},
+    dbl::{
+        modal::*,
+        model::{DiscreteTabModel, FpDblModel},
+        theory::{TabMorType, TabObType, Unital},
+    },
+    stdlib::analyses::petri::transition_interface,
+    zero::{QualifiedName, alg::Polynomial, name},
 };
 
 #[cfg(feature = "serde")]
@@ -34,12 +51,156 @@ pub struct StochasticMassActionProblemData {
 
     /// Duration of simulation.
     pub duration: f32,
+
+    /// Seed for the random number generator.
+    ///
+    /// Simulations are reproducible when a seed is given.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: Option<u64>,
+
+    /// Time between samples of the state, by default one unit of time.
+    #[cfg_attr(feature = "serde", serde(rename = "sampleInterval", default))]
+    pub sample_interval: Option<f32>,
+
+    /// Method of stochastic simulation.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub method: StochasticMethod,
+}
+
+/// Method of simulating a stochastic mass-action system.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum StochasticMethod {
+    /// Exact simulation by Gillespie's direct method.
+    #[default]
+    Exact,
+
+    /// Approximate simulation by tau-leaping.
+    TauLeaping {
+        /// Bound on the relative change in propensities over a leap.
+        #[cfg_attr(feature = "serde", serde(default = "default_epsilon"))]
+        epsilon: f32,
+    },
+
+    /// Hybrid simulation, treating species with large copy numbers as
+    /// continuous.
+    ///
+    /// Reactions that change only continuous species are simulated by the
+    /// mass-action ODEs, while all other reactions fire stochastically. Species
+    /// are classified at the start of each sample interval.
+    Hybrid {
+        /// Copy number at or above which a species is continuous.
+        threshold: f32,
+    },
+}
+
+fn default_epsilon() -> f32 {
+    0.03
+}
+
+/// Data defining an ensemble of stochastic simulations.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct StochasticEnsembleData {
+    /// Number of simulations in the ensemble.
+    pub runs: usize,
+
+    /// Quantiles, between zero and one, at which to summarize the ensemble.
+    #[cfg_attr(feature = "serde", serde(default = "default_quantiles"))]
+    pub quantiles: Vec<f32>,
+}
+
+fn default_quantiles() -> Vec<f32> {
+    vec![0.05, 0.25, 0.5, 0.75, 0.95]
+}
+
+/// Values of a quantile of a species over time, across an ensemble.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct QuantileBand {
+    /// The quantile, between zero and one.
+    pub quantile: f32,
+
+    /// Values of the quantile at the sample times.
+    pub values: Vec<f32>,
+}
+
+/// Summary statistics of a species over time, across an ensemble.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct SpeciesSummary {
+    /// Means at the sample times.
+    pub mean: Vec<f32>,
+
+    /// Sample standard deviations at the sample times.
+    #[cfg_attr(feature = "serde", serde(rename = "stdDev"))]
+    pub std_dev: Vec<f32>,
+
+    /// Quantile bands, in the order requested.
+    pub quantiles: Vec<QuantileBand>,
+
+    /// Fraction of simulations in which the species is extinct, that is, has
+    /// no individuals, at the end of the simulation.
+    #[cfg_attr(feature = "serde", serde(rename = "extinctionProbability"))]
+    pub extinction_probability: f32,
+}
+
+/// Results of an ensemble of stochastic simulations.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct StochasticEnsemble {
+    /// Sample times, shared by all simulations.
+    pub time: Vec<f32>,
+
+    /// Individual simulations of the ensemble.
+    pub trajectories: Vec<ODESolution>,
+
+    /// Map from object IDs to summary statistics.
+    pub summaries: HashMap<QualifiedName, SpeciesSummary>,
+}
+
+/// An error in a stochastic simulation.
+#[derive(Debug, Error)]
+pub enum StochasticError {
+    /// The time between samples is not positive.
+    #[error("Sample interval `{0}` is not positive")]
+    SampleInterval(f32),
+
+    /// An ensemble has no runs to summarize.
+    #[error("Ensemble has no runs")]
+    NoRuns,
+
+    /// A flow or link of a stock-flow model is attached to something other
+    /// than a stock.
+    #[error("Object `{0}` is not a stock")]
+    NotAStock(QualifiedName),
+
+    /// The ODEs of the hybrid method could not be integrated.
+    #[error("Simulation failed: {0}")]
+    Integration(#[from] IntegrationError),
 }
 
 /// Stochastic mass-action analysis of a model.
 pub struct StochasticMassActionAnalysis {
-    /// Reaction network for the analysis.
-    pub problem: rebop::gillespie::Gillespie,
+    /// Reaction network for exact simulation by Gillespie's direct method.
+    ///
+    /// Absent when some propensity is not of mass-action form, in which case
+    /// exact simulation uses the [reactions](Self::reactions) instead.
+    pub problem: Option<rebop::gillespie::Gillespie>,
 
     /// Map from object IDs to variable indices.
     pub variable_index: IndexMap<QualifiedName, usize>,
@@ -49,31 +210,262 @@ pub struct StochasticMassActionAnalysis {
 
     /// Duration of simulation.
     pub duration: f32,
+
+    /// Seed for the random number generator, if any.
+    pub seed: Option<u64>,
+
+    /// Time between samples of the state.
+    pub sample_interval: f32,
+
+    /// Method of stochastic simulation.
+    pub method: StochasticMethod,
+
+    /// Reactions of the network.
+    pub reactions: Vec<Reaction>,
+
+    /// Transition IDs of the reactions.
+    pub transitions: Vec<QualifiedName>,
+
+    /// Symbolic mass-action ODEs of the network, used by the hybrid method.
+    pub mass_action: PolynomialSystem<QualifiedName, Parameter<FlowParameter>, i8>,
+
+    /// Map from transition IDs to rate coefficients.
+    pub rates: HashMap<QualifiedName, f32>,
+
+    rng: SmallRng,
 }
 
 impl StochasticMassActionAnalysis {
+    fn new(
+        problem: Option<gillespie::Gillespie>,
+        variable_index: IndexMap<QualifiedName, usize>,
+        reactions: Vec<Reaction>,
+        transitions: Vec<QualifiedName>,
+        mass_action: PolynomialSystem<QualifiedName, Parameter<FlowParameter>, i8>,
+        data: StochasticMassActionProblemData,
+    ) -> Self {
+        Self {
+            problem,
+            variable_index,
+            initial_values: data.initial_values,
+            duration: data.duration,
+            seed: data.seed,
+            sample_interval: data.sample_interval.unwrap_or(1.0),
+            method: data.method,
+            reactions,
+            transitions,
+            mass_action,
+            rates: data.rates,
+            rng: SmallRng::from_os_rng(),
+        }
+    }
+
     /// Simulates the stochastic mass-action system and collects the results.
-    pub fn simulate(&mut self) -> ODESolution {
-        let mut time = vec![0.0];
-        let mut states: HashMap<_, _> = self
+    ///
+    /// The state is sampled at multiples of the sample interval, from the
+    /// start to the end of the simulation. The sample interval must be
+    /// positive. Otherwise, only the hybrid method can fail, when its ODEs
+    /// cannot be integrated.
+    pub fn simulate(&mut self) -> Result<ODESolution, StochasticError> {
+        self.check_sample_interval()?;
+        if let Some(seed) = self.seed {
+            self.reseed(seed);
+        }
+        Ok(self.run()?)
+    }
+
+    /// Simulates an ensemble of independent runs of the stochastic system and
+    /// summarizes the results.
+    ///
+    /// When a seed is given, the runs are seeded consecutively starting from
+    /// it, so that the ensemble is reproducible and each run can be reproduced
+    /// on its own. The ensemble must have at least one run.
+    pub fn simulate_ensemble(
+        &mut self,
+        data: &StochasticEnsembleData,
+    ) -> Result<StochasticEnsemble, StochasticError> {
+        self.check_sample_interval()?;
+        if data.runs == 0 {
+            return Err(StochasticError::NoRuns);
+        }
+        let trajectories = (0..data.runs)
+            .map(|k| {
+                if let Some(seed) = self.seed {
+                    self.reseed(seed.wrapping_add(k as u64));
+                }
+                Ok(self.run()?)
+            })
+            .collect::<Result<Vec<_>, StochasticError>>()?;
+        let time = self.sample_times();
+        let summaries = self
             .variable_index
             .keys()
             .map(|id| {
-                let initial = self.initial_values.get(id).copied().unwrap_or_default();
-                (id.clone(), vec![initial as f32])
+                let runs = trajectories.iter().map(|sol| &sol.states[id]).collect_vec();
+                (id.clone(), summarize(&runs, time.len(), &data.quantiles))
             })
             .collect();
-        for t in 0..(self.duration as usize) {
-            self.problem.advance_until(t as f64);
-            time.push(self.problem.get_time() as f32);
+        Ok(StochasticEnsemble { time, trajectories, summaries })
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        if let Some(problem) = self.problem.as_mut() {
+            problem.seed(seed);
+        }
+        self.rng = SmallRng::seed_from_u64(seed);
+    }
+
+    /// Runs one simulation from the initial state.
+    fn run(&mut self) -> Result<ODESolution, IntegrationError> {
+        let initial = self
+            .variable_index
+            .keys()
+            .map(|id| self.initial_values.get(id).copied().unwrap_or_default())
+            .collect_vec();
+        if let Some(problem) = self.problem.as_mut() {
+            problem.set_species(initial.iter().map(|x| *x as isize).collect_vec());
+            problem.set_time(0.0);
+        }
+        let mut x = initial.into_iter().map(f64::from).collect_vec();
+
+        let time = self.sample_times();
+        let mut states: HashMap<_, _> = self
+            .variable_index
+            .keys()
+            .map(|id| (id.clone(), Vec::with_capacity(time.len())))
+            .collect();
+        let mut t_prev = 0.0;
+        for t in time.iter().map(|t| *t as f64) {
+            match self.method {
+                StochasticMethod::Exact => match self.problem.as_mut() {
+                    Some(problem) => {
+                        problem.advance_until(t);
+                        for (i, x) in x.iter_mut().enumerate() {
+                            *x = problem.get_species(i) as f64;
+                        }
+                    }
+                    None => {
+                        exact_steps(&self.reactions, &mut x, t_prev, t, usize::MAX, &mut self.rng);
+                    }
+                },
+                StochasticMethod::TauLeaping { epsilon } => {
+                    tau_leap(&self.reactions, &mut x, t_prev, t, epsilon as f64, &mut self.rng);
+                }
+                StochasticMethod::Hybrid { threshold } => {
+                    let partition = HybridPartition::new(&self.reactions, &x, threshold as f64);
+                    let fast_system = self.fast_system(&partition);
+                    let (reactions, rng) = (&self.reactions, &mut self.rng);
+                    hybrid_step(reactions, &partition, fast_system, &mut x, t_prev, t, rng)?;
+                }
+            }
             for (id, idx) in self.variable_index.iter() {
-                states.get_mut(id).unwrap().push(self.problem.get_species(*idx) as f32)
+                states.get_mut(id).unwrap().push(x[*idx] as f32)
             }
+            t_prev = t;
         }
-        ODESolution { time, states }
+        Ok(ODESolution {
+            time,
+            states,
+            events: Vec::new(),
+            stats: Default::default(),
+        })
+    }
+
+    /// Mass-action ODEs of the fast reactions in a hybrid partition, indexed
+    /// by variable.
+    fn fast_system(&self, partition: &HybridPartition) -> Vec<Polynomial<usize, f32, i8>> {
+        let fast_rate = |transition: &QualifiedName| {
+            let fast = self
+                .transitions
+                .iter()
+                .position(|t| t == transition)
+                .is_some_and(|j| partition.fast[j]);
+            if fast {
+                self.rates.get(transition).copied().unwrap_or_default()
+            } else {
+                0.0
+            }
+        };
+        let sys = self.mass_action.clone().extend_scalars(|poly| {
+            poly.eval(|param| match param {
+                FlowParameter::Balanced { transition } => fast_rate(transition),
+                _ => 0.0,
+            })
+        });
+        self.variable_index
+            .keys()
+            .map(|id| match sys.components.get(id) {
+                Some(poly) => poly.map_variables(|ob| self.variable_index[ob]).normalize(),
+                None => Polynomial::zero(),
+            })
+            .collect()
+    }
+
+    fn check_sample_interval(&self) -> Result<(), StochasticError> {
+        if self.sample_interval > 0.0 {
+            Ok(())
+        } else {
+            Err(StochasticError::SampleInterval(self.sample_interval))
+        }
+    }
+
+    /// Times at which the state is sampled, given a positive sample interval.
+    fn sample_times(&self) -> Vec<f32> {
+        let dt = self.sample_interval;
+        let n = (self.duration / dt).floor().max(0.0) as usize;
+        (0..=n).map(|i| i as f32 * dt).collect()
+    }
+}
+
+/// Summarizes the runs of a species across an ensemble.
+fn summarize(runs: &[&Vec<f32>], len: usize, quantiles: &[f32]) -> SpeciesSummary {
+    let n = runs.len() as f32;
+    let samples = (0..len).map(|i| runs.iter().map(|run| run[i]).collect_vec()).collect_vec();
+    let mean = samples.iter().map(|xs| xs.iter().sum::<f32>() / n).collect_vec();
+    let std_dev = std::iter::zip(&samples, &mean)
+        .map(|(xs, m)| {
+            if runs.len() < 2 {
+                return 0.0;
+            }
+            let ss: f32 = xs.iter().map(|x| (x - m).powi(2)).sum();
+            (ss / (n - 1.0)).sqrt()
+        })
+        .collect_vec();
+    let sorted = samples
+        .into_iter()
+        .map(|xs| xs.into_iter().sorted_by(f32::total_cmp).collect_vec())
+        .collect_vec();
+    let quantiles = quantiles
+        .iter()
+        .map(|q| QuantileBand {
+            quantile: *q,
+            values: sorted.iter().map(|xs| quantile(xs, *q)).collect(),
+        })
+        .collect();
+    let extinct = runs.iter().filter(|run| run.last().is_some_and(|x| *x == 0.0)).count();
+    let extinction_probability = if runs.is_empty() {
+        0.0
+    } else {
+        extinct as f32 / n
+    };
+    SpeciesSummary {
+        mean,
+        std_dev,
+        quantiles,
+        extinction_probability,
     }
 }
 
+/// Quantile of sorted data, interpolating linearly between order statistics.
+fn quantile(sorted: &[f32], q: f32) -> f32 {
+    let Some(last) = sorted.len().checked_sub(1) else {
+        return f32::NAN;
+    };
+    let h = q.clamp(0.0, 1.0) * last as f32;
+    let (lo, hi) = (h.floor() as usize, h.ceil() as usize);
+    sorted[lo] + (h - lo as f32) * (sorted[hi] - sorted[lo])
+}
+
 /// Stochastic mass-action analysis for Petri nets.
 pub struct PetriNetStochasticMassActionAnalysis {
     /// Object type for places.
@@ -106,6 +498,7 @@ impl PetriNetStochasticMassActionAnalysis {
             .map(|id| data.initial_values.get(id).copied().unwrap_or_default() as isize)
             .collect();
         let mut problem = gillespie::Gillespie::new(initial, false);
+        let (mut reactions, mut transitions) = (Vec::new(), Vec::new());
 
         for mor in model.mor_generators_with_type(&self.transition_mor_type) {
             let (inputs, outputs) = transition_interface(model, &mor);
@@ -131,19 +524,151 @@ impl PetriNetStochasticMassActionAnalysis {
                 .map(|(o, i)| o - (i as isize))
                 .collect();
             if let Some(rate) = data.rates.get(&mor) {
+                reactions.push(Reaction {
+                    reactants: input_vec.clone(),
+                    modifiers: vec![0; ob_generators.len()],
+                    changes: output_vec.clone(),
+                    rate: *rate as f64,
+                });
+                transitions.push(mor.clone());
                 problem.add_reaction(gillespie::Rate::lma(*rate as f64, input_vec), output_vec)
             }
         }
 
         let variable_index: IndexMap<_, _> =
             ob_generators.into_iter().enumerate().map(|(i, x)| (x, i)).collect();
+        let mass_action = PetriNetMassActionAnalysis {
+            place_ob_type: self.place_ob_type.clone(),
+            transition_mor_type: self.transition_mor_type.clone(),
+        }
+        .build_system(model, MassConservationType::Balanced);
 
-        StochasticMassActionAnalysis {
-            problem,
+        StochasticMassActionAnalysis::new(
+            Some(problem),
             variable_index,
-            initial_values: data.initial_values,
-            duration: data.duration,
+            reactions,
+            transitions,
+            mass_action,
+            data,
+        )
+    }
+}
+
+/// Stochastic mass-action analysis for stock-flow models.
+///
+/// Each flow is a jump process moving one unit from its source stock to its
+/// target stock, with propensity given by the mass-action rate of the flow:
+/// the rate coefficient times the source stock, times the source of each
+/// positive link to the flow and divided by the source of each negative link.
+/// Thus a compartmental model can be simulated as a continuous-time Markov
+/// chain.
+pub struct StockFlowStochasticMassActionAnalysis {
+    /// Object type for stocks.
+    pub stock_ob_type: TabObType,
+    /// Morphism type for flows between stocks.
+    pub flow_mor_type: TabMorType,
+    /// Morphism type for positive links from stocks to flows.
+    pub pos_link_mor_type: TabMorType,
+    /// Morphism type for negative links from stocks to flows.
+    pub neg_link_mor_type: TabMorType,
+}
+
+impl Default for StockFlowStochasticMassActionAnalysis {
+    fn default() -> Self {
+        let StockFlowMassActionAnalysis {
+            stock_ob_type,
+            flow_mor_type,
+            pos_link_mor_type,
+            neg_link_mor_type,
+        } = Default::default();
+        Self {
+            stock_ob_type,
+            flow_mor_type,
+            pos_link_mor_type,
+            neg_link_mor_type,
+        }
+    }
+}
+
+impl StockFlowStochasticMassActionAnalysis {
+    /// Creates a stochastic mass-action system.
+    ///
+    /// Fails if a flow or link is attached to an object that is not a stock.
+    pub fn build_stochastic_system(
+        &self,
+        model: &DiscreteTabModel,
+        data: StochasticMassActionProblemData,
+    ) -> Result<StochasticMassActionAnalysis, StochasticError> {
+        let analysis = StockFlowMassActionAnalysis {
+            stock_ob_type: self.stock_ob_type.clone(),
+            flow_mor_type: self.flow_mor_type.clone(),
+            pos_link_mor_type: self.pos_link_mor_type.clone(),
+            neg_link_mor_type: self.neg_link_mor_type.clone(),
+        };
+        let variable_index: IndexMap<_, _> = model
+            .ob_generators_with_type(&self.stock_ob_type)
+            .enumerate()
+            .map(|(i, x)| (x, i))
+            .collect();
+        let n = variable_index.len();
+        let index = |ob: QualifiedName| {
+            variable_index.get(&ob).copied().ok_or(StochasticError::NotAStock(ob))
+        };
+
+        let (mut reactions, mut transitions) = (Vec::new(), Vec::new());
+        let mut monomials = analysis.flow_monomials(model).into_iter().collect_vec();
+        monomials.sort_by(|(f, _), (g, _)| f.cmp(g));
+        for (flow, monomial) in monomials {
+            let Some(rate) = data.rates.get(&flow) else {
+                continue;
+            };
+            let dom = index(model.mor_generator_dom(&flow).unwrap_basic())?;
+            let cod = index(model.mor_generator_cod(&flow).unwrap_basic())?;
+
+            // The source stock is consumed, while the links only modulate the flow.
+            let mut reactants = vec![0; n];
+            reactants[dom] = 1;
+            let mut modifiers = vec![0; n];
+            for (stock, exponent) in monomial {
+                modifiers[index(stock)?] += exponent;
+            }
+            modifiers[dom] -= 1;
+            let mut changes = vec![0; n];
+            changes[dom] -= 1;
+            changes[cod] += 1;
+
+            reactions.push(Reaction {
+                reactants,
+                modifiers,
+                changes,
+                rate: *rate as f64,
+            });
+            transitions.push(flow);
         }
+
+        // Flows without links can be simulated by rebop.
+        let problem = reactions.iter().all(|r| r.modifiers.iter().all(|e| *e == 0)).then(|| {
+            let initial = variable_index
+                .keys()
+                .map(|id| data.initial_values.get(id).copied().unwrap_or_default() as isize)
+                .collect_vec();
+            let mut problem = gillespie::Gillespie::new(initial, false);
+            for r in reactions.iter() {
+                problem.add_reaction(gillespie::Rate::lma(r.rate, &r.reactants), &r.changes);
+            }
+            problem
+        });
+
+        let mass_action = analysis.build_system(model, MassConservationType::Balanced);
+
+        Ok(StochasticMassActionAnalysis::new(
+            problem,
+            variable_index,
+            reactions,
+            transitions,
+            mass_action,
+            data,
+        ))
     }
 }
 
@@ -152,7 +677,8 @@ mod tests {
     use std::rc::Rc;
 
     use super::*;
-    use crate::stdlib::sir_petri;
+    use crate::dbl::{model::MutDblModel, theory::DblTheory};
+    use crate::stdlib::models::*;
     use crate::stdlib::theories::*;
     use crate::zero::name;
 
@@ -168,10 +694,166 @@ mod tests {
                 (name("R"), 0),
             ]),
             duration: 10f32,
+            seed: None,
+            sample_interval: None,
+            method: Default::default(),
         };
         let sys =
             PetriNetStochasticMassActionAnalysis::default().build_stochastic_system(&model, data);
-        assert_eq!(2, sys.problem.nb_reactions());
-        assert_eq!(3, sys.problem.nb_species());
+        let problem = sys.problem.unwrap();
+        assert_eq!(2, problem.nb_reactions());
+        assert_eq!(3, problem.nb_species());
+    }
+
+    #[test]
+    fn sir_petri_stochastic_ensemble() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let data = || StochasticMassActionProblemData {
+            rates: HashMap::from_iter([(name("infect"), 0.02f32), (name("recover"), 0.5f32)]),
+            initial_values: HashMap::from_iter([(name("S"), 100), (name("I"), 2), (name("R"), 0)]),
+            duration: 20f32,
+            seed: Some(42),
+            sample_interval: Some(0.5),
+            method: Default::default(),
+        };
+        let ensemble = StochasticEnsembleData { runs: 50, quantiles: vec![0.0, 0.5, 1.0] };
+        let mut sys =
+            PetriNetStochasticMassActionAnalysis::default().build_stochastic_system(&model, data());
+        let result = sys.simulate_ensemble(&ensemble).unwrap();
+        assert_eq!(result.time.len(), 41);
+        assert_eq!(result.time.last(), Some(&20.0));
+        assert_eq!(result.trajectories.len(), 50);
+
+        // Reproducible from the seed, run by run.
+        let mut other =
+            PetriNetStochasticMassActionAnalysis::default().build_stochastic_system(&model, data());
+        let first = other.simulate().unwrap();
+        assert_eq!(first.states, result.trajectories[0].states);
+        assert_ne!(result.trajectories[0].states, result.trajectories[1].states);
+
+        // Summary statistics are consistent with each other.
+        let summary = &result.summaries[&name("S")];
+        assert!(summary.mean.iter().zip(&summary.std_dev).all(|(m, sd)| *m >= 0.0 && *sd >= 0.0));
+        assert_eq!(summary.mean[0], 100.0);
+        assert_eq!(summary.std_dev[0], 0.0);
+        let [min, median, max] = &summary.quantiles[..] else {
+            panic!("Should have three quantile bands");
+        };
+        for i in 0..result.time.len() {
+            assert!(min.values[i] <= median.values[i] && median.values[i] <= max.values[i]);
+        }
+
+        // The epidemic sometimes dies out early, but then the infected are extinct.
+        let infected = &result.summaries[&name("I")];
+        assert!(infected.extinction_probability > 0.0);
+        assert_eq!(result.summaries[&name("R")].extinction_probability, 0.0);
+
+        // An ensemble needs runs, and the sample interval must be positive.
+        let empty = StochasticEnsembleData { runs: 0, quantiles: Vec::new() };
+        assert!(matches!(sys.simulate_ensemble(&empty), Err(StochasticError::NoRuns)));
+        let data = StochasticMassActionProblemData { sample_interval: Some(0.0), ..data() };
+        let mut sys =
+            PetriNetStochasticMassActionAnalysis::default().build_stochastic_system(&model, data);
+        assert!(matches!(sys.simulate(), Err(StochasticError::SampleInterval(_))));
+        assert!(matches!(
+            sys.simulate_ensemble(&ensemble),
+            Err(StochasticError::SampleInterval(_))
+        ));
+    }
+
+    #[test]
+    fn sir_petri_approximate_methods() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let n = 1_000_000;
+        let data = |method| StochasticMassActionProblemData {
+            rates: HashMap::from_iter([(name("infect"), 3e-7f32), (name("recover"), 0.1f32)]),
+            initial_values: HashMap::from_iter([(name("S"), n), (name("I"), 10), (name("R"), 0)]),
+            duration: 150f32,
+            seed: Some(1),
+            sample_interval: Some(5.0),
+            method,
+        };
+        let methods = [
+            StochasticMethod::TauLeaping { epsilon: 0.03 },
+            StochasticMethod::Hybrid { threshold: 100.0 },
+        ];
+        for method in methods {
+            let solution = PetriNetStochasticMassActionAnalysis::default()
+                .build_stochastic_system(&model, data(method))
+                .simulate()
+                .unwrap();
+            let last = |id: &str| *solution.states[&name(id)].last().unwrap();
+            assert_eq!(solution.time.len(), 31);
+
+            // With basic reproduction number 3, most of the population is
+            // eventually infected.
+            assert!(last("R") > 0.9 * n as f32);
+            assert!(last("I") < 0.01 * n as f32);
+
+            // Total population is conserved, up to numerical error in the
+            // continuous species.
+            let total = last("S") + last("I") + last("R");
+            assert!((total - (n + 10) as f32).abs() < 10.0);
+        }
+    }
+
+    #[test]
+    fn stock_flow_stochastic_dynamics() {
+        let data = |method| StochasticMassActionProblemData {
+            rates: HashMap::from_iter([(name("f"), 0.01f32)]),
+            initial_values: HashMap::from_iter([(name("x"), 100), (name("y"), 1)]),
+            duration: 20f32,
+            seed: Some(7),
+            sample_interval: None,
+            method,
+        };
+        let analysis = StockFlowStochasticMassActionAnalysis::default();
+
+        // Infection flow `x -> y` reinforced by a positive link from `y`.
+        let model = backward_link(Rc::new(th_category_links()));
+        let mut sys = analysis.build_stochastic_system(&model, data(Default::default())).unwrap();
+        assert!(sys.problem.is_none());
+        assert_eq!(sys.transitions, vec![name("f")]);
+        let r = &sys.reactions[0];
+        let (x, y) = (sys.variable_index[&name("x")], sys.variable_index[&name("y")]);
+        assert_eq!((r.reactants[x], r.modifiers[x], r.changes[x]), (1, 0, -1));
+        assert_eq!((r.reactants[y], r.modifiers[y], r.changes[y]), (0, 1, 1));
+
+        let methods = [
+            StochasticMethod::Exact,
+            StochasticMethod::TauLeaping { epsilon: 0.03 },
+            StochasticMethod::Hybrid { threshold: 50.0 },
+        ];
+        for method in methods {
+            sys.method = method;
+            let solution = sys.simulate().unwrap();
+            assert_eq!(solution.time.len(), 21);
+            let (xs, ys) = (&solution.states[&name("x")], &solution.states[&name("y")]);
+            assert!(std::iter::zip(xs, ys).all(|(x, y)| (x + y - 101.0).abs() < 1e-3));
+            assert!(ys.windows(2).all(|w| w[0] <= w[1] + 1e-3));
+            assert!(*ys.last().unwrap() > 90.0);
+        }
+
+        // Flow damped by a negative link, with propensity `f x / y`.
+        let model = negative_backward_link(Rc::new(th_category_signed_links()));
+        let mut sys = analysis.build_stochastic_system(&model, data(Default::default())).unwrap();
+        assert!((sys.reactions[0].propensity(&[100.0, 4.0]) - 0.25).abs() < 1e-6);
+        let solution = sys.simulate().unwrap();
+        let last = |id: &str| *solution.states[&name(id)].last().unwrap();
+        assert_eq!(last("x") + last("y"), 101.0);
+        assert!(last("y") > 1.0 && last("y") < 100.0);
+
+        // Flow into an object that is not a stock.
+        let th = Rc::new(th_category_links());
+        let ob_type = TabObType::Basic(name("Object"));
+        let mut model = DiscreteTabModel::new(th.clone());
+        model.add_ob(name("x"), ob_type.clone());
+        model.add_mor(name("f"), name("x").into(), name("z").into(), th.hom_type(ob_type));
+        assert!(matches!(
+            analysis.build_stochastic_system(&model, data(Default::default())),
+            Err(StochasticError::NotAStock(ob)) if ob == name("z")
+        ));
     }
 }
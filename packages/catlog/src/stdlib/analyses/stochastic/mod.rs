@@ -1,5 +1,7 @@
 //! Stochastic analyses of models.
 
+pub mod approximate;
 pub mod mass_action;
 
+pub use approximate::*;
 pub use mass_action::*;
@@ -0,0 +1,272 @@
+//! Structural analyses of Petri nets.
+//!
+//! Structural properties depend only on the arcs of a net, not on its
+//! behavior from a particular marking, which makes them cheap to check even
+//! for nets with large or infinite state spaces:
+//!
+//! - A *P-invariant* is a weighting of the places whose weighted total of
+//!   tokens is preserved by every transition.
+//! - A *T-invariant* is a multiset of transitions whose firing returns any
+//!   marking to itself.
+//! - A *siphon* is a set of places such that every transition producing tokens
+//!   in the set also consumes tokens from it, so that an empty siphon stays
+//!   empty forever.
+//! - A *trap* is a set of places such that every transition consuming tokens
+//!   from the set also produces tokens in it, so that a marked trap stays
+//!   marked forever.
+//!
+//! By Commoner's theorem, a free-choice net is live if and only if every siphon
+//! contains a trap that is initially marked ([Desel & Esparza
+//! 1995](https://doi.org/10.1017/CBO9780511526558)).
+
+use std::collections::{BTreeSet, HashMap};
+
+use itertools::Itertools;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use crate::dbl::modal::model::ModalDblModel;
+use crate::dbl::theory::Unital;
+use crate::stdlib::analyses::petri::{PetriNet, semiflows};
+use crate::zero::QualifiedName;
+
+/// Data defining a structural analysis of a Petri net.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct StructuralAnalysisData {
+    /// Map from place IDs to number of initial tokens of that type, used to
+    /// check Commoner's condition.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tokens: HashMap<QualifiedName, u32>,
+}
+
+/// Results of a structural analysis of a Petri net.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct StructuralAnalysis {
+    /// Minimal P-invariants, as maps from place IDs to positive weights.
+    #[cfg_attr(feature = "serde", serde(rename = "pInvariants"))]
+    pub p_invariants: Vec<HashMap<QualifiedName, u32>>,
+
+    /// Minimal T-invariants, as maps from transition IDs to positive counts.
+    #[cfg_attr(feature = "serde", serde(rename = "tInvariants"))]
+    pub t_invariants: Vec<HashMap<QualifiedName, u32>>,
+
+    /// Minimal nonempty siphons, as sorted lists of place IDs.
+    pub siphons: Vec<Vec<QualifiedName>>,
+
+    /// Minimal nonempty traps, as sorted lists of place IDs.
+    pub traps: Vec<Vec<QualifiedName>>,
+
+    /// Whether the net is (extended) free-choice and ordinary, that is, any two
+    /// transitions sharing an input place have the same input places, and no
+    /// arc has weight more than one.
+    #[cfg_attr(feature = "serde", serde(rename = "freeChoice"))]
+    pub free_choice: bool,
+
+    /// Whether every siphon contains a trap marked by the initial tokens.
+    #[cfg_attr(feature = "serde", serde(rename = "commonerCondition"))]
+    pub commoner_condition: bool,
+
+    /// Whether the net is live from the initial tokens, as decided by
+    /// Commoner's theorem. Absent unless the net is free-choice.
+    pub live: Option<bool>,
+}
+
+/// Analyzes the structure of a Petri net.
+pub fn structural_analysis(
+    m: &ModalDblModel<Unital>,
+    data: StructuralAnalysisData,
+) -> StructuralAnalysis {
+    let net = PetriNet::new(m);
+    let incidence = net.incidence();
+    let transposed = (0..net.transitions.len())
+        .map(|t| incidence.iter().map(|row| row[t]).collect())
+        .collect_vec();
+    let weights = |ids: &[QualifiedName], y: Vec<u32>| {
+        std::iter::zip(ids, y)
+            .filter(|(_, c)| *c != 0)
+            .map(|(id, c)| (id.clone(), c))
+            .collect()
+    };
+    let p_invariants = semiflows(&incidence).into_iter().map(|y| weights(&net.places, y)).collect();
+    let t_invariants = semiflows(&transposed)
+        .into_iter()
+        .map(|y| weights(&net.transitions, y))
+        .collect();
+
+    let siphons = minimal_siphons(&net.pre, &net.post, net.places.len());
+    let traps = minimal_siphons(&net.post, &net.pre, net.places.len());
+
+    let marked = net.marking_support(&data.tokens);
+    let commoner_condition = siphons.iter().all(|siphon| {
+        let trap = maximal_trap(&net, siphon.clone());
+        !trap.is_disjoint(&marked)
+    });
+    let free_choice = is_free_choice(&net);
+
+    let names = |sets: Vec<BTreeSet<usize>>| {
+        sets.into_iter()
+            .map(|set| set.into_iter().map(|p| net.places[p].clone()).collect())
+            .collect()
+    };
+    StructuralAnalysis {
+        p_invariants,
+        t_invariants,
+        siphons: names(siphons),
+        traps: names(traps),
+        free_choice,
+        commoner_condition,
+        live: free_choice.then_some(commoner_condition),
+    }
+}
+
+impl PetriNet {
+    /// Places with at least one token.
+    fn marking_support(&self, tokens: &HashMap<QualifiedName, u32>) -> BTreeSet<usize> {
+        (0..self.places.len())
+            .filter(|p| tokens.get(&self.places[*p]).is_some_and(|x| *x > 0))
+            .collect()
+    }
+}
+
+/// Computes the minimal nonempty siphons of a net.
+///
+/// Swapping the inputs and outputs of the transitions computes the minimal
+/// traps instead. Siphons are enumerated by branching on the input places of a
+/// transition that violates the siphon condition, excluding the places already
+/// branched on so that each siphon is found at most once from each root.
+fn minimal_siphons(pre: &[Vec<u32>], post: &[Vec<u32>], n: usize) -> Vec<BTreeSet<usize>> {
+    fn search(
+        pre: &[Vec<u32>],
+        post: &[Vec<u32>],
+        set: BTreeSet<usize>,
+        mut excluded: BTreeSet<usize>,
+        found: &mut Vec<BTreeSet<usize>>,
+    ) {
+        // A transition that produces in the set without consuming from it.
+        let violation = std::iter::zip(pre, post).find(|(pre, post)| {
+            set.iter().any(|p| post[*p] > 0) && set.iter().all(|p| pre[*p] == 0)
+        });
+        let Some((inputs, _)) = violation else {
+            found.push(set);
+            return;
+        };
+        let candidates = (0..inputs.len()).filter(|p| inputs[*p] > 0 && !excluded.contains(p));
+        for p in candidates.collect_vec() {
+            let mut next = set.clone();
+            next.insert(p);
+            search(pre, post, next, excluded.clone(), found);
+            excluded.insert(p);
+        }
+    }
+
+    let mut found = Vec::new();
+    for p in 0..n {
+        search(pre, post, BTreeSet::from([p]), (0..p).collect(), &mut found);
+    }
+    found
+        .iter()
+        .filter(|s| !found.iter().any(|t| t.len() < s.len() && t.is_subset(s)))
+        .unique()
+        .cloned()
+        .sorted()
+        .collect()
+}
+
+/// Computes the largest trap contained in a set of places.
+fn maximal_trap(net: &PetriNet, mut set: BTreeSet<usize>) -> BTreeSet<usize> {
+    // Remove places consumed by a transition that produces nothing in the set.
+    while let Some(p) = set.iter().copied().find(|p| {
+        std::iter::zip(&net.pre, &net.post)
+            .any(|(pre, post)| pre[*p] > 0 && set.iter().all(|q| post[*q] == 0))
+    }) {
+        set.remove(&p);
+    }
+    set
+}
+
+/// Whether a net is ordinary and extended free-choice.
+fn is_free_choice(net: &PetriNet) -> bool {
+    let ordinary = net.pre.iter().chain(&net.post).flatten().all(|w| *w <= 1);
+    let inputs = net.pre.iter().map(|pre| pre.iter().map(|w| *w > 0).collect_vec()).collect_vec();
+    let shares_input = |s: &[bool], t: &[bool]| std::iter::zip(s, t).any(|(x, y)| *x && *y);
+    ordinary && inputs.iter().tuple_combinations().all(|(s, t)| !shares_input(s, t) || s == t)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::{model::*, theory::*};
+    use crate::stdlib::{sir_petri, th_sym_monoidal_category};
+    use crate::zero::name;
+
+    #[test]
+    fn sir_structure() {
+        let model = sir_petri(Rc::new(th_sym_monoidal_category()));
+        let data = StructuralAnalysisData {
+            tokens: HashMap::from_iter([(name("S"), 10), (name("I"), 1)]),
+        };
+        let result = structural_analysis(&model, data);
+        let total = HashMap::from_iter([(name("S"), 1), (name("I"), 1), (name("R"), 1)]);
+        assert_eq!(result.p_invariants, vec![total]);
+        assert!(result.t_invariants.is_empty());
+
+        // Nothing produces susceptibles, and infectives are only produced from
+        // infectives; recovered are never consumed.
+        assert_eq!(result.siphons, vec![vec![name("I")], vec![name("S")]]);
+        assert_eq!(result.traps, vec![vec![name("R")]]);
+
+        // Infection and recovery share an input place but not all of them.
+        assert!(!result.free_choice);
+        assert!(!result.commoner_condition);
+        assert_eq!(result.live, None);
+    }
+
+    #[test]
+    fn cycle_structure() {
+        // A token circulating between two places, with a choice at `p` of two
+        // ways to move to `q`.
+        let th = Rc::new(th_sym_monoidal_category());
+        let ob_type = ModalObType::new(name("Object"));
+        let mut model = ModalDblModel::new(th);
+        let (p, q) = (name("p"), name("q"));
+        model.add_ob(p.clone(), ob_type.clone());
+        model.add_ob(q.clone(), ob_type.clone());
+        let mor_type = ModalMorType::Zero(ob_type);
+        model.add_mor(name("f"), p.clone().into(), q.clone().into(), mor_type.clone());
+        model.add_mor(name("g"), p.clone().into(), q.clone().into(), mor_type.clone());
+        model.add_mor(name("h"), q.into(), p.into(), mor_type);
+
+        let analyze = |tokens| structural_analysis(&model, StructuralAnalysisData { tokens });
+        let result = analyze(HashMap::from_iter([(name("p"), 1)]));
+        let both = vec![vec![name("p"), name("q")]];
+        assert_eq!(result.p_invariants, vec![HashMap::from_iter([(name("p"), 1), (name("q"), 1)])]);
+        let invariants = [name("f"), name("g")]
+            .map(|t| HashMap::from_iter([(t, 1), (name("h"), 1)]))
+            .to_vec();
+        assert_eq!(result.t_invariants, invariants);
+        assert_eq!((&result.siphons, &result.traps), (&both, &both));
+        assert!(result.free_choice);
+        assert_eq!(result.live, Some(true));
+
+        // Without any tokens, nothing can fire.
+        let result = analyze(HashMap::new());
+        assert_eq!(result.live, Some(false));
+    }
+}
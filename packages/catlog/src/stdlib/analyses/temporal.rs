@@ -0,0 +1,955 @@
+//! Temporal-logic model checking of Petri nets.
+//!
+//! Properties in computation tree logic (CTL) or linear temporal logic (LTL)
+//! are checked over the reachability graph of a bounded Petri net, from its
+//! initial marking. Atomic propositions are linear constraints on the numbers
+//! of tokens at the places, or the proposition that no transition is enabled.
+//! Temporal operators range over infinite paths, so a path reaching a deadlock
+//! is considered to stay in the deadlocked marking forever.
+//!
+//! CTL is checked by the usual labeling algorithm. LTL is checked by searching
+//! the product of the reachability graph with a tableau for the negated
+//! property for a fair cycle. The tableau is exponential in the number of
+//! temporal operators, so LTL formulas are limited in size. For background,
+//! see the *Handbook of Model Checking* ([Clarke et al
+//! 2018](https://doi.org/10.1007/978-3-319-10575-8)).
+
+use std::collections::{HashMap, VecDeque};
+
+use itertools::Itertools;
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use crate::dbl::modal::model::ModalDblModel;
+use crate::dbl::theory::Unital;
+use crate::stdlib::analyses::petri::PetriNet;
+use crate::stdlib::analyses::reachability::ReachabilityGraph;
+use crate::zero::QualifiedName;
+
+/// Comparison in a linear constraint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum Comparison {
+    /// Less than or equal to.
+    Le,
+    /// Less than.
+    Lt,
+    /// Equal to.
+    Eq,
+    /// Greater than or equal to.
+    Ge,
+    /// Greater than.
+    Gt,
+}
+
+/// A linear constraint on the numbers of tokens at places.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct LinearConstraint {
+    /// Map from place IDs to coefficients. Places not in the map have
+    /// coefficient zero.
+    pub coefficients: HashMap<QualifiedName, i32>,
+
+    /// Comparison of the weighted total of tokens with the bound.
+    pub comparison: Comparison,
+
+    /// Bound on the weighted total of tokens.
+    pub bound: i32,
+}
+
+/// A formula of computation tree logic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "content"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum CtlFormula {
+    /// Always true.
+    True,
+    /// No transition is enabled.
+    Deadlock,
+    /// A linear constraint on the marking holds.
+    Atom(LinearConstraint),
+    /// Negation.
+    Not(Box<CtlFormula>),
+    /// Conjunction.
+    And(Vec<CtlFormula>),
+    /// Disjunction.
+    Or(Vec<CtlFormula>),
+    /// Holds at some next marking (EX).
+    ExistsNext(Box<CtlFormula>),
+    /// Holds at every next marking (AX).
+    AllNext(Box<CtlFormula>),
+    /// Holds eventually along some path (EF).
+    ExistsFinally(Box<CtlFormula>),
+    /// Holds eventually along every path (AF).
+    AllFinally(Box<CtlFormula>),
+    /// Holds always along some path (EG).
+    ExistsGlobally(Box<CtlFormula>),
+    /// Holds always along every path (AG).
+    AllGlobally(Box<CtlFormula>),
+    /// First holds until second holds, along some path (EU).
+    ExistsUntil(Box<CtlFormula>, Box<CtlFormula>),
+    /// First holds until second holds, along every path (AU).
+    AllUntil(Box<CtlFormula>, Box<CtlFormula>),
+}
+
+/// A formula of linear temporal logic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "content"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum LtlFormula {
+    /// Always true.
+    True,
+    /// No transition is enabled.
+    Deadlock,
+    /// A linear constraint on the marking holds.
+    Atom(LinearConstraint),
+    /// Negation.
+    Not(Box<LtlFormula>),
+    /// Conjunction.
+    And(Vec<LtlFormula>),
+    /// Disjunction.
+    Or(Vec<LtlFormula>),
+    /// Holds at the next marking (X).
+    Next(Box<LtlFormula>),
+    /// Holds eventually (F).
+    Finally(Box<LtlFormula>),
+    /// Holds always (G).
+    Globally(Box<LtlFormula>),
+    /// First holds until second holds (U).
+    Until(Box<LtlFormula>, Box<LtlFormula>),
+    /// Second holds until and including when first holds, if ever (R).
+    Release(Box<LtlFormula>, Box<LtlFormula>),
+}
+
+/// A temporal property of a Petri net.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "logic", content = "formula"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum TemporalProperty {
+    /// A CTL formula, required to hold at the initial marking.
+    Ctl(CtlFormula),
+    /// An LTL formula, required to hold along every path from the initial
+    /// marking.
+    Ltl(LtlFormula),
+}
+
+/// Data defining a model checking problem for a Petri net.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct ModelCheckingData {
+    /// Map from place IDs to number of initial tokens of that type.
+    pub tokens: HashMap<QualifiedName, u32>,
+
+    /// Property to check.
+    pub property: TemporalProperty,
+
+    /// Maximum number of markings in the reachability graph.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "maxStates", default = "default_max_states")
+    )]
+    pub max_states: usize,
+}
+
+#[cfg(feature = "serde")]
+fn default_max_states() -> usize {
+    10_000
+}
+
+/// A path through the markings of a Petri net.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct Trace {
+    /// Markings along the path, as maps from place IDs to numbers of tokens.
+    pub markings: Vec<HashMap<QualifiedName, u32>>,
+
+    /// IDs of the transitions fired from each marking to the next, absent when
+    /// the path stays in a deadlocked marking.
+    ///
+    /// For a finite path, there is one fewer transition than markings. For an
+    /// infinite path, the last transition leads back to the start of the loop.
+    pub transitions: Vec<Option<QualifiedName>>,
+
+    /// Index of the marking at which the path loops back, if it is infinite.
+    #[cfg_attr(feature = "serde", serde(rename = "loopStart"))]
+    pub loop_start: Option<usize>,
+}
+
+/// Result of checking a temporal property of a Petri net.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ModelCheckingResult {
+    /// Whether the property holds.
+    pub holds: bool,
+
+    /// A path from the initial marking on which the property fails, if it does.
+    ///
+    /// For CTL, the path demonstrates the failure of the outermost temporal
+    /// operator; for LTL, it is an infinite path violating the formula.
+    pub counterexample: Option<Trace>,
+
+    /// Number of reachable markings.
+    pub states: usize,
+}
+
+/// An error in checking a temporal property.
+#[derive(Debug, Error)]
+pub enum ModelCheckingError {
+    /// The reachability graph has more markings than allowed.
+    #[error("More than {0} markings are reachable; the net may be unbounded")]
+    TooManyStates(usize),
+
+    /// The LTL formula has too many temporal operators for the tableau.
+    #[error("Formula has {0} temporal operators, more than the supported {MAX_TEMPORAL}")]
+    FormulaTooLarge(usize),
+}
+
+/// Largest number of temporal operators in an LTL formula.
+const MAX_TEMPORAL: usize = 10;
+
+/// Checks a temporal property of a Petri net from an initial marking.
+pub fn model_check(
+    m: &ModalDblModel<Unital>,
+    data: ModelCheckingData,
+) -> Result<ModelCheckingResult, ModelCheckingError> {
+    let net = PetriNet::new(m);
+    let initial = net.marking(&data.tokens);
+    let (graph, deadlocks) = net.reachability_graph(initial, data.max_states);
+    if !graph.complete {
+        return Err(ModelCheckingError::TooManyStates(data.max_states));
+    }
+    let kripke = Kripke::new(&net.places, &graph, &deadlocks);
+
+    let counterexample = match &data.property {
+        TemporalProperty::Ctl(formula) => {
+            let holds = kripke.sat(formula)[0];
+            (!holds).then(|| kripke.counterexample(formula, 0))
+        }
+        TemporalProperty::Ltl(formula) => {
+            let tableau = Tableau::new(formula);
+            if tableau.temporal.len() > MAX_TEMPORAL {
+                return Err(ModelCheckingError::FormulaTooLarge(tableau.temporal.len()));
+            }
+            tableau.fair_path(&kripke)
+        }
+    };
+    Ok(ModelCheckingResult {
+        holds: counterexample.is_none(),
+        counterexample: counterexample.map(|path| kripke.trace(path)),
+        states: graph.markings.len(),
+    })
+}
+
+/// A path through the states of a Kripke structure, possibly looping back.
+struct Path {
+    states: Vec<usize>,
+    loop_start: Option<usize>,
+}
+
+impl Path {
+    fn finite(states: Vec<usize>) -> Self {
+        Self { states, loop_start: None }
+    }
+}
+
+/// The reachability graph of a Petri net as a Kripke structure, in which every
+/// deadlocked marking has a transition to itself.
+struct Kripke<'a> {
+    places: &'a [QualifiedName],
+    graph: &'a ReachabilityGraph,
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+    deadlock: Vec<bool>,
+}
+
+impl<'a> Kripke<'a> {
+    fn new(places: &'a [QualifiedName], graph: &'a ReachabilityGraph, deadlocks: &[usize]) -> Self {
+        let n = graph.markings.len();
+        let mut successors = vec![Vec::new(); n];
+        for edge in graph.edges.iter() {
+            successors[edge.source].push(edge.target);
+        }
+        let mut deadlock = vec![false; n];
+        for k in deadlocks {
+            deadlock[*k] = true;
+            successors[*k].push(*k);
+        }
+        let mut predecessors = vec![Vec::new(); n];
+        for (s, succ) in successors.iter().enumerate() {
+            succ.iter().for_each(|t| predecessors[*t].push(s));
+        }
+        Self {
+            places,
+            graph,
+            successors,
+            predecessors,
+            deadlock,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.successors.len()
+    }
+
+    fn holds(&self, constraint: &LinearConstraint, state: usize) -> bool {
+        let marking = &self.graph.markings[state];
+        let total: i64 = std::iter::zip(self.places, marking)
+            .map(|(place, x)| {
+                let c = constraint.coefficients.get(place).copied().unwrap_or_default();
+                i64::from(c) * i64::from(*x)
+            })
+            .sum();
+        let bound = i64::from(constraint.bound);
+        match constraint.comparison {
+            Comparison::Le => total <= bound,
+            Comparison::Lt => total < bound,
+            Comparison::Eq => total == bound,
+            Comparison::Ge => total >= bound,
+            Comparison::Gt => total > bound,
+        }
+    }
+
+    /// Computes the states satisfying a CTL formula.
+    fn sat(&self, formula: &CtlFormula) -> Vec<bool> {
+        let n = self.len();
+        let not = |xs: Vec<bool>| xs.into_iter().map(|x| !x).collect_vec();
+        match formula {
+            CtlFormula::True => vec![true; n],
+            CtlFormula::Deadlock => self.deadlock.clone(),
+            CtlFormula::Atom(c) => (0..n).map(|s| self.holds(c, s)).collect(),
+            CtlFormula::Not(f) => not(self.sat(f)),
+            CtlFormula::And(fs) => fs.iter().fold(vec![true; n], |acc, f| {
+                std::iter::zip(acc, self.sat(f)).map(|(x, y)| x && y).collect()
+            }),
+            CtlFormula::Or(fs) => fs.iter().fold(vec![false; n], |acc, f| {
+                std::iter::zip(acc, self.sat(f)).map(|(x, y)| x || y).collect()
+            }),
+            CtlFormula::ExistsNext(f) => {
+                let sat = self.sat(f);
+                self.successors.iter().map(|succ| succ.iter().any(|t| sat[*t])).collect()
+            }
+            CtlFormula::AllNext(f) => {
+                let sat = self.sat(f);
+                self.successors.iter().map(|succ| succ.iter().all(|t| sat[*t])).collect()
+            }
+            CtlFormula::ExistsFinally(f) => self.exists_until(&vec![true; n], self.sat(f)),
+            CtlFormula::AllFinally(f) => not(self.exists_globally(not(self.sat(f)))),
+            CtlFormula::ExistsGlobally(f) => self.exists_globally(self.sat(f)),
+            CtlFormula::AllGlobally(f) => not(self.exists_until(&vec![true; n], not(self.sat(f)))),
+            CtlFormula::ExistsUntil(f, g) => self.exists_until(&self.sat(f), self.sat(g)),
+            CtlFormula::AllUntil(f, g) => not(self.sat(&all_until_negation(f, g))),
+        }
+    }
+
+    /// States from which some path passes through `before` until reaching
+    /// `after`, by a least fixed point.
+    fn exists_until(&self, before: &[bool], mut after: Vec<bool>) -> Vec<bool> {
+        let mut queue = (0..self.len()).filter(|s| after[*s]).collect::<VecDeque<_>>();
+        while let Some(t) = queue.pop_front() {
+            for s in self.predecessors[t].iter().copied() {
+                if !after[s] && before[s] {
+                    after[s] = true;
+                    queue.push_back(s);
+                }
+            }
+        }
+        after
+    }
+
+    /// States from which some path stays in `within` forever, by a greatest
+    /// fixed point.
+    fn exists_globally(&self, mut within: Vec<bool>) -> Vec<bool> {
+        // Count the successors of each state remaining in the set, removing
+        // states that have none left.
+        let mut count = self
+            .successors
+            .iter()
+            .map(|succ| succ.iter().filter(|t| within[**t]).count())
+            .collect_vec();
+        let mut queue = (0..self.len()).filter(|s| within[*s] && count[*s] == 0).collect_vec();
+        queue.iter().for_each(|s| within[*s] = false);
+        while let Some(t) = queue.pop() {
+            for s in self.predecessors[t].iter().copied() {
+                count[s] -= 1;
+                if within[s] && count[s] == 0 {
+                    within[s] = false;
+                    queue.push(s);
+                }
+            }
+        }
+        within
+    }
+
+    /// Finds a path demonstrating that a CTL formula holds at a state.
+    fn witness(&self, formula: &CtlFormula, s: usize) -> Path {
+        let n = self.len();
+        match formula {
+            CtlFormula::Not(f) => self.counterexample(f, s),
+            CtlFormula::Or(fs) => match fs.iter().find(|f| self.sat(f)[s]) {
+                Some(f) => self.witness(f, s),
+                None => Path::finite(vec![s]),
+            },
+            CtlFormula::ExistsNext(f) => {
+                let sat = self.sat(f);
+                let t = self.successors[s].iter().copied().find(|t| sat[*t]);
+                Path::finite([Some(s), t].into_iter().flatten().collect())
+            }
+            CtlFormula::ExistsFinally(f) => self.path_to(s, &vec![true; n], &self.sat(f)),
+            CtlFormula::ExistsUntil(f, g) => self.path_to(s, &self.sat(f), &self.sat(g)),
+            CtlFormula::ExistsGlobally(f) => {
+                let within = self.exists_globally(self.sat(f));
+                let mut states = vec![s];
+                loop {
+                    let last = *states.last().unwrap();
+                    let Some(t) = self.successors[last].iter().copied().find(|t| within[*t]) else {
+                        return Path::finite(states);
+                    };
+                    if let Some(i) = states.iter().position(|u| *u == t) {
+                        return Path { states, loop_start: Some(i) };
+                    }
+                    states.push(t);
+                }
+            }
+            _ => Path::finite(vec![s]),
+        }
+    }
+
+    /// Finds a path demonstrating that a CTL formula fails at a state.
+    fn counterexample(&self, formula: &CtlFormula, s: usize) -> Path {
+        let not = |f: &CtlFormula| CtlFormula::Not(Box::new(f.clone()));
+        match formula {
+            CtlFormula::Not(f) => self.witness(f, s),
+            CtlFormula::And(fs) => match fs.iter().find(|f| !self.sat(f)[s]) {
+                Some(f) => self.counterexample(f, s),
+                None => Path::finite(vec![s]),
+            },
+            CtlFormula::AllNext(f) => self.witness(&CtlFormula::ExistsNext(not(f).into()), s),
+            CtlFormula::AllFinally(f) => {
+                self.witness(&CtlFormula::ExistsGlobally(not(f).into()), s)
+            }
+            CtlFormula::AllGlobally(f) => {
+                self.witness(&CtlFormula::ExistsFinally(not(f).into()), s)
+            }
+            CtlFormula::AllUntil(f, g) => self.witness(&all_until_negation(f, g), s),
+            _ => Path::finite(vec![s]),
+        }
+    }
+
+    /// Finds a shortest path from a state through `within` to `goal`.
+    fn path_to(&self, s: usize, within: &[bool], goal: &[bool]) -> Path {
+        let mut parent = vec![None; self.len()];
+        let mut visited = vec![false; self.len()];
+        let mut queue = VecDeque::from([s]);
+        visited[s] = true;
+        while let Some(u) = queue.pop_front() {
+            if goal[u] {
+                let mut states = vec![u];
+                while let Some(p) = parent[*states.last().unwrap()] {
+                    states.push(p);
+                }
+                states.reverse();
+                return Path::finite(states);
+            }
+            if !within[u] {
+                continue;
+            }
+            for t in self.successors[u].iter().copied() {
+                if !visited[t] {
+                    visited[t] = true;
+                    parent[t] = Some(u);
+                    queue.push_back(t);
+                }
+            }
+        }
+        Path::finite(vec![s])
+    }
+
+    /// Converts a path of states into a trace of markings and transitions.
+    fn trace(&self, path: Path) -> Trace {
+        let markings = path
+            .states
+            .iter()
+            .map(|s| {
+                let marking = &self.graph.markings[*s];
+                std::iter::zip(self.places, marking).map(|(p, x)| (p.clone(), *x)).collect()
+            })
+            .collect();
+        let closing = path.loop_start.map(|i| (*path.states.last().unwrap(), path.states[i]));
+        let transitions = path
+            .states
+            .iter()
+            .copied()
+            .tuple_windows()
+            .chain(closing)
+            .map(|(s, t)| {
+                let edge = self.graph.edges.iter().find(|e| e.source == s && e.target == t);
+                edge.map(|e| e.transition.clone())
+            })
+            .collect();
+        Trace {
+            markings,
+            transitions,
+            loop_start: path.loop_start,
+        }
+    }
+}
+
+/// The negation of `A[f U g]`, which is `E[¬g U (¬f ∧ ¬g)] ∨ EG ¬g`.
+fn all_until_negation(f: &CtlFormula, g: &CtlFormula) -> CtlFormula {
+    let not_f = CtlFormula::Not(Box::new(f.clone()));
+    let not_g = CtlFormula::Not(Box::new(g.clone()));
+    let neither = CtlFormula::And(vec![not_f, not_g.clone()]);
+    CtlFormula::Or(vec![
+        CtlFormula::ExistsUntil(Box::new(not_g.clone()), Box::new(neither)),
+        CtlFormula::ExistsGlobally(Box::new(not_g)),
+    ])
+}
+
+/// A node of an LTL formula in negation normal form.
+enum Node {
+    Const(bool),
+    Deadlock(bool),
+    Atom(LinearConstraint, bool),
+    And(Vec<usize>),
+    Or(Vec<usize>),
+    Next(usize),
+    Until(usize, usize),
+    Release(usize, usize),
+}
+
+/// Tableau for the negation of an LTL formula.
+///
+/// A state of the tableau assigns a truth value to each temporal subformula
+/// at the next step, encoded as the bits of an integer.
+struct Tableau {
+    nodes: Vec<Node>,
+    root: usize,
+    /// Indices of the temporal nodes, indexing the bits of tableau states.
+    temporal: Vec<usize>,
+}
+
+impl Tableau {
+    fn new(formula: &LtlFormula) -> Self {
+        let mut tableau = Self {
+            nodes: Vec::new(),
+            root: 0,
+            temporal: Vec::new(),
+        };
+        tableau.root = tableau.build(formula, true);
+        tableau
+    }
+
+    /// Adds a formula, negated if required, in negation normal form.
+    fn build(&mut self, formula: &LtlFormula, negated: bool) -> usize {
+        let node = match formula {
+            LtlFormula::True => Node::Const(!negated),
+            LtlFormula::Deadlock => Node::Deadlock(negated),
+            LtlFormula::Atom(c) => Node::Atom(c.clone(), negated),
+            LtlFormula::Not(f) => return self.build(f, !negated),
+            LtlFormula::And(fs) | LtlFormula::Or(fs) => {
+                let args = fs.iter().map(|f| self.build(f, negated)).collect();
+                if matches!(formula, LtlFormula::And(_)) != negated {
+                    Node::And(args)
+                } else {
+                    Node::Or(args)
+                }
+            }
+            LtlFormula::Next(f) => Node::Next(self.build(f, negated)),
+            LtlFormula::Finally(f) => {
+                let (t, f) = (self.constant(!negated), self.build(f, negated));
+                if negated {
+                    Node::Release(t, f)
+                } else {
+                    Node::Until(t, f)
+                }
+            }
+            LtlFormula::Globally(f) => {
+                let (t, f) = (self.constant(negated), self.build(f, negated));
+                if negated {
+                    Node::Until(t, f)
+                } else {
+                    Node::Release(t, f)
+                }
+            }
+            LtlFormula::Until(f, g) | LtlFormula::Release(f, g) => {
+                let (f, g) = (self.build(f, negated), self.build(g, negated));
+                if matches!(formula, LtlFormula::Until(..)) != negated {
+                    Node::Until(f, g)
+                } else {
+                    Node::Release(f, g)
+                }
+            }
+        };
+        let temporal = matches!(node, Node::Next(_) | Node::Until(..) | Node::Release(..));
+        self.nodes.push(node);
+        let index = self.nodes.len() - 1;
+        if temporal {
+            self.temporal.push(index);
+        }
+        index
+    }
+
+    fn constant(&mut self, value: bool) -> usize {
+        self.nodes.push(Node::Const(value));
+        self.nodes.len() - 1
+    }
+
+    /// Bit of the tableau state for a temporal node.
+    fn bit(&self, node: usize) -> u32 {
+        1 << self.temporal.iter().position(|k| *k == node).unwrap()
+    }
+
+    /// Evaluates a node at a state of the Kripke structure and the tableau.
+    fn eval(&self, kripke: &Kripke, node: usize, s: usize, next: u32) -> bool {
+        let eval = |k: usize| self.eval(kripke, k, s, next);
+        match &self.nodes[node] {
+            Node::Const(value) => *value,
+            Node::Deadlock(negated) => kripke.deadlock[s] != *negated,
+            Node::Atom(c, negated) => kripke.holds(c, s) != *negated,
+            Node::And(args) => args.iter().all(|k| eval(*k)),
+            Node::Or(args) => args.iter().any(|k| eval(*k)),
+            Node::Next(_) => next & self.bit(node) != 0,
+            Node::Until(f, g) => eval(*g) || (eval(*f) && next & self.bit(node) != 0),
+            Node::Release(f, g) => eval(*g) && (eval(*f) || next & self.bit(node) != 0),
+        }
+    }
+
+    /// Whether the tableau may move between two product states, meaning that
+    /// the temporal nodes hold at the second state as promised at the first.
+    fn consistent(&self, kripke: &Kripke, next: u32, (t, after): (usize, u32)) -> bool {
+        self.temporal.iter().all(|k| {
+            let promised = next & self.bit(*k) != 0;
+            let target = match self.nodes[*k] {
+                Node::Next(f) => f,
+                _ => *k,
+            };
+            promised == self.eval(kripke, target, t, after)
+        })
+    }
+
+    /// Whether a product state fulfills the eventuality of an until node.
+    fn fulfills(&self, kripke: &Kripke, node: usize, (s, next): (usize, u32)) -> bool {
+        let Node::Until(_, g) = self.nodes[node] else {
+            return true;
+        };
+        !self.eval(kripke, node, s, next) || self.eval(kripke, g, s, next)
+    }
+
+    /// Finds an infinite path satisfying the (negated) formula, if any.
+    fn fair_path(&self, kripke: &Kripke) -> Option<Path> {
+        // Explore the product breadth-first from the initial states.
+        let labels = 0..(1u32 << self.temporal.len());
+        let mut index = HashMap::new();
+        let mut states = Vec::new();
+        let mut parent = Vec::new();
+        let mut successors: Vec<Vec<usize>> = Vec::new();
+        for next in labels.clone().filter(|next| self.eval(kripke, self.root, 0, *next)) {
+            index.insert((0, next), states.len());
+            states.push((0, next));
+            parent.push(None);
+        }
+        let mut k = 0;
+        while k < states.len() {
+            let (s, next) = states[k];
+            let mut succ = Vec::new();
+            for t in kripke.successors[s].iter().copied() {
+                for after in labels.clone() {
+                    if !self.consistent(kripke, next, (t, after)) {
+                        continue;
+                    }
+                    let j = *index.entry((t, after)).or_insert_with(|| {
+                        states.push((t, after));
+                        parent.push(Some(k));
+                        states.len() - 1
+                    });
+                    succ.push(j);
+                }
+            }
+            successors.push(succ);
+            k += 1;
+        }
+
+        // Find a strongly connected component with a cycle fulfilling every
+        // eventuality, and a lasso through it.
+        let untils = self
+            .temporal
+            .iter()
+            .copied()
+            .filter(|k| matches!(self.nodes[*k], Node::Until(..)))
+            .collect_vec();
+        for component in strongly_connected_components(&successors) {
+            let mut inside = vec![false; states.len()];
+            component.iter().for_each(|u| inside[*u] = true);
+            let entry = component[0];
+            let cyclic = component.len() > 1 || successors[entry].contains(&entry);
+            let goals = untils
+                .iter()
+                .map(|node| {
+                    component.iter().copied().find(|u| self.fulfills(kripke, *node, states[*u]))
+                })
+                .collect::<Option<Vec<_>>>();
+            let Some(goals) = goals.filter(|_| cyclic) else {
+                continue;
+            };
+
+            let mut prefix = vec![entry];
+            while let Some(p) = parent[*prefix.last().unwrap()] {
+                prefix.push(p);
+            }
+            prefix.reverse();
+            let mut cycle = vec![entry];
+            for goal in goals.into_iter().chain([entry]) {
+                let from = *cycle.last().unwrap();
+                let path = path_within(&successors, &inside, from, goal);
+                cycle.extend(path.into_iter().skip(1));
+            }
+            cycle.pop();
+
+            let loop_start = prefix.len() - 1;
+            let path = prefix.into_iter().chain(cycle.into_iter().skip(1));
+            return Some(Path {
+                states: path.map(|u| states[u].0).collect(),
+                loop_start: Some(loop_start),
+            });
+        }
+        None
+    }
+}
+
+/// Finds a shortest path of at least one step between two states of a graph,
+/// staying within a set of states.
+fn path_within(successors: &[Vec<usize>], within: &[bool], from: usize, to: usize) -> Vec<usize> {
+    let mut parent = vec![None; successors.len()];
+    let mut queue = VecDeque::new();
+    for t in successors[from].iter().copied().filter(|t| within[*t]) {
+        if parent[t].is_none() {
+            parent[t] = Some(from);
+            queue.push_back(t);
+        }
+    }
+    while let Some(u) = queue.pop_front() {
+        if u == to {
+            let mut path = vec![to];
+            let mut v = to;
+            while let Some(p) = parent[v] {
+                path.push(p);
+                if p == from {
+                    break;
+                }
+                v = p;
+            }
+            path.reverse();
+            return path;
+        }
+        for t in successors[u].iter().copied().filter(|t| within[*t]) {
+            if parent[t].is_none() {
+                parent[t] = Some(u);
+                queue.push_back(t);
+            }
+        }
+    }
+    vec![from, to]
+}
+
+/// Computes the strongly connected components of a graph by Tarjan's
+/// algorithm, without recursion.
+pub(crate) fn strongly_connected_components(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let n = successors.len();
+    let mut index = vec![usize::MAX; n];
+    let mut lowlink = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let (mut stack, mut components, mut counter) = (Vec::new(), Vec::new(), 0);
+    for root in 0..n {
+        if index[root] != usize::MAX {
+            continue;
+        }
+        let mut work = vec![(root, 0)];
+        while let Some((v, i)) = work.pop() {
+            if i == 0 {
+                index[v] = counter;
+                lowlink[v] = counter;
+                counter += 1;
+                stack.push(v);
+                on_stack[v] = true;
+            }
+            if let Some(&w) = successors[v].get(i) {
+                work.push((v, i + 1));
+                if index[w] == usize::MAX {
+                    work.push((w, 0));
+                } else if on_stack[w] {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                }
+                continue;
+            }
+            if lowlink[v] == index[v] {
+                let mut component = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                component.reverse();
+                components.push(component);
+            }
+            if let Some(&(u, _)) = work.last() {
+                lowlink[u] = lowlink[u].min(lowlink[v]);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::{model::*, theory::*};
+    use crate::stdlib::{sir_petri, th_sym_monoidal_category};
+    use crate::zero::name;
+
+    fn constraint(terms: &[(&str, i32)], comparison: Comparison, bound: i32) -> LinearConstraint {
+        LinearConstraint {
+            coefficients: terms.iter().map(|(x, c)| (name(*x), *c)).collect(),
+            comparison,
+            bound,
+        }
+    }
+
+    #[test]
+    fn sir_ctl() {
+        let model = sir_petri(Rc::new(th_sym_monoidal_category()));
+        let check = |formula| {
+            let data = ModelCheckingData {
+                tokens: HashMap::from_iter([(name("S"), 3), (name("I"), 1)]),
+                property: TemporalProperty::Ctl(formula),
+                max_states: 100,
+            };
+            model_check(&model, data).unwrap()
+        };
+        let atom = |terms: &[_], comparison, bound| {
+            Box::new(CtlFormula::Atom(constraint(terms, comparison, bound)))
+        };
+
+        // The total population is conserved.
+        let total = atom(&[("S", 1), ("I", 1), ("R", 1)], Comparison::Eq, 4);
+        let result = check(CtlFormula::AllGlobally(total));
+        assert!(result.holds);
+        assert_eq!(result.counterexample, None);
+
+        // The epidemic can grow to infect more than two, as shown by a path
+        // of infections.
+        let result = check(CtlFormula::AllGlobally(atom(&[("I", 1)], Comparison::Le, 2)));
+        assert!(!result.holds);
+        let trace = result.counterexample.unwrap();
+        assert_eq!(trace.markings.first().unwrap()[&name("I")], 1);
+        assert_eq!(trace.markings.last().unwrap()[&name("I")], 3);
+        assert_eq!(trace.transitions, vec![Some(name("infect")); 2]);
+        assert_eq!(trace.loop_start, None);
+
+        // Every path ends when nobody is infected.
+        let deadlock = Box::new(CtlFormula::Deadlock);
+        assert!(check(CtlFormula::AllFinally(deadlock.clone())).holds);
+        let healthy = atom(&[("I", 1)], Comparison::Eq, 0);
+        let formula = CtlFormula::AllGlobally(Box::new(CtlFormula::Or(vec![
+            CtlFormula::Not(deadlock),
+            *healthy,
+        ])));
+        assert!(check(formula).holds);
+
+        // Not every path passes through a state with three infected.
+        let three = atom(&[("I", 1)], Comparison::Eq, 3);
+        assert!(check(CtlFormula::ExistsFinally(three.clone())).holds);
+        let result = check(CtlFormula::AllFinally(three));
+        assert!(!result.holds);
+        let trace = result.counterexample.unwrap();
+        assert!(trace.loop_start.is_some());
+        assert_eq!(trace.transitions.last().unwrap(), &None);
+    }
+
+    #[test]
+    fn sir_ltl() {
+        let model = sir_petri(Rc::new(th_sym_monoidal_category()));
+        let check = |formula| {
+            let data = ModelCheckingData {
+                tokens: HashMap::from_iter([(name("S"), 3), (name("I"), 1)]),
+                property: TemporalProperty::Ltl(formula),
+                max_states: 100,
+            };
+            model_check(&model, data).unwrap()
+        };
+        let atom = |terms: &[_], comparison, bound| {
+            Box::new(LtlFormula::Atom(constraint(terms, comparison, bound)))
+        };
+
+        // The epidemic eventually dies out.
+        let none = atom(&[("I", 1)], Comparison::Eq, 0);
+        assert!(check(LtlFormula::Finally(Box::new(LtlFormula::Globally(none.clone())))).holds);
+
+        // Infection and recovery alternate until the end.
+        let formula = LtlFormula::Until(atom(&[("I", 1)], Comparison::Ge, 1), none);
+        assert!(check(formula).holds);
+
+        // So there is not always someone infected.
+        let result = check(LtlFormula::Globally(atom(&[("I", 1)], Comparison::Ge, 1)));
+        assert!(!result.holds);
+        let trace = result.counterexample.unwrap();
+        let start = trace.loop_start.unwrap();
+        assert!(trace.markings[start..].iter().all(|m| m[&name("I")] == 0));
+        assert_eq!(trace.transitions.len(), trace.markings.len());
+        assert_eq!(trace.transitions.last().unwrap(), &None);
+    }
+
+    #[test]
+    fn unbounded_model_checking() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let (ob_type, op) = (ModalObType::new(name("Object")), name("tensor"));
+        let mut model = ModalDblModel::new(th);
+        model.add_ob(name("p"), ob_type.clone());
+        let list = |obs: Vec<QualifiedName>| {
+            let obs = obs.into_iter().map(ModalOb::from).collect();
+            ModalOb::App(ModalOb::List(List::Symmetric, obs).into(), op.clone())
+        };
+        let mor_type = ModalMorType::Zero(ob_type);
+        model.add_mor(name("t"), list(vec![]), list(vec![name("p")]), mor_type);
+
+        let data = ModelCheckingData {
+            tokens: HashMap::new(),
+            property: TemporalProperty::Ctl(CtlFormula::True),
+            max_states: 20,
+        };
+        let result = model_check(&model, data);
+        assert!(matches!(result, Err(ModelCheckingError::TooManyStates(20))));
+    }
+}
@@ -31,6 +31,25 @@ pub fn th_schema() -> DiscreteDblTheory {
     cat.into()
 }
 
+/// The theory of database schemas with nullable mappings and attributes.
+///
+/// Extends the [theory of schemas](th_schema) with a morphism type `Nullable` of
+/// mappings that need not be defined everywhere, together with its composite
+/// `NullableAttr` with attributes. As in the theory of [nullable signed
+/// categories](th_nullable_signed_category), nullability is absorbing: any
+/// composite involving a nullable morphism is nullable.
+pub fn th_nullable_schema() -> DiscreteDblTheory {
+    let mut cat = FpCategory::new();
+    cat.add_ob_generator(name("Entity"));
+    cat.add_ob_generator(name("AttrType"));
+    cat.add_mor_generator(name("Attr"), name("Entity"), name("AttrType"));
+    cat.add_mor_generator(name("Nullable"), name("Entity"), name("Entity"));
+    cat.add_mor_generator(name("NullableAttr"), name("Entity"), name("AttrType"));
+    cat.equate(Path::pair(name("Nullable"), name("Nullable")), name("Nullable").into());
+    cat.equate(Path::pair(name("Nullable"), name("Attr")), name("NullableAttr").into());
+    cat.into()
+}
+
 /// The theory of signed categories.
 ///
 /// A *signed category* is a category sliced over the group of (nonzero) signs. Free
@@ -358,6 +377,7 @@ mod tests {
         assert!(th_empty().validate().is_ok());
         assert!(th_category().validate().is_ok());
         assert!(th_schema().validate().is_ok());
+        assert!(th_nullable_schema().validate().is_ok());
         assert!(th_signed_category().validate().is_ok());
         assert!(th_delayable_signed_category().validate().is_ok());
         assert!(th_nullable_signed_category().validate().is_ok());
@@ -1,11 +1,11 @@
 //! Commutative algebra and polynomials.
 
 use itertools::Itertools;
-use num_traits::{One, Pow, Zero, one, zero};
+use num_traits::{One, Pow, ToPrimitive, Zero, one, zero};
 use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::iter::{Product, Sum};
-use std::ops::{Add, AddAssign, Mul, Neg};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
 
 use derivative::Derivative;
 
@@ -67,6 +67,11 @@ where
         self.0.variables()
     }
 
+    /// Iterates over the coefficients in the polynomial.
+    pub fn coefficients(&self) -> impl Iterator<Item = &Coef> {
+        (&self.0).into_iter().map(|(coef, _)| coef)
+    }
+
     /// Maps the coefficients of the polynomial.
     ///
     /// In the usual situations when the coefficients from commutative rigs and the
@@ -90,6 +95,22 @@ where
         self.0.eval_with_order(self.monomials().map(|m| m.eval(f.clone())))
     }
 
+    /// Evaluates the polynomial after mapping the coefficients into the values.
+    ///
+    /// This is useful when the coefficients are not themselves values, such as
+    /// when evaluating a polynomial with `f32` coefficients in `f64`.
+    pub fn eval_with_scalars<A, F, G>(&self, f: F, mut g: G) -> A
+    where
+        A: Clone + Mul<Output = A> + Pow<Exp, Output = A> + Sum + Product,
+        F: Clone + FnMut(&Var) -> A,
+        G: FnMut(&Coef) -> A,
+        Exp: Clone,
+    {
+        std::iter::zip(self.coefficients(), self.monomials())
+            .map(|(coef, m)| g(coef) * m.eval(f.clone()))
+            .sum()
+    }
+
     /// Evaluates the polynomial on a sequence of variable-value pairs.
     ///
     /// This is a convenient way to evaluate the polynomial at a single point but it
@@ -124,6 +145,42 @@ where
             .collect()
     }
 
+    /// Computes the partial derivative of the polynomial with respect to a
+    /// variable.
+    ///
+    /// The exponents are assumed to be integers, possibly negative as in Laurent
+    /// polynomials. The result is in normal form provided that the input is.
+    pub fn partial_derivative(&self, var: &Var) -> Self
+    where
+        Var: Clone,
+        Coef: Clone + Zero + One + Neg<Output = Coef> + Mul<Output = Coef>,
+        Exp: Clone + Zero + One + Sub<Output = Exp> + ToPrimitive,
+    {
+        (&self.0)
+            .into_iter()
+            .filter_map(|(coef, m)| {
+                let exp = m.clone().into_iter().find(|(v, _)| v == var)?.1;
+                let coef = coef.clone() * int_to_ring(exp.to_i64()?);
+                let exp = exp - Exp::one();
+                let m: Monomial<_, _> = m
+                    .clone()
+                    .into_iter()
+                    .filter_map(|(v, e)| {
+                        if v != *var {
+                            Some((v, e))
+                        } else if exp.is_zero() {
+                            None
+                        } else {
+                            Some((v, exp.clone()))
+                        }
+                    })
+                    .collect();
+                Some((coef, m))
+            })
+            .collect::<Self>()
+            .normalize()
+    }
+
     /// Puts the polynomial into normal form.
     ///
     /// The data structure for polynomials is already pretty close to being a normal
@@ -147,6 +204,12 @@ where
     }
 }
 
+/// Image of an integer under the unique ring homomorphism from the integers.
+fn int_to_ring<R: Clone + Zero + One + Neg<Output = R>>(n: i64) -> R {
+    let unit = if n < 0 { R::one().neg() } else { R::one() };
+    std::iter::repeat_n(unit, n.unsigned_abs() as usize).fold(R::zero(), |acc, x| acc + x)
+}
+
 impl<Var, Coef, Exp> Polynomial<Var, Coef, Exp>
 where
     Var: Display,
@@ -186,6 +249,36 @@ where
     }
 }
 
+impl<Var, InnerVar, Coef, Exp> Polynomial<Var, Polynomial<InnerVar, Coef, Exp>, Exp>
+where
+    Var: Clone + Ord,
+    InnerVar: Clone + Ord,
+    Exp: Ord,
+{
+    /// Flattens a polynomial whose coefficients are themselves polynomials.
+    ///
+    /// The result is a polynomial in both the outer and the inner variables,
+    /// which are mapped into a common type of variables.
+    pub fn flatten<NewVar, F, G>(&self, mut f: F, mut g: G) -> Polynomial<NewVar, Coef, Exp>
+    where
+        NewVar: Ord,
+        Coef: Clone + Add<Output = Coef>,
+        Exp: Clone + Add<Output = Exp>,
+        F: FnMut(&Var) -> NewVar,
+        G: FnMut(&InnerVar) -> NewVar,
+    {
+        let mut result = Polynomial::default();
+        for (inner, m) in &self.0 {
+            for (coef, n) in &inner.0 {
+                let outer = m.clone().into_iter().map(|(var, exp)| (f(&var), exp));
+                let inner = n.clone().into_iter().map(|(var, exp)| (g(&var), exp));
+                result += (coef.clone(), outer.chain(inner).collect());
+            }
+        }
+        result
+    }
+}
+
 impl<Var, Coef, Exp> FromIterator<(Coef, Monomial<Var, Exp>)> for Polynomial<Var, Coef, Exp>
 where
     Var: Ord,
@@ -479,6 +572,8 @@ mod tests {
         assert_eq!(p.eval_pairs([('x', 1), ('y', 1)]), 5);
         assert_eq!(p.eval_pairs([('x', 1), ('y', 2)]), 16);
         assert_eq!(p.eval_pairs([('y', 1), ('x', 2)]), 14);
+        let value: i64 = p.eval_with_scalars(|v| if *v == 'x' { 2 } else { 1 }, |c| *c as i64);
+        assert_eq!(value, 14);
 
         let p = (x() + y()) * (x() + y());
         assert_eq!(p.to_string(), "2 x y + x^2 + y^2");
@@ -486,4 +581,29 @@ mod tests {
         let p = (x() + y()) * (x() + y().neg());
         assert_eq!(p.normalize().to_string(), "x^2 - y^2");
     }
+
+    #[test]
+    fn partial_derivatives() {
+        let x = || Polynomial::<_, i32, u32>::generator('x');
+        let y = || Polynomial::<_, i32, u32>::generator('y');
+        let p = x() * y() * x() * 2 + y() * 3 + x();
+        assert_eq!(p.partial_derivative(&'x').to_string(), "1 + 4 x y");
+        assert_eq!(p.partial_derivative(&'y').eval_pairs([('x', 2)]), 11);
+        assert_eq!(p.partial_derivative(&'z'), Polynomial::zero());
+
+        let x = Polynomial::<_, f32, i8>::generator('x');
+        let p: Polynomial<_, f32, i8> =
+            [(2.0, [('x', -1), ('y', 1)].into_iter().collect())].into_iter().collect();
+        assert_eq!(p.partial_derivative(&'x').to_string(), "-2 x^{-2} y");
+        assert_eq!(x.partial_derivative(&'x').to_string(), "1");
+    }
+
+    #[test]
+    fn flatten() {
+        let a = Polynomial::<_, f32, u8>::generator('a');
+        let x = Polynomial::<_, Polynomial<_, f32, u8>, u8>::generator('x');
+        let p = x.clone() * x * (a.clone() * a + 2.0);
+        let flat = p.flatten(|v| v.to_string(), |v| v.to_uppercase().to_string());
+        assert_eq!(flat.to_string(), "A^2 x^2 + 2 x^2");
+    }
 }